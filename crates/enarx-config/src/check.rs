@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Semantic validation of a [`Config`]
+
+use super::{Config, ConnectFile, File, ListenFile};
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+
+/// Severity of a [`Diagnostic`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The configuration will not work as intended
+    Error,
+
+    /// The configuration is valid, but likely not what was intended
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Configuration key a [`Diagnostic`] refers to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// The `steward` key
+    Steward,
+
+    /// The `files` array as a whole
+    Files,
+
+    /// The file descriptor at the given index of the `files` array
+    File(usize),
+
+    /// The `env` table
+    Env,
+
+    /// The `keep` table
+    Keep,
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Steward => write!(f, "steward"),
+            Self::Files => write!(f, "files"),
+            Self::File(i) => write!(f, "files[{i}]"),
+            Self::Env => write!(f, "env"),
+            Self::Keep => write!(f, "keep"),
+        }
+    }
+}
+
+impl Serialize for Key {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A semantic problem found in a [`Config`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Severity of the problem
+    pub severity: Severity,
+
+    /// Configuration key the problem was found at
+    pub key: Key,
+
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(key: Key, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            key,
+            message: message.into(),
+        }
+    }
+
+    fn warning(key: Key, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            key,
+            message: message.into(),
+        }
+    }
+}
+
+impl Config {
+    /// Check the configuration for semantic problems, which are not caught by deserialization,
+    /// but would only surface once the Keep is started.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::{Config, Severity};
+    /// const CONFIG: &str = r#"
+    /// steward = "http://attest.example.com"
+    /// "#;
+    ///
+    /// let config: Config = toml::from_str(CONFIG).unwrap();
+    /// let diags = config.check();
+    /// assert!(diags.iter().any(|d| d.severity == Severity::Error));
+    /// ```
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diags = vec![];
+
+        if let Some(ref url) = self.steward {
+            if url.scheme() != "https" {
+                diags.push(Diagnostic::error(
+                    Key::Steward,
+                    format!(
+                        "Steward URL scheme must be `https`, found `{}`, the Keep refuses unencrypted Steward URLs",
+                        url.scheme()
+                    ),
+                ));
+            }
+        }
+
+        let stdio: Vec<_> = self
+            .files
+            .iter()
+            .filter_map(|file| match file {
+                File::Stdin(..) => Some("stdin"),
+                File::Stdout(..) => Some("stdout"),
+                File::Stderr(..) => Some("stderr"),
+                _ => None,
+            })
+            .collect();
+        for kind in ["stdin", "stdout", "stderr"] {
+            if !stdio.contains(&kind) {
+                diags.push(Diagnostic::warning(
+                    Key::Files,
+                    format!("no `{kind}` file descriptor configured"),
+                ));
+            }
+        }
+
+        let mut names = HashMap::new();
+        let mut listens = HashMap::new();
+        for (i, file) in self.files.iter().enumerate() {
+            if let Some(j) = names.insert(file.name(), i) {
+                diags.push(Diagnostic::error(
+                    Key::File(i),
+                    format!(
+                        "duplicate file descriptor name `{}`, already used by `{}`",
+                        file.name(),
+                        Key::File(j)
+                    ),
+                ));
+            }
+
+            match file {
+                File::Listen(ListenFile::Tls { addr, port, .. })
+                | File::Listen(ListenFile::Tcp { addr, port, .. }) => {
+                    if *port == 0 {
+                        diags.push(Diagnostic::error(Key::File(i), "listen port 0 is reserved"));
+                    } else if *port < 1024 {
+                        diags.push(Diagnostic::warning(
+                            Key::File(i),
+                            format!("listen port {port} is privileged and requires the Enarx host process to have the `CAP_NET_BIND_SERVICE` capability"),
+                        ));
+                    }
+                    if let Some(j) = listens.insert((addr.as_str(), *port), i) {
+                        diags.push(Diagnostic::error(
+                            Key::File(i),
+                            format!(
+                                "address `{addr}` and port {port} are already used by `{}`",
+                                Key::File(j)
+                            ),
+                        ));
+                    }
+                }
+                File::Connect(ConnectFile::Tls { port, .. })
+                | File::Connect(ConnectFile::Tcp { port, .. }) => {
+                    if *port == 0 {
+                        diags.push(Diagnostic::error(
+                            Key::File(i),
+                            "connect port 0 is reserved",
+                        ));
+                    }
+                }
+                File::Null(..) | File::Stdin(..) | File::Stdout(..) | File::Stderr(..) => {}
+            }
+        }
+
+        let mut env: Vec<_> = self.env.keys().collect();
+        env.sort();
+        for k in env {
+            if k.is_empty() || k.contains('=') || k.contains('\0') {
+                diags.push(Diagnostic::error(
+                    Key::Env,
+                    format!("invalid environment variable name `{k}`"),
+                ));
+            } else if k == "FD_COUNT" || k == "FD_NAMES" {
+                diags.push(Diagnostic::warning(
+                    Key::Env,
+                    format!("environment variable `{k}` is reserved for the Enarx runtime"),
+                ));
+            }
+        }
+
+        if let (Some(memory), Some(max_memory)) = (self.keep.memory, self.keep.max_memory) {
+            if memory > max_memory {
+                diags.push(Diagnostic::error(
+                    Key::Keep,
+                    format!(
+                        "memory of {memory} bytes exceeds the max-memory of {max_memory} bytes"
+                    ),
+                ));
+            }
+        }
+
+        diags
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(config: &str) -> Vec<Diagnostic> {
+        toml::from_str::<Config>(config).unwrap().check()
+    }
+
+    #[test]
+    fn default() {
+        assert_eq!(Config::default().check(), vec![]);
+    }
+
+    #[test]
+    fn steward() {
+        assert_eq!(
+            check(r#"steward = "https://attest.example.com""#),
+            vec![
+                Diagnostic::warning(Key::Files, "no `stdin` file descriptor configured"),
+                Diagnostic::warning(Key::Files, "no `stdout` file descriptor configured"),
+                Diagnostic::warning(Key::Files, "no `stderr` file descriptor configured"),
+            ]
+        );
+        let diags = check(r#"steward = "http://attest.example.com""#);
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(diags[0].key, Key::Steward);
+    }
+
+    #[test]
+    fn files() {
+        const CONFIG: &str = r#"
+        [[files]]
+        kind = "stdin"
+
+        [[files]]
+        kind = "stdout"
+
+        [[files]]
+        name = "stdout"
+        kind = "null"
+
+        [[files]]
+        name = "a"
+        kind = "listen"
+        prot = "tls"
+
+        [[files]]
+        name = "b"
+        kind = "listen"
+        prot = "tls"
+        port = 443
+
+        [[files]]
+        name = "c"
+        kind = "listen"
+        prot = "tcp"
+        port = 0
+
+        [[files]]
+        kind = "connect"
+        prot = "tcp"
+        host = "example.com"
+        port = 0
+
+        [env]
+        FD_COUNT = "1"
+        "#;
+
+        assert_eq!(
+            check(CONFIG),
+            vec![
+                Diagnostic::warning(Key::Files, "no `stderr` file descriptor configured"),
+                Diagnostic::error(
+                    Key::File(2),
+                    "duplicate file descriptor name `stdout`, already used by `files[1]`"
+                ),
+                Diagnostic::warning(
+                    Key::File(3),
+                    "listen port 443 is privileged and requires the Enarx host process to have the `CAP_NET_BIND_SERVICE` capability"
+                ),
+                Diagnostic::warning(
+                    Key::File(4),
+                    "listen port 443 is privileged and requires the Enarx host process to have the `CAP_NET_BIND_SERVICE` capability"
+                ),
+                Diagnostic::error(
+                    Key::File(4),
+                    "address `::` and port 443 are already used by `files[3]`"
+                ),
+                Diagnostic::error(Key::File(5), "listen port 0 is reserved"),
+                Diagnostic::error(Key::File(6), "connect port 0 is reserved"),
+                Diagnostic::warning(
+                    Key::Env,
+                    "environment variable `FD_COUNT` is reserved for the Enarx runtime"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep() {
+        let mut config = Config::default();
+        config.keep.memory = "1G".parse().ok();
+        config.keep.max_memory = "512M".parse().ok();
+        assert_eq!(
+            config.check(),
+            vec![Diagnostic::error(
+                Key::Keep,
+                "memory of 1073741824 bytes exceeds the max-memory of 536870912 bytes"
+            )]
+        );
+    }
+}
@@ -8,6 +8,13 @@
 #![deny(clippy::all)]
 #![warn(rust_2018_idioms)]
 
+mod check;
+
+pub use check::{Diagnostic, Key, Severity};
+
+use std::fmt;
+use std::num::NonZeroU64;
+use std::str::FromStr;
 use std::{collections::HashMap, ops::Deref};
 
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
@@ -30,6 +37,12 @@ pub const CONFIG_TEMPLATE: &str = r#"## Configuration for a WASI application in
 # VAR1 = "var1"
 # VAR2 = "var2"
 
+## Resources of the Keep
+# [keep]
+# threads = 4
+# memory = "256MiB"
+# max-memory = "4GiB"
+
 ## Pre-opened file descriptors
 [[files]]
 kind = "stdin"
@@ -151,6 +164,10 @@ pub struct Config {
     /// The environment variables to provide to the application
     #[serde(default)]
     pub env: HashMap<String, String>,
+
+    /// The resources of the Keep
+    #[serde(default)]
+    pub keep: Keep,
 }
 
 impl Default for Config {
@@ -166,6 +183,84 @@ impl Default for Config {
             args: vec![],
             files,
             steward: None, // TODO: Default to a deployed Steward instance
+            keep: Keep::default(),
+        }
+    }
+}
+
+/// Resources of the Keep running the application
+///
+/// Unset resources are chosen by the Keep backend. The resources of SGX and SEV
+/// Keeps are part of their launch measurement.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Keep {
+    /// Maximum number of threads, i.e. vCPUs of KVM and SEV Keeps and TCSs of SGX Keeps
+    pub threads: Option<NonZeroU64>,
+
+    /// Memory of the Keep on startup
+    pub memory: Option<MemorySize>,
+
+    /// Maximum memory of the Keep
+    pub max_memory: Option<MemorySize>,
+}
+
+/// A memory size in bytes
+///
+/// It is either an integer number of bytes or a string with a binary unit suffix,
+/// e.g. `"512MiB"` or `"4G"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct MemorySize(pub u64);
+
+impl FromStr for MemorySize {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number.parse().map_err(|_| "invalid memory size")?;
+        let shift = match unit.trim_start() {
+            "" | "B" => 0,
+            "K" | "KiB" => 10,
+            "M" | "MiB" => 20,
+            "G" | "GiB" => 30,
+            "T" | "TiB" => 40,
+            _ => {
+                return Err(
+                    "unknown memory size unit, expected one of `KiB`, `MiB`, `GiB` or `TiB`",
+                )
+            }
+        };
+        number
+            .checked_mul(1 << shift)
+            .map(Self)
+            .ok_or("memory size is too large")
+    }
+}
+
+impl fmt::Display for MemorySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for MemorySize {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Size {
+            Bytes(u64),
+            String(String),
+        }
+
+        match Size::deserialize(deserializer)? {
+            Size::Bytes(bytes) => Ok(Self(bytes)),
+            Size::String(s) => s.parse().map_err(D::Error::custom),
         }
     }
 }
@@ -355,6 +450,35 @@ mod test {
         let _cfg_str = toml::to_string(&cfg).unwrap();
     }
 
+    #[test]
+    fn keep() {
+        let cfg: Config = toml::from_str(
+            r#"
+            [keep]
+            threads = 4
+            memory = "256MiB"
+            max-memory = 4294967296
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cfg.keep,
+            Keep {
+                threads: NonZeroU64::new(4),
+                memory: Some(MemorySize(256 << 20)),
+                max_memory: Some(MemorySize(4 << 30)),
+            }
+        );
+
+        assert_eq!("4G".parse(), Ok(MemorySize(4 << 30)));
+        assert_eq!("12".parse(), Ok(MemorySize(12)));
+        assert!("4 GB".parse::<MemorySize>().is_err());
+        assert!("GiB".parse::<MemorySize>().is_err());
+        assert!("100000000TiB".parse::<MemorySize>().is_err());
+        assert!(toml::from_str::<Config>("[keep]\nthreads = 0").is_err());
+    }
+
     #[test]
     fn names() {
         let cfg: Config = toml::from_str(CONFIG).unwrap();
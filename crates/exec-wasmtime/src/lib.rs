@@ -10,10 +10,15 @@
 mod log;
 mod runtime;
 mod workload;
+mod yank;
 
 #[cfg(unix)]
 pub use log::Level as LogLevel;
 pub use workload::{Package, Workload, PACKAGE_CONFIG, PACKAGE_ENTRYPOINT};
+pub use yank::{
+    get_yank, is_yank_marker, latest_yank_marker, without_yank_markers, yank_marker, Yank,
+    MAX_YANK_SIZE, YANK_FILE,
+};
 
 use runtime::Runtime;
 
@@ -2,6 +2,8 @@
 
 //! Workload-related functionality and definitions.
 
+use crate::yank::{get_yank, Yank};
+
 use std::fs::File;
 use std::io::Read;
 #[cfg(unix)]
@@ -12,6 +14,7 @@ use drawbridge_client::types::{Meta, TagEntry, TreeDirectory, TreeEntry, TreeNam
 use drawbridge_client::{scope, Client, Entity, Node, Scope};
 use enarx_config::Config;
 use once_cell::sync::Lazy;
+use tracing::warn;
 use ureq::serde_json;
 use url::Url;
 use wiggle::tracing::instrument;
@@ -40,8 +43,14 @@ const WASM_MEDIA_TYPE: &str = "application/wasm";
 #[cfg_attr(unix, derive(serde::Deserialize, serde::Serialize))]
 #[cfg_attr(unix, serde(deny_unknown_fields, tag = "t", content = "c"))]
 pub enum Package {
-    /// Remote URL to fetch package from
-    Remote(Url),
+    /// Remote package
+    Remote {
+        /// URL to fetch package from
+        url: Url,
+        /// Whether to execute the package even if it was yanked
+        #[cfg_attr(unix, serde(default))]
+        allow_yanked: bool,
+    },
 
     /// Local package
     #[cfg(unix)]
@@ -130,7 +139,21 @@ impl TryFrom<Package> for Workload {
     #[instrument]
     fn try_from(mut pkg: Package) -> Result<Self, Self::Error> {
         match pkg {
-            Package::Remote(ref url) => {
+            Package::Remote {
+                ref url,
+                allow_yanked,
+            } => {
+                if let Some(Yank {
+                    yanked: true,
+                    reason,
+                }) = get_yank(url).context("failed to fetch package yank status")?
+                {
+                    let reason = reason.map(|r| format!(": {r}")).unwrap_or_default();
+                    if !allow_yanked {
+                        bail!("package `{url}` was yanked{reason}")
+                    }
+                    warn!("executing yanked package `{url}`{reason}");
+                }
                 let cl = Client::<scope::Unknown>::new_scoped(url.clone())
                     .context("failed to construct client")?;
                 let top = Entity::new(&cl);
@@ -0,0 +1,200 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Package yank-related functionality and definitions.
+//!
+//! Drawbridge tags are immutable, therefore the yank status of a tag is recorded in separate
+//! marker tags named `<tag>+yank.<n>` (or `<tag>.yank.<n>` if `<tag>` already contains build
+//! metadata). Each marker contains a single [`YANK_FILE`] with a JSON-encoded [`Yank`] record.
+//! The marker with the highest `<n>` determines the current yank status of `<tag>`.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use drawbridge_client::types::{TagEntry, TreePath};
+use drawbridge_client::{scope, Client, Entity, Node};
+use serde::{Deserialize, Serialize};
+use ureq::serde_json;
+use url::Url;
+
+/// Name of the file containing the [`Yank`] record in a yank marker tag
+pub const YANK_FILE: &str = "yank.json";
+
+/// Maximum size of the tag list in bytes
+const MAX_TAGS_SIZE: u64 = 1_000_000;
+/// Maximum size of a yank record in bytes
+pub const MAX_YANK_SIZE: u64 = 100_000;
+
+/// Yank status of a package
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Yank {
+    /// Whether the package is yanked
+    pub yanked: bool,
+
+    /// Optional human-readable reason for the yank
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+fn yank_marker_prefix(tag: &str) -> String {
+    if tag.contains('+') {
+        format!("{tag}.yank.")
+    } else {
+        format!("{tag}+yank.")
+    }
+}
+
+/// Returns the name of the yank marker of `tag` with sequence number `seq`.
+pub fn yank_marker(tag: &str, seq: u64) -> String {
+    format!("{}{seq}", yank_marker_prefix(tag))
+}
+
+/// Returns the tag whose yank marker `name` would be.
+fn yanked_tag(name: &str) -> Option<&str> {
+    let (head, seq) = name.rsplit_once("yank.")?;
+    let seq = seq.parse().ok()?;
+    let tag = head.strip_suffix('+').or_else(|| head.strip_suffix('.'))?;
+    (yank_marker(tag, seq) == name).then_some(tag)
+}
+
+/// Returns `true` if `name` is the yank marker of one of `tags`.
+///
+/// A name ending with a marker suffix, whose tag does not exist, is a regular tag
+/// with build metadata, e.g. `1.0.0+build.yank.3`.
+pub fn is_yank_marker<'a>(name: &str, tags: impl IntoIterator<Item = &'a str>) -> bool {
+    yanked_tag(name).map_or(false, |tag| tags.into_iter().any(|t| t == tag))
+}
+
+/// Removes the yank markers from the tag list `tags`.
+pub fn without_yank_markers<T: ToString>(tags: Vec<T>) -> Vec<T> {
+    let names: Vec<_> = tags.iter().map(ToString::to_string).collect();
+    tags.into_iter()
+        .zip(&names)
+        .filter(|(_, name)| !is_yank_marker(name, names.iter().map(String::as_str)))
+        .map(|(tag, _)| tag)
+        .collect()
+}
+
+/// Returns the sequence number and name of the latest yank marker of `tag` found in `tags`.
+pub fn latest_yank_marker<'a>(
+    tag: &str,
+    tags: impl IntoIterator<Item = &'a str>,
+) -> Option<(u64, &'a str)> {
+    let prefix = yank_marker_prefix(tag);
+    tags.into_iter()
+        .filter_map(|name| {
+            name.strip_prefix(prefix.as_str())
+                .and_then(|seq| seq.parse().ok())
+                .map(|seq| (seq, name))
+        })
+        .max_by_key(|(seq, _)| *seq)
+}
+
+/// Fetches the yank status of the Drawbridge tag at `url`.
+///
+/// Returns `None` if `url` does not refer to a Drawbridge tag or if the tag was never yanked.
+pub fn get_yank(url: &Url) -> Result<Option<Yank>> {
+    let mut segments = match url.path_segments() {
+        Some(segments) => segments.rev(),
+        None => return Ok(None),
+    };
+    let tag = match (segments.next(), segments.next()) {
+        (Some(tag), Some("_tag")) if !tag.is_empty() => tag,
+        _ => return Ok(None),
+    };
+
+    let mut tags_url = url.clone();
+    tags_url
+        .path_segments_mut()
+        .map_err(|()| anyhow!("URL `{url}` cannot be a base"))?
+        .pop();
+    let cl = Client::<scope::Unknown>::new_scoped(tags_url.clone())
+        .context("failed to construct client")?;
+    let (_, rdr) = Entity::new(&cl)
+        .get(MAX_TAGS_SIZE)
+        .with_context(|| format!("failed to fetch tags at `{tags_url}`"))?;
+    let tags: Vec<String> = serde_json::from_reader(rdr).context("failed to decode tag list")?;
+
+    let marker = match latest_yank_marker(tag, tags.iter().map(String::as_str)) {
+        Some((_, marker)) => marker,
+        None => return Ok(None),
+    };
+
+    let mut marker_url = tags_url;
+    marker_url
+        .path_segments_mut()
+        .map_err(|()| anyhow!("URL `{url}` cannot be a base"))?
+        .push(marker);
+    let cl = Client::<scope::Unknown>::new_scoped(marker_url.clone())
+        .context("failed to construct client")?;
+    let top = Entity::new(&cl);
+    let (_, rdr) = top
+        .get(MAX_YANK_SIZE)
+        .with_context(|| format!("failed to fetch yank marker `{marker_url}`"))?;
+    let entry = match serde_json::from_reader(rdr).context("failed to decode yank marker")? {
+        TagEntry::Unsigned(e) => e,
+        TagEntry::Signed(_jws) => bail!("signed tags are not currently supported"),
+    };
+    let (meta, yank) = Node::new(top.child("tree"), &TreePath::ROOT)
+        .get_bytes(MAX_YANK_SIZE)
+        .context("failed to fetch yank record")?;
+    ensure!(
+        meta == entry.meta,
+        "yank record metadata does not match tag entry metadata"
+    );
+    serde_json::from_slice(&yank)
+        .context("failed to decode yank record")
+        .map(Some)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn marker_names() {
+        assert_eq!(yank_marker("1.0.0", 0), "1.0.0+yank.0");
+        assert_eq!(yank_marker("1.0.0+build.1", 2), "1.0.0+build.1.yank.2");
+
+        let tags = ["1.0.0", "1.0.0+build.1", "2.0.0+build.yank.3"];
+        assert!(is_yank_marker("1.0.0+yank.0", tags));
+        assert!(is_yank_marker("1.0.0+build.1.yank.2", tags));
+        assert!(!is_yank_marker("1.0.0", tags));
+        assert!(!is_yank_marker("1.0.0+yank", tags));
+        assert!(!is_yank_marker("1.0.0+noyank.1", tags));
+        assert!(!is_yank_marker("1.0.0.yank.1", tags));
+        assert!(!is_yank_marker("1.0.0+yank.01", tags));
+        assert!(!is_yank_marker("1.0.1+yank.0", tags));
+        assert!(!is_yank_marker("2.0.0+build.yank.3", tags));
+    }
+
+    #[test]
+    fn marker_filter() {
+        let tags = vec![
+            "1.0.0",
+            "1.0.0+yank.0",
+            "1.0.0+build.1",
+            "1.0.0+build.1.yank.2",
+            "2.0.0+build.yank.3",
+        ];
+        assert_eq!(
+            without_yank_markers(tags),
+            ["1.0.0", "1.0.0+build.1", "2.0.0+build.yank.3"]
+        );
+    }
+
+    #[test]
+    fn latest_marker() {
+        let tags = [
+            "1.0.0",
+            "1.0.0+yank.0",
+            "1.0.0+yank.10",
+            "1.0.0+yank.2",
+            "1.0.1+yank.11",
+            "2.0.0",
+        ];
+        assert_eq!(
+            latest_yank_marker("1.0.0", tags),
+            Some((10, "1.0.0+yank.10"))
+        );
+        assert_eq!(latest_yank_marker("2.0.0", tags), None);
+    }
+}
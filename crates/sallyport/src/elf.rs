@@ -55,6 +55,18 @@ pub mod note {
     /// The number of sallyport blocks of the shim (u64)
     pub const NUM_BLOCKS: u32 = 0x73677821;
 
+    /// The maximum number of threads of the Keep (u64; 0 for the default)
+    ///
+    /// The host overrides this and the following notes in the loaded shim, so
+    /// their values are part of the launch measurement.
+    pub const MAX_THREADS: u32 = 0x73677830;
+
+    /// The memory of the Keep on startup in bytes (u64; 0 for the default)
+    pub const MEMORY: u32 = 0x73677831;
+
+    /// The maximum memory of the Keep in bytes (u64; 0 for no limit)
+    pub const MAX_MEMORY: u32 = 0x73677832;
+
     /// SGX ELF Notes
     pub mod sgx {
         /// The SGX enclave bits (u8; in powers of 2)
@@ -3,3 +3,4 @@
 //! share code between the Enarx components not using std
 
 pub mod cpuid_page;
+pub mod note;
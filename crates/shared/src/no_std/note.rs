@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lookup of ELF notes in a note section
+
+use core::mem::size_of;
+
+/// Alignment of the name and descriptor of a note
+const ALIGN: usize = 4;
+
+fn align(n: usize) -> Option<usize> {
+    n.checked_add(ALIGN - 1).map(|n| n / ALIGN * ALIGN)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset.checked_add(size_of::<u32>())?)?;
+    Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+}
+
+/// Returns the descriptor of the first note of type `kind` named `name` in the note section `notes`.
+pub fn find<'a>(notes: &'a [u8], name: &str, kind: u32) -> Option<&'a [u8]> {
+    let mut offset = 0;
+    while offset < notes.len() {
+        let namesz = read_u32(notes, offset)? as usize;
+        let descsz = read_u32(notes, offset + 4)? as usize;
+        let n_type = read_u32(notes, offset + 8)?;
+
+        let name_start = offset + 3 * size_of::<u32>();
+        let desc_start = name_start.checked_add(align(namesz)?)?;
+        let desc_end = desc_start.checked_add(descsz)?;
+
+        let n_name = notes.get(name_start..name_start + namesz)?;
+        let desc = notes.get(desc_start..desc_end)?;
+        let n_name = n_name.strip_suffix(&[0]).unwrap_or(n_name);
+
+        if n_type == kind && n_name == name.as_bytes() {
+            return Some(desc);
+        }
+
+        offset = align(desc_end)?;
+    }
+    None
+}
+
+/// Returns the `u64` descriptor of the first note of type `kind` named `name` in the note section `notes`.
+pub fn find_u64(notes: &[u8], name: &str, kind: u32) -> Option<u64> {
+    let desc = find(notes, name, kind)?;
+    Some(u64::from_ne_bytes(desc.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn note(name: &str, kind: u32, desc: &[u8]) -> Vec<u8> {
+        let mut note = Vec::new();
+        note.extend((name.len() as u32 + 1).to_ne_bytes());
+        note.extend((desc.len() as u32).to_ne_bytes());
+        note.extend(kind.to_ne_bytes());
+        note.extend(name.as_bytes());
+        note.push(0);
+        note.resize(align(note.len()).unwrap(), 0);
+        note.extend(desc);
+        note.resize(align(note.len()).unwrap(), 0);
+        note
+    }
+
+    #[test]
+    fn lookup() {
+        let mut notes = note("sallyport", 1, &[1, 2, 3]);
+        notes.extend(note("other", 2, &7u64.to_ne_bytes()));
+        notes.extend(note("sallyport", 2, &42u64.to_ne_bytes()));
+
+        assert_eq!(find(&notes, "sallyport", 1), Some(&[1, 2, 3][..]));
+        assert_eq!(find_u64(&notes, "sallyport", 2), Some(42));
+        assert_eq!(find_u64(&notes, "sallyport", 1), None);
+        assert_eq!(find(&notes, "sallyport", 3), None);
+        assert_eq!(find(&notes[..notes.len() - 4], "sallyport", 2), None);
+        assert_eq!(find(&[], "sallyport", 1), None);
+    }
+}
@@ -21,6 +21,7 @@ use goblin::elf::program_header::program_header64::*;
 use linked_list_allocator::Heap;
 use lset::{Line, Span};
 use primordial::Address;
+use sallyport::elf::note;
 use sallyport::guest::Handler;
 use spin::{Lazy, Mutex, MutexGuard, RwLockWriteGuard};
 use x86_64::instructions::tlb::flush_all;
@@ -73,6 +74,8 @@ pub static ALLOCATOR: LockedEnarxAllocator = LockedEnarxAllocator {
 pub struct EnarxAllocator {
     last_alloc: usize,
     max_alloc: usize,
+    ballooned: usize,
+    max_mem: usize,
     end_of_mem: PhysAddr,
     allocator: Heap,
 }
@@ -82,6 +85,8 @@ impl core::fmt::Debug for EnarxAllocator {
         f.debug_struct("EnarxAllocator")
             .field("last_alloc", &self.last_alloc)
             .field("max_alloc", &self.max_alloc)
+            .field("ballooned", &self.ballooned)
+            .field("max_mem", &self.max_mem)
             .finish()
     }
 }
@@ -426,6 +431,16 @@ impl EnarxAllocator {
         let next_alloc = (2usize).checked_pow(MIN_EXP).unwrap();
         let max_alloc = (2usize).checked_pow(max_exp).unwrap();
 
+        // The first allocation requests the configured memory of the Keep,
+        // rounded up to the next power of two.
+        let first_alloc = crate::note_u64(note::MEMORY).map_or(next_alloc, |memory| {
+            (memory as usize)
+                .checked_next_power_of_two()
+                .unwrap_or(max_alloc)
+                .clamp(next_alloc, max_alloc)
+        });
+        let max_mem = crate::note_u64(note::MAX_MEMORY).map_or(usize::MAX, |m| m as usize);
+
         let mem_start: PhysAddr = {
             let shim_virt = ShimVirtAddr::from(&crate::_ENARX_MEM_START);
             PhysAddr::new(ShimPhysAddr::try_from(shim_virt).unwrap().raw().raw())
@@ -493,8 +508,10 @@ impl EnarxAllocator {
         let allocator = Heap::empty();
 
         EnarxAllocator {
-            last_alloc: next_alloc.checked_div(2).unwrap(),
+            last_alloc: first_alloc.checked_div(2).unwrap(),
             max_alloc,
+            ballooned: 0,
+            max_mem,
             end_of_mem,
             allocator,
         }
@@ -509,6 +526,14 @@ impl EnarxAllocator {
                 .checked_mul(last_size as u64)
                 .unwrap_or(last_size as u64) as _;
             let new_size = new_size.min(self.max_alloc);
+            // never exceed the configured maximum memory of the Keep
+            let new_size = align_down(
+                new_size.min(self.max_mem.saturating_sub(self.ballooned)) as u64,
+                Page::<Size4KiB>::SIZE,
+            ) as usize;
+            if new_size == 0 {
+                return false;
+            }
             let num_pages = new_size.checked_div(PG_USIZE).unwrap();
 
             let ret = HostCall::maint().balloon_memory(
@@ -546,6 +571,7 @@ impl EnarxAllocator {
                         }
                     }
                     self.last_alloc = new_size;
+                    self.ballooned = self.ballooned.checked_add(new_size).unwrap();
 
                     return true;
                 }
@@ -14,8 +14,7 @@ use crate::snp::snp_active;
 use crate::spin::RacyCell;
 use crate::syscall::SyscallStackFrameValue;
 use crate::thread::{
-    GenPurposeRegs, NewThreadFromRegisters, Tcb, TcbRefCell, NEW_THREAD_QUEUE, THREADS_FREE,
-    THREAD_ID_CNT,
+    GenPurposeRegs, NewThreadFromRegisters, Tcb, TcbRefCell, NEW_THREAD_QUEUE, THREAD_ID_CNT,
 };
 use crate::MAX_NUM_CPUS;
 
@@ -55,9 +54,9 @@ pub const NUM_BLOCKS: usize = 2;
 
 const BLOCK_SIZE_USIZE: usize = BLOCK_SIZE / core::mem::size_of::<usize>();
 
-const SNP_VCEK_BUF_SIZE: usize = 4096;
+const SNP_VCEK_BUF_SIZE: usize = 16384;
 
-/// SNP VCEK buffer
+/// SNP evidence buffer with the VCEK, the AMD CRLs, the certificate chain and the reported TCB
 pub static SNP_VCEK: Lazy<Result<&[u8], c_int>> = Lazy::new(|| {
     static SNP_VCEK_BUFFER: RacyCell<[u8; SNP_VCEK_BUF_SIZE]> =
         RacyCell::new([0; SNP_VCEK_BUF_SIZE]);
@@ -459,16 +458,22 @@ impl Handler for HostCall<'_> {
 
         ptid.store(new_tid as _, Ordering::Relaxed);
 
-        if snp_active() && THREADS_FREE.load(Ordering::SeqCst) < queue.len() {
-            eprintln!("[{tid}] clone() no thread free, allocate new VMSA");
-            // https://github.com/enarx/enarx/issues/2310
-            todo!();
-        }
+        // Release the queue, so that vCPUs exiting on the host can be reused.
+        drop(queue);
 
+        // The host runs an idle or new vCPU, which picks up the next queued thread.
         let ret = self.spawn(0);
 
         eprintln!("[{tid}] spawn() = {ret:#?} {new_tid}");
-        ret?;
+
+        if let Err(e) = ret {
+            // No vCPU is left, unless an idle one picked up the thread meanwhile.
+            let mut queue = NEW_THREAD_QUEUE.write();
+            if let Some(pos) = queue.iter().position(|thread| thread.tid == new_tid) {
+                queue.remove(pos);
+                return Err(e);
+            }
+        }
 
         Ok(new_tid)
     }
@@ -63,6 +63,25 @@ extern "C" {
     pub static _ENARX_EXEC_END: Page4KiB;
     /// Extern
     pub static _ENARX_CPUID: CpuidPage;
+    /// Extern
+    pub static _ENARX_NOTE_START: u8;
+    /// Extern
+    pub static _ENARX_NOTE_END: u8;
+}
+
+/// Get the `u64` value of the Enarx note of type `kind`
+///
+/// The host may override the value before the launch, so it is part of the measurement.
+/// A value of zero means the default.
+pub fn note_u64(kind: u32) -> Option<u64> {
+    // Safety: the linker script places the symbols around the note section
+    let notes = unsafe {
+        let start = &_ENARX_NOTE_START as *const u8;
+        let end = &_ENARX_NOTE_END as *const u8;
+        core::slice::from_raw_parts(start, (end as usize).checked_sub(start as usize)?)
+    };
+
+    shared::no_std::note::find_u64(notes, sallyport::elf::note::NAME, kind).filter(|v| *v != 0)
 }
 
 /// Maximum virtual cpus supported
@@ -75,6 +94,10 @@ pub const SHIM_STACK_START: u64 = 0xFFFF_FF48_4800_0000;
 #[allow(clippy::integer_arithmetic)]
 pub const SHIM_STACK_SIZE: u64 = bytes![2; MiB];
 
+/// The distance between the main kernel stacks of two consecutive CPUs
+#[allow(clippy::integer_arithmetic)]
+pub const SHIM_STACK_STRIDE: u64 = SHIM_STACK_SIZE + bytes![2; MiB];
+
 /// Exec virtual address, where the elf binary is mapped to, plus a random offset
 const EXEC_ELF_VIRT_ADDR_BASE: VirtAddr = VirtAddr::new_truncate(0x7f00_0000_0000);
 
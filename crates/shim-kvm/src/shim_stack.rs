@@ -3,6 +3,9 @@
 //! Helper functions for the shim stack
 
 use crate::allocator::PageTableAllocatorLock;
+use crate::{MAX_NUM_CPUS, SHIM_STACK_START, SHIM_STACK_STRIDE};
+
+use core::arch::asm;
 
 use x86_64::structures::paging::{Page, PageTableFlags, Size4KiB};
 use x86_64::{PhysAddr, VirtAddr};
@@ -75,3 +78,20 @@ pub fn init_stack_with_guard(
         slice: mem_slice,
     }
 }
+
+/// The number of the CPU owning the shim stack the caller runs on
+///
+/// Every CPU runs on its own shim stack after startup, also for syscalls
+/// and exceptions, so the stack pointer identifies the current CPU.
+/// Returns `None` on the initial stack.
+#[inline]
+pub fn current_cpu() -> Option<usize> {
+    let rsp: u64;
+    // SAFETY: reading the stack pointer has no side effects
+    unsafe { asm!("mov {}, rsp", out(reg) rsp, options(nomem, nostack, preserves_flags)) };
+
+    // The stack pointer is at the end of an empty stack, so look at the byte below it.
+    let offset = rsp.checked_sub(SHIM_STACK_START)?.checked_sub(1)?;
+    let cpunum = offset.checked_div(SHIM_STACK_STRIDE)? as usize;
+    (cpunum < MAX_NUM_CPUS).then_some(cpunum)
+}
@@ -7,19 +7,23 @@
 
 use crate::addr::SHIM_VIRT_OFFSET;
 use crate::pagetables::{clear_c_bit_address_range, smash};
+use crate::shim_stack::current_cpu;
 use crate::snp::secrets_page::SECRETS;
 use crate::snp::{pvalidate, ByteSized, PvalidateSize};
 use crate::spin::{Locked, RacyCell, RwLocked};
+use crate::MAX_NUM_CPUS;
 
+use alloc::boxed::Box;
 use core::arch::asm;
 use core::mem::size_of;
+use core::ops::Deref;
 use core::ptr;
 
 use aes_gcm::{AeadInPlace, Aes256Gcm, KeyInit, Nonce, Tag};
 use bitflags::bitflags;
 use const_default::ConstDefault;
 use sallyport::libc::{EINVAL, EIO};
-use spin::Lazy;
+use spin::{Lazy, Once};
 use x86_64::registers::model_specific::Msr;
 use x86_64::structures::paging::{Page, Size4KiB};
 use x86_64::{PhysAddr, VirtAddr};
@@ -307,17 +311,46 @@ pub struct GhcbHandle<'a> {
 }
 
 #[cfg_attr(coverage, no_coverage)]
-fn lazy_ghcb() -> RwLocked<GhcbHandle<'static>> {
+fn lazy_ghcb(cpunum: usize) -> RwLocked<GhcbHandle<'static>> {
     #[link_section = ".ghcb"]
     static GHCB: RacyCell<Ghcb> = RacyCell::new(<Ghcb as ConstDefault>::DEFAULT);
 
-    // Safety: The above static `RacyCell` can only be accessed via the `RwLocked handle.
-    let ghcb_mut_ref = unsafe { &mut *GHCB.get() };
+    let ghcb_mut_ref = if cpunum == 0 {
+        // Safety: The above static `RacyCell` can only be accessed via the `RwLocked` handle
+        // of the first CPU.
+        unsafe { &mut *GHCB.get() }
+    } else {
+        Box::leak(Box::new(<Ghcb as ConstDefault>::DEFAULT))
+    };
     RwLocked::<GhcbHandle<'_>>::new(GhcbHandle::new(ghcb_mut_ref))
 }
 
-/// The global Enarx guest hypervisor communication block - GHCB
-pub static GHCB: Lazy<RwLocked<GhcbHandle<'_>>> = Lazy::new(lazy_ghcb);
+/// The guest hypervisor communication blocks - GHCB - of all CPUs
+///
+/// The hypervisor only accepts the GHCB registered on the CPU doing the `vmgexit`,
+/// so every CPU registers its own GHCB on first use.
+static GHCBS: [Once<RwLocked<GhcbHandle<'static>>>; MAX_NUM_CPUS] = {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const GHCB_ONCE: Once<RwLocked<GhcbHandle<'static>>> = Once::new();
+    [GHCB_ONCE; MAX_NUM_CPUS]
+};
+
+/// The Enarx guest hypervisor communication block - GHCB - of the current CPU
+pub struct CpuGhcb;
+
+impl Deref for CpuGhcb {
+    type Target = RwLocked<GhcbHandle<'static>>;
+
+    #[cfg_attr(coverage, no_coverage)]
+    fn deref(&self) -> &Self::Target {
+        // Only the first CPU uses the GHCB before switching to its shim stack.
+        let cpunum = current_cpu().unwrap_or(0);
+        GHCBS[cpunum].call_once(|| lazy_ghcb(cpunum))
+    }
+}
+
+/// The Enarx guest hypervisor communication block - GHCB - of the current CPU
+pub static GHCB: CpuGhcb = CpuGhcb;
 
 impl<'a> GhcbHandle<'a> {
     #[cfg_attr(coverage, no_coverage)]
@@ -19,7 +19,7 @@ use enarx_shim_kvm::snp::C_BIT_MASK;
 use enarx_shim_kvm::sse;
 use enarx_shim_kvm::stdio::enable_printing;
 use enarx_shim_kvm::thread::pickup_new_threads;
-use enarx_shim_kvm::{exec, MAX_NUM_CPUS, SHIM_STACK_SIZE, SHIM_STACK_START};
+use enarx_shim_kvm::{exec, MAX_NUM_CPUS, SHIM_STACK_SIZE, SHIM_STACK_START, SHIM_STACK_STRIDE};
 
 use core::arch::{asm, global_asm};
 use core::mem::size_of;
@@ -30,7 +30,7 @@ use rcrt1::dyn_reloc;
 use sallyport::{elf::note, REQUIRES};
 use spin::Lazy;
 use x86_64::registers::control::{Cr0Flags, Cr4Flags, EferFlags};
-use x86_64::structures::paging::{Page, PageTableFlags, Size2MiB};
+use x86_64::structures::paging::PageTableFlags;
 use x86_64::VirtAddr;
 
 const POLICY_FLAGS: PolicyFlags = PolicyFlags::SMT;
@@ -56,6 +56,10 @@ noted! {
 
     static NOTE_BLOCK_SIZE<note::NAME, note::BLOCK_SIZE, u64> = BLOCK_SIZE as u64;
 
+    static NOTE_MAX_THREADS<note::NAME, note::MAX_THREADS, u64> = 0;
+    static NOTE_MEMORY<note::NAME, note::MEMORY, u64> = 0;
+    static NOTE_MAX_MEMORY<note::NAME, note::MAX_MEMORY, u64> = 0;
+
     static NOTE_SVN<note::NAME, note::snp::SVN, u32> = 1;
     static NOTE_POLICY<note::NAME, note::snp::POLICY, u64> = POLICY;
     static NOTE_FAMILY_ID<note::NAME, note::snp::FAMILY_ID, [u8; 16]> = [0; 16];
@@ -75,9 +79,7 @@ static INITIAL_SHIM_STACK: [primordial::Page; INITIAL_STACK_PAGES] =
 /// Create a shim stack
 pub fn shim_stack(cpunum: usize) -> GuardedStack {
     assert!(cpunum < MAX_NUM_CPUS);
-    let start = VirtAddr::new(
-        SHIM_STACK_START + (cpunum as u64) * (SHIM_STACK_SIZE + Page::<Size2MiB>::SIZE),
-    );
+    let start = VirtAddr::new(SHIM_STACK_START + (cpunum as u64) * SHIM_STACK_STRIDE);
     init_stack_with_guard(start, SHIM_STACK_SIZE, PageTableFlags::empty())
 }
 
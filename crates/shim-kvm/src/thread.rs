@@ -11,7 +11,7 @@ use core::cell::{Cell, UnsafeCell};
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 use core::ptr::NonNull;
-use core::sync::atomic::{AtomicI32, AtomicU32, AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicI32, AtomicU32};
 
 use const_default::ConstDefault;
 use sallyport::guest::ThreadLocalStorage;
@@ -38,12 +38,10 @@ pub fn pickup_new_threads() -> ! {
                 unsafe { thread.regs.load_registers() }
             }
             None => {
-                THREADS_FREE.fetch_add(1, Ordering::SeqCst);
                 drop(queue);
                 HostCall::exit_io(0);
             }
         }
-        THREADS_FREE.fetch_sub(1, Ordering::SeqCst);
     }
 }
 
@@ -289,9 +287,6 @@ impl TcbRefCell {
 /// actual thread ID to be used for the next thread
 pub static THREAD_ID_CNT: AtomicI32 = AtomicI32::new(1);
 
-/// number of free threads
-pub static THREADS_FREE: AtomicUsize = AtomicUsize::new(0);
-
 /// Extend some trait with a method to load registers
 pub trait LoadRegsExt {
     /// manually load the registers from the SSA
@@ -31,7 +31,7 @@ use crate::handler::usermem::UserMemScope;
 use crate::heap::{Access, Heap};
 use crate::thread::{
     NewThread, NewThreadFromRegisters, Tcb, Tcs, ThreadMem, NEW_THREAD_QUEUE, THREADS_FREE,
-    THREAD_ID_CNT,
+    THREADS_TOTAL, THREAD_ID_CNT,
 };
 use crate::{
     shim_address, CSSA_0_STACK_SIZE, CSSA_1_PLUS_STACK_SIZE, DEBUG, ENARX_EXEC_END,
@@ -50,6 +50,7 @@ use core::sync::atomic::{AtomicU32, Ordering};
 
 use mmledger::{Record, Region, Span};
 use primordial::{Address, Offset, Page};
+use sallyport::elf::note;
 use sallyport::guest::{self, Handler as _, Platform, ThreadLocalStorage};
 use sallyport::item::enarxcall::sgx::{Report, ReportData, TargetInfo, TECH};
 use sallyport::item::enarxcall::{SYS_GETATT, SYS_GETKEY};
@@ -72,9 +73,16 @@ const OP_SYSCALL: u16 = 0x050f;
 const OP_CPUID: u16 = 0xa20f;
 
 /// The keep heap
+///
+/// The heap is limited by the configured maximum memory of the Keep, if any.
 pub static HEAP: Lazy<RwLock<Heap>> = Lazy::new(|| {
     let start = unsafe { &ENARX_EXEC_END as *const _ } as usize;
     let end = shim_address() + ENCL_SIZE;
+    let end = match crate::note_u64(note::MAX_MEMORY) {
+        Some(max_memory) => end.min(start.saturating_add(max_memory as usize)),
+        None => end,
+    };
+    let end = end & !(Page::SIZE - 1);
     let span: Span = Region::new(Address::new(start), Address::new(end)).into();
     RwLock::new(Heap::new(span.start, span.count))
 });
@@ -312,8 +320,17 @@ impl guest::Handler for Handler<'_> {
         let mut threads_free_guard = THREADS_FREE.write();
 
         let addr = if *threads_free_guard == 0 {
+            let mut threads_total_guard = THREADS_TOTAL.write();
+            if let Some(max_threads) = crate::note_u64(note::MAX_THREADS) {
+                if *threads_total_guard >= max_threads {
+                    debugln!(self, "[{tid}] maximum number of threads reached");
+                    return Err(EAGAIN);
+                }
+            }
             debugln!(self, "[{tid}] allocating new thread");
-            self.thread_mem_alloc().map_err(|_| EAGAIN)? as usize
+            let addr = self.thread_mem_alloc().map_err(|_| EAGAIN)? as usize;
+            *threads_total_guard += 1;
+            addr
         } else {
             *threads_free_guard -= 1;
             0
@@ -80,6 +80,24 @@ extern "C" {
     pub static ENARX_EXEC_START: u8;
     /// Extern
     pub static ENARX_EXEC_END: u8;
+    /// Extern
+    pub static ENARX_NOTE_START: u8;
+    /// Extern
+    pub static ENARX_NOTE_END: u8;
+}
+
+/// Get the `u64` value of the Enarx note of type `kind`
+///
+/// The host may override the value before the launch, so it is part of the measurement.
+/// A value of zero means the default.
+pub fn note_u64(kind: u32) -> Option<u64> {
+    let notes = unsafe {
+        let start = &ENARX_NOTE_START as *const u8;
+        let end = &ENARX_NOTE_END as *const u8;
+        core::slice::from_raw_parts(start, (end as usize).checked_sub(start as usize)?)
+    };
+
+    shared::no_std::note::find_u64(notes, sallyport::elf::note::NAME, kind).filter(|v| *v != 0)
 }
 
 /// Get the Shim's base address used to check ranges and calculate offsets.
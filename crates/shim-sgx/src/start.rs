@@ -41,6 +41,10 @@ noted! {
 
     static NOTE_BLOCK_SIZE<note::NAME, note::BLOCK_SIZE, u64> = BLOCK_SIZE as u64;
 
+    static NOTE_MAX_THREADS<note::NAME, note::MAX_THREADS, u64> = 0;
+    static NOTE_MEMORY<note::NAME, note::MEMORY, u64> = 0;
+    static NOTE_MAX_MEMORY<note::NAME, note::MAX_MEMORY, u64> = 0;
+
     static NOTE_BITS<note::NAME, note::sgx::BITS, u8> = ENCL_SIZE_BITS;
     static NOTE_SSAP<note::NAME, note::sgx::SSAP, u8> = 1;
 
@@ -220,6 +220,9 @@ pub static THREAD_ID_CNT: AtomicI32 = AtomicI32::new(1);
 /// number of free threads
 pub static THREADS_FREE: RwLock<usize> = RwLock::new(0);
 
+/// number of allocated threads, including the main thread
+pub static THREADS_TOTAL: RwLock<u64> = RwLock::new(1);
+
 /// Extend some trait with a method to load registers
 pub trait LoadRegsExt {
     /// manually load the registers from the SSA
@@ -0,0 +1,400 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline verification of the attestation evidence of Enarx Keeps
+//!
+//! A Keep requests its certificate from a Steward with a CSR, which carries the
+//! attestation evidence of the Keep in an extension identified by the technology.
+
+pub mod pki;
+pub mod roots;
+pub mod sgx;
+pub mod snp;
+
+use crate::caching::CrlList;
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context, Result};
+use der::oid::ObjectIdentifier;
+use der::{Decode, Document, Encode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384};
+use x509_cert::request::{CertReq, ExtensionReq};
+use x509_cert::Certificate;
+
+const EXTENSION_REQ: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.14");
+
+/// The attestation technology of a Keep
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Technology {
+    Kvm,
+    Sgx,
+    Snp,
+}
+
+impl Technology {
+    const KVM: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.58270.1.1");
+    const SGX: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.58270.1.2");
+    const SNP: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.58270.1.3");
+
+    fn from_oid(oid: ObjectIdentifier) -> Option<Self> {
+        match oid {
+            Self::KVM => Some(Self::Kvm),
+            Self::SGX => Some(Self::Sgx),
+            Self::SNP => Some(Self::Snp),
+            _ => None,
+        }
+    }
+
+    /// Returns the report data a Keep of this technology binds to `public_key`.
+    fn report_data(self, public_key: &[u8]) -> [u8; 64] {
+        let mut data = [0; 64];
+        match self {
+            Self::Snp => data[..48].copy_from_slice(&Sha384::digest(public_key)),
+            _ => data[..32].copy_from_slice(&Sha256::digest(public_key)),
+        }
+        data
+    }
+}
+
+/// Attestation evidence of a Keep
+#[derive(Debug)]
+pub struct Evidence {
+    pub technology: Technology,
+    /// DER encoded `SubjectPublicKeyInfo` of the CSR, if the evidence came with one
+    pub public_key: Option<Vec<u8>>,
+    /// The technology specific evidence
+    pub data: Vec<u8>,
+}
+
+impl Evidence {
+    /// Extracts the evidence from a PEM or DER encoded CSR and verifies the CSR signature.
+    pub fn from_csr(bytes: &[u8]) -> Result<Self> {
+        let doc;
+        let der = match std::str::from_utf8(bytes) {
+            Ok(pem) if pem.trim_start().starts_with("-----BEGIN") => {
+                let (_, d) =
+                    Document::from_pem(pem.trim()).context("Failed to PEM-decode the CSR")?;
+                doc = d;
+                doc.as_bytes()
+            }
+            _ => bytes,
+        };
+        let csr = CertReq::from_der(der).context("Failed to DER-decode the CSR")?;
+
+        let signature = csr
+            .signature
+            .as_bytes()
+            .ok_or_else(|| anyhow!("CSR signature is not octet-aligned"))?;
+        pki::verify_signature(
+            &csr.info.public_key,
+            &csr.algorithm,
+            &csr.info.to_der()?,
+            signature,
+        )
+        .context("Invalid CSR signature")?;
+
+        let mut evidence = None;
+        for attr in csr.info.attributes.iter() {
+            if attr.oid != EXTENSION_REQ {
+                continue;
+            }
+            for value in attr.values.iter() {
+                let req: ExtensionReq = value
+                    .decode_as()
+                    .context("Failed to decode the CSR extension request")?;
+                for ext in req.0 {
+                    if let Some(technology) = Technology::from_oid(ext.extn_id) {
+                        if evidence.is_some() {
+                            bail!("CSR contains more than one attestation extension");
+                        }
+                        evidence = Some((technology, ext.extn_value.into_bytes()));
+                    }
+                }
+            }
+        }
+        let (technology, data) =
+            evidence.ok_or_else(|| anyhow!("CSR does not contain attestation evidence"))?;
+
+        Ok(Self {
+            technology,
+            public_key: Some(csr.info.public_key.to_der()?),
+            data,
+        })
+    }
+}
+
+/// The result of a single verification step
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub pass: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<String>,
+}
+
+/// The outcome of verifying attestation evidence
+#[derive(Debug, Serialize)]
+pub struct Verdict {
+    pub technology: Technology,
+    /// Whether all checks passed
+    pub verified: bool,
+    /// The values attested by the evidence
+    pub claims: BTreeMap<&'static str, String>,
+    pub checks: Vec<Check>,
+}
+
+impl Verdict {
+    fn new(technology: Technology) -> Self {
+        Self {
+            technology,
+            verified: false,
+            claims: BTreeMap::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    fn claim(&mut self, name: &'static str, value: String) {
+        self.claims.insert(name, value);
+    }
+
+    fn check(&mut self, name: &'static str, result: Result<()>) {
+        self.checks.push(match result {
+            Ok(()) => Check {
+                name,
+                pass: true,
+                info: None,
+            },
+            Err(e) => Check {
+                name,
+                pass: false,
+                info: Some(format!("{e:#}")),
+            },
+        });
+    }
+
+    /// Checks that the attested `value` equals the hex encoded `expected` value, if any.
+    fn expect(&mut self, name: &'static str, expected: &Option<String>, value: &[u8]) {
+        if let Some(expected) = expected {
+            let result = match hex::decode(expected) {
+                Ok(expected) if expected == value => Ok(()),
+                Ok(_) => Err(anyhow!("expected {expected}, found {}", hex::encode(value))),
+                Err(e) => Err(anyhow!("invalid reference value {expected:?}: {e}")),
+            };
+            self.check(name, result);
+        }
+    }
+}
+
+/// Collateral to verify evidence with, overriding the collateral embedded in the evidence
+#[derive(Default)]
+pub struct Collateral {
+    /// CRLs as cached by `enarx platform {sgx,snp} cache-crl`
+    pub crls: Option<CrlList>,
+    /// TCB info as cached by `enarx platform sgx cache-tcb`
+    pub tcb: Option<Vec<u8>>,
+    /// The trusted Intel SGX root CA certificate
+    pub sgx_root: Option<Certificate>,
+    /// The AMD certificate chain of the VCEK, the ASK followed by the trusted ARK.
+    /// If only the ARK is given, the ASK is taken from the evidence.
+    pub snp_chain: Vec<Certificate>,
+}
+
+/// Expected values of a Keep, in the format printed by `enarx measure`
+#[derive(Debug, Default, Deserialize)]
+pub struct Reference {
+    pub sgx: Option<sgx::Reference>,
+    pub sev: Option<snp::Reference>,
+}
+
+/// Verifies `evidence` with `collateral` and checks it against `reference` at time `now`.
+///
+/// Errors are returned if the evidence is malformed, failed checks are recorded in the verdict.
+pub fn verify(
+    evidence: &Evidence,
+    collateral: &Collateral,
+    reference: &Reference,
+    now: SystemTime,
+) -> Result<Verdict> {
+    let mut verdict = Verdict::new(evidence.technology);
+    let report_data = evidence
+        .public_key
+        .as_ref()
+        .map(|key| evidence.technology.report_data(key));
+    let report_data = report_data.as_ref().map(|data| &data[..]);
+
+    match evidence.technology {
+        Technology::Kvm => verdict.check(
+            "Hardware attestation",
+            Err(anyhow!("KVM Keeps provide no attestation evidence")),
+        ),
+        Technology::Sgx => sgx::verify(
+            &mut verdict,
+            &evidence.data,
+            report_data,
+            collateral,
+            reference.sgx.as_ref(),
+            now,
+        )?,
+        Technology::Snp => snp::verify(
+            &mut verdict,
+            &evidence.data,
+            report_data,
+            collateral,
+            reference.sev.as_ref(),
+            now,
+        )?,
+    }
+
+    verdict.verified = verdict.checks.iter().all(|check| check.pass);
+    Ok(verdict)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/attestation");
+
+    fn read(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{DATA}/{name}")).unwrap()
+    }
+
+    fn now() -> SystemTime {
+        // The fixtures were generated on 2026-10-18.
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_281_600)
+    }
+
+    fn crls(name: &str) -> Option<CrlList> {
+        Some(CrlList::from_der(&read(name)).unwrap())
+    }
+
+    fn reference() -> Reference {
+        serde_json::from_slice(&read("reference.json")).unwrap()
+    }
+
+    fn failed(verdict: &Verdict) -> Vec<&str> {
+        verdict
+            .checks
+            .iter()
+            .filter(|check| !check.pass)
+            .map(|check| check.name)
+            .collect()
+    }
+
+    #[test]
+    fn sgx() {
+        let evidence = Evidence::from_csr(&read("sgx.csr")).unwrap();
+        assert_eq!(evidence.technology, Technology::Sgx);
+
+        let collateral = Collateral {
+            sgx_root: Some(
+                pki::read_certificates(&read("sgx-root.pem"))
+                    .unwrap()
+                    .remove(0),
+            ),
+            ..Default::default()
+        };
+        let verdict = verify(&evidence, &collateral, &reference(), now()).unwrap();
+        assert_eq!(failed(&verdict), Vec::<&str>::new());
+        assert!(verdict.verified);
+        assert_eq!(verdict.claims["tcb_status"], "UpToDate");
+
+        // Without a trust anchor the evidence is not verified.
+        let verdict = verify(&evidence, &Collateral::default(), &reference(), now()).unwrap();
+        assert_eq!(failed(&verdict), ["PCK certificate chain"]);
+
+        // A revoked PCK certificate fails.
+        let collateral = Collateral {
+            crls: crls("sgx-revoked.crls"),
+            ..collateral
+        };
+        let verdict = verify(&evidence, &collateral, &reference(), now()).unwrap();
+        assert_eq!(failed(&verdict), ["Certificate revocation"]);
+    }
+
+    #[test]
+    fn snp() {
+        let evidence = Evidence::from_csr(&read("snp.csr")).unwrap();
+        assert_eq!(evidence.technology, Technology::Snp);
+
+        let collateral = Collateral {
+            snp_chain: pki::read_certificates(&read("snp-chain.pem")).unwrap(),
+            ..Default::default()
+        };
+        let verdict = verify(&evidence, &collateral, &reference(), now()).unwrap();
+        assert_eq!(failed(&verdict), Vec::<&str>::new());
+        assert!(verdict.verified);
+
+        let mut reference = reference();
+        reference.sev.as_mut().unwrap().guest_svn = Some(100);
+        let verdict = verify(&evidence, &collateral, &reference, now()).unwrap();
+        assert_eq!(failed(&verdict), ["Guest SVN"]);
+
+        let collateral = Collateral {
+            crls: crls("snp-revoked.crls"),
+            ..collateral
+        };
+        let verdict = verify(&evidence, &collateral, &Reference::default(), now()).unwrap();
+        assert_eq!(failed(&verdict), ["Certificate revocation"]);
+    }
+
+    #[test]
+    fn snp_chain() {
+        let evidence = Evidence::from_csr(&read("snp-chain.csr")).unwrap();
+        let ark = pki::read_certificates(&read("snp-chain.pem"))
+            .unwrap()
+            .remove(1);
+
+        // The ASK is taken from the evidence, only the ARK must be trusted.
+        let collateral = Collateral {
+            snp_chain: vec![ark],
+            ..Default::default()
+        };
+        let verdict = verify(&evidence, &collateral, &reference(), now()).unwrap();
+        assert_eq!(failed(&verdict), Vec::<&str>::new());
+        assert!(verdict.checks.iter().any(|c| c.name == "VCEK TCB version"));
+        assert_eq!(verdict.claims["reported_tcb"], "0x7308000000000003");
+
+        // The ARK in the evidence is not trusted.
+        let verdict = verify(&evidence, &Collateral::default(), &reference(), now()).unwrap();
+        assert!(failed(&verdict).contains(&"VCEK certificate chain"));
+
+        // The ASK must be issued by the trusted ARK.
+        let collateral = Collateral {
+            snp_chain: pki::read_certificates(&read("sgx-root.pem")).unwrap(),
+            ..Default::default()
+        };
+        let verdict = verify(&evidence, &collateral, &reference(), now()).unwrap();
+        assert!(failed(&verdict).contains(&"VCEK certificate chain"));
+    }
+
+    #[test]
+    fn kvm() {
+        let evidence = Evidence::from_csr(&read("kvm.csr")).unwrap();
+        assert_eq!(evidence.technology, Technology::Kvm);
+        let verdict = verify(&evidence, &Collateral::default(), &reference(), now()).unwrap();
+        assert!(!verdict.verified);
+    }
+
+    #[test]
+    fn tampered() {
+        let mut csr = read("snp.csr");
+        let n = csr.len();
+        csr[n - 80] ^= 1;
+        assert!(Evidence::from_csr(&csr).is_err());
+
+        let mut evidence = Evidence::from_csr(&read("snp.csr")).unwrap();
+        evidence.data.truncate(evidence.data.len() / 2);
+        assert!(verify(&evidence, &Collateral::default(), &reference(), now()).is_err());
+
+        let mut evidence = Evidence::from_csr(&read("sgx.csr")).unwrap();
+        evidence.public_key = Some(vec![0; 91]);
+        let verdict = verify(&evidence, &Collateral::default(), &reference(), now()).unwrap();
+        assert!(failed(&verdict).contains(&"Report data"));
+    }
+}
@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! X.509 certificate and CRL verification
+
+use crate::caching::CrlList;
+
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context, Result};
+use der::oid::ObjectIdentifier;
+use der::{Decode, Encode};
+use ring::signature::{self, UnparsedPublicKey, VerificationAlgorithm};
+use rsa::pkcs1::RsaPssParams;
+use x509_cert::crl::CertificateList;
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::Certificate;
+
+const ECDSA_WITH_SHA_256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA_384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const RSASSA_PSS: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.10");
+const ID_SHA_384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+const SECP_256_R_1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const SECP_384_R_1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+
+/// Reads one or more certificates from PEM or DER encoded `bytes`.
+pub fn read_certificates(bytes: &[u8]) -> Result<Vec<Certificate>> {
+    if bytes.starts_with(b"-----BEGIN") {
+        rustls_pemfile::certs(&mut &bytes[..])
+            .context("Failed to PEM-decode certificates")?
+            .iter()
+            .map(|der| Certificate::from_der(der).context("Failed to DER-decode certificate"))
+            .collect()
+    } else {
+        Ok(vec![
+            Certificate::from_der(bytes).context("Failed to DER-decode certificate")?
+        ])
+    }
+}
+
+/// Returns the raw public key of `spki`, e.g. the SEC1-encoded point of an EC key.
+pub fn public_key(spki: &SubjectPublicKeyInfoOwned) -> Result<&[u8]> {
+    spki.subject_public_key
+        .as_bytes()
+        .ok_or_else(|| anyhow!("public key is not octet-aligned"))
+}
+
+fn algorithm(
+    key: &SubjectPublicKeyInfoOwned,
+    algorithm: &AlgorithmIdentifierOwned,
+) -> Result<&'static dyn VerificationAlgorithm> {
+    let alg: &'static dyn VerificationAlgorithm = match algorithm.oid {
+        ECDSA_WITH_SHA_256 | ECDSA_WITH_SHA_384 => {
+            let curve: ObjectIdentifier = key
+                .algorithm
+                .parameters
+                .as_ref()
+                .ok_or_else(|| anyhow!("EC public key is missing the curve"))?
+                .decode_as()
+                .context("Failed to decode EC curve")?;
+            match (algorithm.oid, curve) {
+                (ECDSA_WITH_SHA_256, SECP_256_R_1) => &signature::ECDSA_P256_SHA256_ASN1,
+                (ECDSA_WITH_SHA_384, SECP_256_R_1) => &signature::ECDSA_P256_SHA384_ASN1,
+                (ECDSA_WITH_SHA_256, SECP_384_R_1) => &signature::ECDSA_P384_SHA256_ASN1,
+                (ECDSA_WITH_SHA_384, SECP_384_R_1) => &signature::ECDSA_P384_SHA384_ASN1,
+                (_, curve) => bail!("unsupported EC curve {curve}"),
+            }
+        }
+        RSASSA_PSS => {
+            let params = algorithm
+                .parameters
+                .as_ref()
+                .ok_or_else(|| anyhow!("RSASSA-PSS signature is missing its parameters"))?
+                .to_der()?;
+            let params = RsaPssParams::from_der(&params)
+                .context("Failed to decode RSASSA-PSS parameters")?;
+            if params.hash.oid != ID_SHA_384 {
+                bail!("unsupported RSASSA-PSS hash algorithm {}", params.hash.oid);
+            }
+            &signature::RSA_PSS_2048_8192_SHA384
+        }
+        oid => bail!("unsupported signature algorithm {oid}"),
+    };
+    Ok(alg)
+}
+
+/// Verifies the X.509 `signature` over `message` by `key` with the given signature `algorithm`.
+pub fn verify_signature(
+    key: &SubjectPublicKeyInfoOwned,
+    algorithm: &AlgorithmIdentifierOwned,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    let alg = self::algorithm(key, algorithm)?;
+    UnparsedPublicKey::new(alg, public_key(key)?)
+        .verify(message, signature)
+        .map_err(|_| anyhow!("invalid signature"))
+}
+
+/// Verifies that `cert` was issued by `issuer`.
+pub fn verify_certificate(cert: &Certificate, issuer: &Certificate) -> Result<()> {
+    if cert.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        bail!(
+            "certificate `{}` was not issued by `{}`",
+            cert.tbs_certificate.subject,
+            issuer.tbs_certificate.subject
+        );
+    }
+    let signature = cert
+        .signature
+        .as_bytes()
+        .ok_or_else(|| anyhow!("certificate signature is not octet-aligned"))?;
+    verify_signature(
+        &issuer.tbs_certificate.subject_public_key_info,
+        &cert.signature_algorithm,
+        &cert.tbs_certificate.to_der()?,
+        signature,
+    )
+    .with_context(|| {
+        format!(
+            "Failed to verify certificate `{}`",
+            cert.tbs_certificate.subject
+        )
+    })
+}
+
+/// Verifies that `crl` was issued by `issuer`.
+pub fn verify_crl(crl: &CertificateList, issuer: &Certificate) -> Result<()> {
+    if crl.tbs_cert_list.issuer != issuer.tbs_certificate.subject {
+        bail!(
+            "CRL of `{}` was not issued by `{}`",
+            crl.tbs_cert_list.issuer,
+            issuer.tbs_certificate.subject
+        );
+    }
+    let signature = crl
+        .signature
+        .as_bytes()
+        .ok_or_else(|| anyhow!("CRL signature is not octet-aligned"))?;
+    verify_signature(
+        &issuer.tbs_certificate.subject_public_key_info,
+        &crl.signature_algorithm,
+        &crl.tbs_cert_list.to_der()?,
+        signature,
+    )
+    .with_context(|| format!("Failed to verify CRL of `{}`", crl.tbs_cert_list.issuer))
+}
+
+/// Verifies a certificate chain, which is ordered from the leaf to the self-signed root.
+///
+/// If `anchor` is given, the root of the chain must be identical to it.
+pub fn verify_chain(
+    chain: &[Certificate],
+    anchor: Option<&Certificate>,
+    now: SystemTime,
+) -> Result<()> {
+    let root = chain
+        .last()
+        .ok_or_else(|| anyhow!("empty certificate chain"))?;
+
+    for cert in chain {
+        let validity = &cert.tbs_certificate.validity;
+        if now < validity.not_before.to_system_time() || now > validity.not_after.to_system_time() {
+            bail!(
+                "certificate `{}` is not valid at this time",
+                cert.tbs_certificate.subject
+            );
+        }
+    }
+    for pair in chain.windows(2) {
+        verify_certificate(&pair[0], &pair[1])?;
+    }
+    verify_certificate(root, root)?;
+
+    if let Some(anchor) = anchor {
+        if root.to_der()? != anchor.to_der()? {
+            bail!(
+                "root certificate `{}` is not trusted",
+                root.tbs_certificate.subject
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Verifies a certificate chain, whose root must be one of the trusted `anchors`.
+pub fn verify_chain_to(
+    chain: &[Certificate],
+    anchors: &[Certificate],
+    now: SystemTime,
+) -> Result<()> {
+    let root = chain
+        .last()
+        .ok_or_else(|| anyhow!("empty certificate chain"))?
+        .to_der()?;
+    for anchor in anchors {
+        if anchor.to_der()? == root {
+            return verify_chain(chain, Some(anchor), now);
+        }
+    }
+    verify_chain(chain, None, now)?;
+    bail!(
+        "root certificate `{}` is not trusted",
+        chain[chain.len() - 1].tbs_certificate.subject
+    )
+}
+
+/// Checks the certificates of `chain` against the CRLs in `crls`.
+///
+/// Only CRLs issued by a certificate of `chain` are considered, each of them is verified
+/// and must be current. Returns the number of CRLs checked.
+pub fn check_revocation(chain: &[Certificate], crls: &CrlList, now: SystemTime) -> Result<usize> {
+    let mut checked = 0;
+    for (url, crl) in crls.entries() {
+        let issuer = match chain
+            .iter()
+            .find(|cert| cert.tbs_certificate.subject == crl.tbs_cert_list.issuer)
+        {
+            Some(issuer) => issuer,
+            None => continue,
+        };
+        verify_current_crl(url, crl, issuer, now)?;
+
+        let revoked = crl.tbs_cert_list.revoked_certificates.iter().flatten();
+        for entry in revoked {
+            if let Some(cert) = chain.iter().find(|cert| {
+                cert.tbs_certificate.issuer == crl.tbs_cert_list.issuer
+                    && cert.tbs_certificate.serial_number == entry.serial_number
+            }) {
+                bail!("certificate `{}` was revoked", cert.tbs_certificate.subject);
+            }
+        }
+        checked += 1;
+    }
+    Ok(checked)
+}
+
+/// Verifies that every CRL in `crls` was issued by one of the trusted `issuers` and is current.
+pub fn verify_crls(crls: &CrlList, issuers: &[Certificate], now: SystemTime) -> Result<()> {
+    for (url, crl) in crls.entries() {
+        let issuer = issuers
+            .iter()
+            .find(|cert| cert.tbs_certificate.subject == crl.tbs_cert_list.issuer)
+            .ok_or_else(|| {
+                anyhow!(
+                    "CRL from `{url}` was issued by untrusted `{}`",
+                    crl.tbs_cert_list.issuer
+                )
+            })?;
+        verify_current_crl(url, crl, issuer, now)?;
+    }
+    Ok(())
+}
+
+fn verify_current_crl(
+    url: &str,
+    crl: &CertificateList,
+    issuer: &Certificate,
+    now: SystemTime,
+) -> Result<()> {
+    verify_crl(crl, issuer).with_context(|| format!("Invalid CRL from `{url}`"))?;
+    if let Some(next_update) = crl.tbs_cert_list.next_update {
+        if now > next_update.to_system_time() {
+            bail!("CRL from `{url}` is outdated");
+        }
+    }
+    Ok(())
+}
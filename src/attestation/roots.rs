@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Root certificates of the platform collateral, which are pinned in Enarx
+//!
+//! Collateral fetched from a vendor service or a mirror is only accepted if it is
+//! rooted in one of these certificates, or in a root passed explicitly with `--trust-root`.
+//! Collateral of processor models without a pinned root is skipped otherwise.
+
+use super::pki;
+
+use anyhow::{Context, Result};
+use x509_cert::Certificate;
+
+/// AMD Root Keys (ARK) of the processor models, as published by the AMD KDS
+///
+/// TODO: pin the ARK of Genoa, until then it has to be passed with `--trust-root`.
+const AMD_ARKS: &[(&str, &str)] = &[("Milan", include_str!("roots/ark-milan.pem"))];
+
+/// Intel SGX root CA certificates
+///
+/// TODO: pin the Intel SGX Root CA, until then it has to be passed with `--trust-root`.
+const INTEL_ROOTS: &[&str] = &[];
+
+fn read(pem: &str) -> Result<Certificate> {
+    pki::read_certificates(pem.as_bytes())?
+        .into_iter()
+        .next()
+        .context("No pinned root certificate found")
+}
+
+/// Returns the pinned AMD ARKs.
+pub fn amd_arks() -> Result<Vec<Certificate>> {
+    AMD_ARKS.iter().map(|(_, pem)| read(pem)).collect()
+}
+
+/// Returns whether the ARK of the AMD processor model `product` is pinned.
+pub fn is_amd_pinned(product: &str) -> bool {
+    AMD_ARKS.iter().any(|(name, _)| *name == product)
+}
+
+/// Returns the pinned Intel SGX root CAs.
+pub fn intel_roots() -> Result<Vec<Certificate>> {
+    INTEL_ROOTS.iter().map(|pem| read(pem)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn amd() {
+        let arks = amd_arks().unwrap();
+        assert_eq!(arks.len(), AMD_ARKS.len());
+
+        // The certificate chain published by the AMD KDS is rooted in the pinned ARK.
+        let chain = pki::read_certificates(include_bytes!("../backend/sev/snp/testdata/chain.pem"))
+            .unwrap();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_281_600);
+        pki::verify_chain_to(&chain, &arks, now).unwrap();
+
+        assert!(is_amd_pinned("Milan"));
+        assert!(!is_amd_pinned("Genoa"));
+    }
+}
@@ -0,0 +1,580 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verification of SGX ECDSA quotes
+//!
+//! See the Intel SGX ECDSA Quote Library API for the quote format and the
+//! Intel SGX PCK Certificate and CRL Profile for the PCK certificate extensions.
+
+use super::{pki, Collateral, Verdict};
+use crate::backend::sgx::TcbPackage;
+use crate::caching::CrlList;
+
+use std::ops::Range;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use chrono::DateTime;
+use der::asn1::OctetStringRef;
+use der::oid::ObjectIdentifier;
+use der::{Any, Decode, Sequence};
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use x509_cert::Certificate;
+
+const HEADER_VERSION: Range<usize> = 0..2;
+const HEADER_ATT_KEY_TYPE: Range<usize> = 2..4;
+const HEADER_TEE_TYPE: Range<usize> = 4..8;
+const HEADER_SIZE: usize = 48;
+const REPORT_SIZE: usize = 384;
+
+const REPORT_ATTRIBUTES: Range<usize> = 48..64;
+const REPORT_MRENCLAVE: Range<usize> = 64..96;
+const REPORT_MRSIGNER: Range<usize> = 128..160;
+const REPORT_ISVPRODID: Range<usize> = 256..258;
+const REPORT_ISVSVN: Range<usize> = 258..260;
+const REPORT_DATA: Range<usize> = 320..384;
+
+/// Debug flag in the enclave attributes
+const ATTRIBUTE_DEBUG: u8 = 1 << 1;
+/// ECDSA-256-with-P-256 curve attestation key type
+const ATT_KEY_TYPE_ECDSA_P256: u16 = 2;
+/// Certification data type of a PEM encoded PCK certificate chain
+const CERT_DATA_PCK_CHAIN: u16 = 5;
+
+const SGX_EXTENSION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1");
+const SGX_TCB: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2");
+const SGX_PCESVN: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2.17");
+const SGX_FMSPC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.4");
+
+/// Evidence of an SGX Keep as produced by `get_quote_and_collateral`
+#[derive(Sequence)]
+struct SgxEvidence<'a> {
+    #[asn1(type = "OCTET STRING")]
+    quote: &'a [u8],
+    crl: CrlList,
+    tcb: TcbPackage<'a>,
+}
+
+/// Expected SGX values, as printed by `enarx measure`
+#[derive(Debug, Default, Deserialize)]
+pub struct Reference {
+    pub mrenclave: Option<String>,
+    pub mrsigner: Option<String>,
+    pub isv_prod_id: Option<u16>,
+    /// Minimum ISV SVN
+    pub isv_svn: Option<u16>,
+}
+
+/// A parsed SGX ECDSA quote (version 3)
+pub struct Quote<'a> {
+    /// The signed part of the quote, the header and the report body
+    signed: &'a [u8],
+    /// The report body
+    report: &'a [u8],
+    signature: &'a [u8],
+    attestation_key: &'a [u8],
+    qe_report: &'a [u8],
+    qe_report_signature: &'a [u8],
+    qe_auth_data: &'a [u8],
+    certification_data_type: u16,
+    certification_data: &'a [u8],
+}
+
+fn take<'a>(buf: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    ensure!(buf.len() >= n, "quote is truncated");
+    let (head, tail) = buf.split_at(n);
+    *buf = tail;
+    Ok(head)
+}
+
+fn take_u16(buf: &mut &[u8]) -> Result<u16> {
+    Ok(u16::from_le_bytes(take(buf, 2)?.try_into()?))
+}
+
+fn take_u32(buf: &mut &[u8]) -> Result<u32> {
+    Ok(u32::from_le_bytes(take(buf, 4)?.try_into()?))
+}
+
+impl<'a> Quote<'a> {
+    /// Parses an SGX ECDSA quote.
+    pub fn parse(quote: &'a [u8]) -> Result<Self> {
+        let mut buf = quote;
+        let signed = take(&mut buf, HEADER_SIZE + REPORT_SIZE)?;
+
+        let version = u16::from_le_bytes(signed[HEADER_VERSION].try_into()?);
+        ensure!(version == 3, "unsupported quote version {version}");
+        let key_type = u16::from_le_bytes(signed[HEADER_ATT_KEY_TYPE].try_into()?);
+        ensure!(
+            key_type == ATT_KEY_TYPE_ECDSA_P256,
+            "unsupported attestation key type {key_type}"
+        );
+        let tee_type = u32::from_le_bytes(signed[HEADER_TEE_TYPE].try_into()?);
+        ensure!(tee_type == 0, "quote is not an SGX quote");
+
+        let len = take_u32(&mut buf)? as usize;
+        let mut buf = take(&mut buf, len)?;
+        let signature = take(&mut buf, 64)?;
+        let attestation_key = take(&mut buf, 64)?;
+        let qe_report = take(&mut buf, REPORT_SIZE)?;
+        let qe_report_signature = take(&mut buf, 64)?;
+        let qe_auth_len = take_u16(&mut buf)? as usize;
+        let qe_auth_data = take(&mut buf, qe_auth_len)?;
+        let certification_data_type = take_u16(&mut buf)?;
+        let certification_len = take_u32(&mut buf)? as usize;
+        let certification_data = take(&mut buf, certification_len)?;
+
+        Ok(Self {
+            signed,
+            report: &signed[HEADER_SIZE..],
+            signature,
+            attestation_key,
+            qe_report,
+            qe_report_signature,
+            qe_auth_data,
+            certification_data_type,
+            certification_data,
+        })
+    }
+
+    /// Returns the PCK certificate chain embedded in the quote, leaf first.
+    pub fn pck_chain(&self) -> Result<Vec<Certificate>> {
+        ensure!(
+            self.certification_data_type == CERT_DATA_PCK_CHAIN,
+            "unsupported certification data type {}",
+            self.certification_data_type
+        );
+        // The PEM chain may be NUL-terminated.
+        let pem = self
+            .certification_data
+            .split(|b| *b == 0)
+            .next()
+            .unwrap_or_default();
+        pki::read_certificates(pem)
+    }
+
+    /// Verifies the quote signatures, given the PCK certificate.
+    pub fn verify(&self, pck: &Certificate) -> Result<()> {
+        let mut key = vec![0x04];
+        key.extend_from_slice(self.attestation_key);
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &key)
+            .verify(self.signed, self.signature)
+            .map_err(|_| anyhow!("invalid quote signature"))?;
+
+        let pck_key = pki::public_key(&pck.tbs_certificate.subject_public_key_info)?;
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, pck_key)
+            .verify(self.qe_report, self.qe_report_signature)
+            .map_err(|_| anyhow!("invalid QE report signature"))?;
+
+        // The QE binds the attestation key to its report.
+        let mut hasher = Sha256::new();
+        hasher.update(self.attestation_key);
+        hasher.update(self.qe_auth_data);
+        let data = &self.qe_report[REPORT_DATA];
+        ensure!(
+            data[..32] == hasher.finalize()[..] && data[32..].iter().all(|b| *b == 0),
+            "attestation key is not bound to the QE report"
+        );
+        Ok(())
+    }
+
+    pub fn mrenclave(&self) -> &[u8] {
+        &self.report[REPORT_MRENCLAVE]
+    }
+
+    pub fn mrsigner(&self) -> &[u8] {
+        &self.report[REPORT_MRSIGNER]
+    }
+
+    pub fn isv_prod_id(&self) -> u16 {
+        u16::from_le_bytes([
+            self.report[REPORT_ISVPRODID.start],
+            self.report[REPORT_ISVPRODID.start + 1],
+        ])
+    }
+
+    pub fn isv_svn(&self) -> u16 {
+        u16::from_le_bytes([
+            self.report[REPORT_ISVSVN.start],
+            self.report[REPORT_ISVSVN.start + 1],
+        ])
+    }
+
+    pub fn debug(&self) -> bool {
+        self.report[REPORT_ATTRIBUTES.start] & ATTRIBUTE_DEBUG != 0
+    }
+
+    pub fn report_data(&self) -> &[u8] {
+        &self.report[REPORT_DATA]
+    }
+}
+
+#[derive(Sequence)]
+struct SgxExtensionEntry {
+    id: ObjectIdentifier,
+    value: Any,
+}
+
+/// The TCB of a platform as certified by its PCK certificate
+#[derive(Debug, PartialEq, Eq)]
+struct PckTcb {
+    components: [u16; 16],
+    pcesvn: u16,
+    fmspc: Vec<u8>,
+}
+
+impl PckTcb {
+    fn from_certificate(pck: &Certificate) -> Result<Self> {
+        let ext = pck
+            .tbs_certificate
+            .extensions
+            .iter()
+            .flatten()
+            .find(|ext| ext.extn_id == SGX_EXTENSION)
+            .ok_or_else(|| anyhow!("PCK certificate is missing the SGX extension"))?;
+        let entries = Vec::<SgxExtensionEntry>::from_der(ext.extn_value.as_bytes())
+            .context("Failed to decode SGX extension")?;
+
+        let mut tcb = None;
+        let mut fmspc = None;
+        for entry in entries {
+            match entry.id {
+                SGX_TCB => tcb = Some(entry.value.decode_as::<Vec<SgxExtensionEntry>>()?),
+                SGX_FMSPC => {
+                    fmspc = Some(
+                        entry
+                            .value
+                            .decode_as::<OctetStringRef<'_>>()?
+                            .as_bytes()
+                            .to_vec(),
+                    )
+                }
+                _ => {}
+            }
+        }
+        let tcb = tcb.ok_or_else(|| anyhow!("SGX extension is missing the TCB"))?;
+        let fmspc = fmspc.ok_or_else(|| anyhow!("SGX extension is missing the FMSPC"))?;
+
+        let mut components = [0; 16];
+        let mut pcesvn = None;
+        for entry in tcb {
+            if entry.id == SGX_PCESVN {
+                pcesvn = Some(entry.value.decode_as::<u16>()?);
+                continue;
+            }
+            // The component SVNs are `SGX_TCB.1` to `SGX_TCB.16`.
+            if entry.id.parent() == Some(SGX_TCB) {
+                if let Some(i @ 1..=16) = entry.id.arcs().last() {
+                    components[i as usize - 1] = entry.value.decode_as::<u16>()?;
+                }
+            }
+        }
+        let pcesvn = pcesvn.ok_or_else(|| anyhow!("SGX TCB is missing the PCESVN"))?;
+
+        Ok(Self {
+            components,
+            pcesvn,
+            fmspc,
+        })
+    }
+}
+
+/// TCB info of an SGX platform model, as published by Intel
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbInfo {
+    fmspc: String,
+    next_update: String,
+    tcb_levels: Vec<TcbLevel>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TcbLevel {
+    tcb: Tcb,
+    tcb_status: String,
+}
+
+#[derive(Deserialize)]
+struct Tcb {
+    sgxtcbcomponents: Vec<TcbComponent>,
+    pcesvn: u16,
+}
+
+#[derive(Deserialize)]
+struct TcbComponent {
+    svn: u16,
+}
+
+/// Returns the raw JSON text of the value of the top-level `key` in `json`.
+///
+/// The TCB info signature covers the exact bytes of the `tcbInfo` object.
+fn raw_value<'a>(json: &'a str, key: &str) -> Result<&'a str> {
+    let start = json
+        .find(&format!("\"{key}\""))
+        .and_then(|i| json[i..].find('{').map(|j| i + j))
+        .ok_or_else(|| anyhow!("missing `{key}`"))?;
+
+    let mut depth = 0;
+    let mut string = false;
+    let mut escape = false;
+    for (i, c) in json[start..].char_indices() {
+        match c {
+            _ if escape => escape = false,
+            '\\' if string => escape = true,
+            '"' => string = !string,
+            '{' if !string => depth += 1,
+            '}' if !string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(&json[start..=start + i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    bail!("unterminated `{key}`")
+}
+
+/// Verifies that the TCB info of `tcb` is signed by a certificate issued by `root`,
+/// is current and applies to the platform model `fmspc`.
+pub(crate) fn verify_tcb_info(
+    tcb: &TcbPackage<'_>,
+    root: Option<&Certificate>,
+    fmspc: &[u8],
+    now: SystemTime,
+) -> Result<TcbInfo> {
+    pki::verify_chain(&tcb.crts, root, now).context("Invalid TCB signing certificate chain")?;
+    let signer = tcb
+        .crts
+        .first()
+        .ok_or_else(|| anyhow!("empty TCB signing certificate chain"))?;
+
+    let json = std::str::from_utf8(tcb.report).context("TCB info is not valid UTF-8")?;
+    let raw = raw_value(json, "tcbInfo")?;
+    #[derive(Deserialize)]
+    struct Signed {
+        signature: String,
+    }
+    let signature = hex::decode(serde_json::from_str::<Signed>(json)?.signature)
+        .context("Failed to decode TCB info signature")?;
+    let key = pki::public_key(&signer.tbs_certificate.subject_public_key_info)?;
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, key)
+        .verify(raw.as_bytes(), &signature)
+        .map_err(|_| anyhow!("invalid TCB info signature"))?;
+
+    let info: TcbInfo = serde_json::from_str(raw).context("Failed to decode TCB info")?;
+    let next_update = DateTime::parse_from_rfc3339(&info.next_update)
+        .context("Failed to parse TCB info next update")?;
+    ensure!(SystemTime::from(next_update) >= now, "TCB info is outdated");
+    ensure!(
+        hex::decode(&info.fmspc)? == fmspc,
+        "TCB info is for FMSPC {}, the platform has {}",
+        info.fmspc,
+        hex::encode_upper(fmspc)
+    );
+    Ok(info)
+}
+
+/// Verifies the TCB info, which must be signed by a certificate issued by `root`,
+/// and returns the TCB status of the platform.
+fn tcb_status(
+    tcb: &TcbPackage<'_>,
+    root: Option<&Certificate>,
+    pck: &PckTcb,
+    now: SystemTime,
+) -> Result<String> {
+    let info = verify_tcb_info(tcb, root, &pck.fmspc, now)?;
+    info.tcb_levels
+        .into_iter()
+        .find(|level| {
+            level.tcb.sgxtcbcomponents.len() == 16
+                && level.tcb.pcesvn <= pck.pcesvn
+                && level
+                    .tcb
+                    .sgxtcbcomponents
+                    .iter()
+                    .zip(pck.components)
+                    .all(|(level, svn)| level.svn <= svn)
+        })
+        .map(|level| level.tcb_status)
+        .ok_or_else(|| anyhow!("platform TCB is not supported"))
+}
+
+pub(super) fn verify(
+    verdict: &mut Verdict,
+    data: &[u8],
+    report_data: Option<&[u8]>,
+    collateral: &Collateral,
+    reference: Option<&Reference>,
+    now: SystemTime,
+) -> Result<()> {
+    let evidence = SgxEvidence::from_der(data).context("Failed to decode SGX evidence")?;
+    let quote = Quote::parse(evidence.quote).context("Failed to parse SGX quote")?;
+    let chain = quote.pck_chain()?;
+    let pck = chain
+        .first()
+        .ok_or_else(|| anyhow!("empty PCK certificate chain"))?;
+
+    verdict.claim("mrenclave", hex::encode(quote.mrenclave()));
+    verdict.claim("mrsigner", hex::encode(quote.mrsigner()));
+    verdict.claim("isv_prod_id", quote.isv_prod_id().to_string());
+    verdict.claim("isv_svn", quote.isv_svn().to_string());
+
+    verdict.check(
+        "PCK certificate chain",
+        pki::verify_chain(&chain, collateral.sgx_root.as_ref(), now).and_then(|_| {
+            if collateral.sgx_root.is_none() {
+                bail!("no trusted Intel SGX root certificate given");
+            }
+            Ok(())
+        }),
+    );
+
+    let crls = collateral.crls.as_ref().unwrap_or(&evidence.crl);
+    verdict.check(
+        "Certificate revocation",
+        pki::check_revocation(&chain, crls, now).and_then(|n| {
+            ensure!(n > 0, "no CRL applies to the PCK certificate chain");
+            Ok(())
+        }),
+    );
+
+    verdict.check("Quote signature", quote.verify(pck));
+
+    let tcb = match collateral.tcb {
+        Some(ref tcb) => Some(TcbPackage::from_der(tcb).context("Failed to decode TCB info")?),
+        None => None,
+    };
+    let status = PckTcb::from_certificate(pck).and_then(|pck| {
+        let root = collateral.sgx_root.as_ref().or(chain.last());
+        tcb_status(tcb.as_ref().unwrap_or(&evidence.tcb), root, &pck, now)
+    });
+    match status {
+        Ok(status) => {
+            verdict.claim("tcb_status", status.clone());
+            verdict.check(
+                "TCB status",
+                match status.as_str() {
+                    "UpToDate"
+                    | "SWHardeningNeeded"
+                    | "ConfigurationNeeded"
+                    | "ConfigurationAndSWHardeningNeeded" => Ok(()),
+                    _ => Err(anyhow!("platform TCB status is {status}")),
+                },
+            );
+        }
+        Err(e) => verdict.check("TCB status", Err(e)),
+    }
+
+    match report_data {
+        Some(report_data) => verdict.check(
+            "Report data",
+            if quote.report_data() == report_data {
+                Ok(())
+            } else {
+                Err(anyhow!("quote is not bound to the CSR public key"))
+            },
+        ),
+        None => verdict.claim("report_data", hex::encode(quote.report_data())),
+    }
+    verdict.check(
+        "Production enclave",
+        if quote.debug() {
+            Err(anyhow!("enclave was launched in debug mode"))
+        } else {
+            Ok(())
+        },
+    );
+
+    if let Some(reference) = reference {
+        verdict.expect("MRENCLAVE", &reference.mrenclave, quote.mrenclave());
+        verdict.expect("MRSIGNER", &reference.mrsigner, quote.mrsigner());
+        if let Some(isv_prod_id) = reference.isv_prod_id {
+            verdict.check(
+                "ISVPRODID",
+                if quote.isv_prod_id() == isv_prod_id {
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "expected {isv_prod_id}, found {}",
+                        quote.isv_prod_id()
+                    ))
+                },
+            );
+        }
+        if let Some(isv_svn) = reference.isv_svn {
+            verdict.check(
+                "ISVSVN",
+                if quote.isv_svn() >= isv_svn {
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "expected at least {isv_svn}, found {}",
+                        quote.isv_svn()
+                    ))
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/attestation");
+    const FMSPC: [u8; 6] = [0x00, 0x90, 0x6E, 0xD5, 0x00, 0x00];
+
+    fn read(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{DATA}/{name}")).unwrap()
+    }
+
+    fn now() -> SystemTime {
+        // The fixtures were generated on 2026-10-18.
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_281_600)
+    }
+
+    #[test]
+    fn tcb_info() {
+        let root = pki::read_certificates(&read("sgx-root.pem"))
+            .unwrap()
+            .remove(0);
+        let tcb = read("sgx-tcb.der");
+        let package = TcbPackage::from_der(&tcb).unwrap();
+        verify_tcb_info(&package, Some(&root), &FMSPC, now()).unwrap();
+
+        // The TCB info must apply to the platform.
+        assert!(verify_tcb_info(&package, Some(&root), &[0; 6], now()).is_err());
+
+        // The TCB info must be signed by a certificate of the trusted root.
+        let other = pki::read_certificates(&read("snp-chain.pem")).unwrap();
+        assert!(verify_tcb_info(&package, other.last(), &FMSPC, now()).is_err());
+
+        // Outdated TCB info is rejected.
+        let later = SystemTime::UNIX_EPOCH + Duration::from_secs(4_800_000_000);
+        assert!(verify_tcb_info(&package, Some(&root), &FMSPC, later).is_err());
+
+        // Tampered TCB info is rejected.
+        let json = std::str::from_utf8(package.report).unwrap();
+        let tampered = json.replacen("OutOfDate", "UpToDate", 1);
+        let package = TcbPackage {
+            crts: package.crts,
+            report: tampered.as_bytes(),
+        };
+        assert!(verify_tcb_info(&package, Some(&root), &FMSPC, now()).is_err());
+    }
+
+    #[test]
+    fn raw() {
+        let json = r#"{"tcbInfo":{"id":"SGX","a":{"b":"}"}},"signature":"00"}"#;
+        assert_eq!(
+            raw_value(json, "tcbInfo").unwrap(),
+            r#"{"id":"SGX","a":{"b":"}"}}"#
+        );
+        assert!(raw_value(json, "missing").is_err());
+    }
+}
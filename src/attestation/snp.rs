@@ -0,0 +1,394 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verification of SEV-SNP attestation reports
+//!
+//! See the SEV Secure Nested Paging Firmware ABI Specification for the report format.
+
+use super::{pki, Collateral, Verdict};
+use crate::caching::CrlList;
+
+use std::ops::Range;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use der::oid::ObjectIdentifier;
+use der::{Decode, Sequence};
+use ring::signature::{UnparsedPublicKey, ECDSA_P384_SHA384_FIXED};
+use serde::Deserialize;
+use x509_cert::Certificate;
+
+const REPORT_VERSION: Range<usize> = 0x00..0x04;
+const REPORT_GUEST_SVN: Range<usize> = 0x04..0x08;
+const REPORT_POLICY: Range<usize> = 0x08..0x10;
+const REPORT_FAMILY_ID: Range<usize> = 0x10..0x20;
+const REPORT_IMAGE_ID: Range<usize> = 0x20..0x30;
+const REPORT_SIG_ALGO: Range<usize> = 0x34..0x38;
+const REPORT_DATA: Range<usize> = 0x50..0x90;
+const REPORT_MEASUREMENT: Range<usize> = 0x90..0xC0;
+const REPORT_ID_KEY_DIGEST: Range<usize> = 0xE0..0x110;
+const REPORT_AUTHOR_KEY_DIGEST: Range<usize> = 0x110..0x140;
+const REPORT_REPORTED_TCB: Range<usize> = 0x180..0x188;
+const REPORT_CHIP_ID: Range<usize> = 0x1A0..0x1E0;
+const REPORT_SIGNED: Range<usize> = 0x00..0x2A0;
+const REPORT_SIG_R: Range<usize> = 0x2A0..0x2E8;
+const REPORT_SIG_S: Range<usize> = 0x2E8..0x330;
+const REPORT_SIZE: usize = 0x4A0;
+
+/// ECDSA P-384 with SHA-384 signature algorithm
+const SIG_ALGO_ECDSA_P384_SHA384: u32 = 1;
+/// Debugging allowed policy bit
+const POLICY_DEBUG: u64 = 1 << 19;
+
+/// VCEK extensions with the security patch levels of the TCB, and their bytes in a TCB version
+const VCEK_SPLS: [(&str, ObjectIdentifier, usize); 4] = [
+    (
+        "boot loader",
+        ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.1"),
+        0,
+    ),
+    (
+        "TEE",
+        ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.2"),
+        1,
+    ),
+    (
+        "SNP",
+        ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.3"),
+        6,
+    ),
+    (
+        "microcode",
+        ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.8"),
+        7,
+    ),
+];
+/// VCEK extension with the ID of the chip
+const VCEK_HWID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.4");
+
+/// Evidence of an SNP Keep as produced by `SnpEvidence::read`
+#[derive(Sequence)]
+struct SnpEvidence {
+    vcek: Certificate,
+    crl: CrlList,
+    /// The ASK and ARK, which issued the VCEK
+    #[asn1(context_specific = "0", optional = "true")]
+    chain: Option<Vec<Certificate>>,
+    /// The reported TCB version, for which the host fetched the VCEK, only a hint for errors
+    #[asn1(context_specific = "1", optional = "true")]
+    tcb: Option<u64>,
+}
+
+/// The SNP extension of a CSR
+#[derive(Sequence)]
+struct SnpExtension<'a> {
+    evidence: SnpEvidence,
+    #[asn1(type = "OCTET STRING")]
+    report: &'a [u8],
+}
+
+/// Expected SNP values, as printed by `enarx measure`
+#[derive(Debug, Default, Deserialize)]
+pub struct Reference {
+    pub measurement: Option<String>,
+    pub family_id: Option<String>,
+    pub image_id: Option<String>,
+    /// Minimum guest SVN
+    pub guest_svn: Option<u32>,
+    pub policy: Option<u64>,
+    pub id_key_digest: Option<String>,
+    pub author_key_digest: Option<String>,
+}
+
+/// A parsed SNP attestation report
+pub struct Report<'a>(&'a [u8]);
+
+impl<'a> Report<'a> {
+    /// Parses an SNP attestation report.
+    pub fn parse(report: &'a [u8]) -> Result<Self> {
+        ensure!(
+            report.len() == REPORT_SIZE,
+            "invalid attestation report size {}",
+            report.len()
+        );
+        let report = Self(report);
+        ensure!(
+            report.u32(REPORT_VERSION) >= 2,
+            "unsupported attestation report version {}",
+            report.u32(REPORT_VERSION)
+        );
+        ensure!(
+            report.u32(REPORT_SIG_ALGO) == SIG_ALGO_ECDSA_P384_SHA384,
+            "unsupported signature algorithm {}",
+            report.u32(REPORT_SIG_ALGO)
+        );
+        Ok(report)
+    }
+
+    fn u32(&self, range: Range<usize>) -> u32 {
+        u32::from_le_bytes(self.0[range].try_into().unwrap())
+    }
+
+    fn u64(&self, range: Range<usize>) -> u64 {
+        u64::from_le_bytes(self.0[range].try_into().unwrap())
+    }
+
+    /// Verifies the report signature with the VCEK.
+    pub fn verify(&self, vcek: &Certificate) -> Result<()> {
+        // The signature components are zero-extended little-endian integers.
+        let mut signature = Vec::with_capacity(96);
+        for range in [REPORT_SIG_R, REPORT_SIG_S] {
+            let component = &self.0[range];
+            ensure!(
+                component[48..].iter().all(|b| *b == 0),
+                "malformed report signature"
+            );
+            signature.extend(component[..48].iter().rev());
+        }
+
+        let key = pki::public_key(&vcek.tbs_certificate.subject_public_key_info)?;
+        UnparsedPublicKey::new(&ECDSA_P384_SHA384_FIXED, key)
+            .verify(&self.0[REPORT_SIGNED], &signature)
+            .map_err(|_| anyhow!("invalid report signature"))
+    }
+
+    pub fn guest_svn(&self) -> u32 {
+        self.u32(REPORT_GUEST_SVN)
+    }
+
+    pub fn policy(&self) -> u64 {
+        self.u64(REPORT_POLICY)
+    }
+
+    pub fn family_id(&self) -> &[u8] {
+        &self.0[REPORT_FAMILY_ID]
+    }
+
+    pub fn image_id(&self) -> &[u8] {
+        &self.0[REPORT_IMAGE_ID]
+    }
+
+    pub fn report_data(&self) -> &[u8] {
+        &self.0[REPORT_DATA]
+    }
+
+    pub fn measurement(&self) -> &[u8] {
+        &self.0[REPORT_MEASUREMENT]
+    }
+
+    pub fn id_key_digest(&self) -> &[u8] {
+        &self.0[REPORT_ID_KEY_DIGEST]
+    }
+
+    pub fn author_key_digest(&self) -> &[u8] {
+        &self.0[REPORT_AUTHOR_KEY_DIGEST]
+    }
+
+    pub fn reported_tcb(&self) -> u64 {
+        self.u64(REPORT_REPORTED_TCB)
+    }
+
+    pub fn chip_id(&self) -> &[u8] {
+        &self.0[REPORT_CHIP_ID]
+    }
+}
+
+/// Verifies that `vcek` was issued for the TCB version `tcb` of the chip `chip_id`.
+fn verify_vcek(vcek: &Certificate, tcb: u64, chip_id: &[u8]) -> Result<()> {
+    let extension = |oid: ObjectIdentifier| {
+        vcek.tbs_certificate
+            .extensions
+            .iter()
+            .flatten()
+            .find(|ext| ext.extn_id == oid)
+            .map(|ext| ext.extn_value.as_bytes())
+            .with_context(|| format!("VCEK has no extension {oid}"))
+    };
+
+    let tcb = tcb.to_le_bytes();
+    for (name, oid, index) in VCEK_SPLS {
+        let spl = u8::from_der(extension(oid)?)
+            .with_context(|| format!("invalid {name} SPL extension of the VCEK"))?;
+        ensure!(
+            spl == tcb[index],
+            "VCEK was issued for {name} SPL {spl}, but the report has {}",
+            tcb[index]
+        );
+    }
+
+    ensure!(
+        extension(VCEK_HWID)? == chip_id,
+        "VCEK was issued for another chip"
+    );
+    Ok(())
+}
+
+pub(super) fn verify(
+    verdict: &mut Verdict,
+    data: &[u8],
+    report_data: Option<&[u8]>,
+    collateral: &Collateral,
+    reference: Option<&Reference>,
+    now: SystemTime,
+) -> Result<()> {
+    let ext = SnpExtension::from_der(data).context("Failed to decode SNP evidence")?;
+    let report = Report::parse(ext.report).context("Failed to parse SNP attestation report")?;
+    let vcek = ext.evidence.vcek;
+
+    verdict.claim("measurement", hex::encode(report.measurement()));
+    verdict.claim("family_id", hex::encode(report.family_id()));
+    verdict.claim("image_id", hex::encode(report.image_id()));
+    verdict.claim("guest_svn", report.guest_svn().to_string());
+    verdict.claim("policy", format!("{:#x}", report.policy()));
+    verdict.claim("reported_tcb", format!("{:#018x}", report.reported_tcb()));
+    verdict.claim("chip_id", hex::encode(report.chip_id()));
+
+    // The ASK is taken from the collateral, if given, otherwise from the evidence.
+    // The trusted ARK is always the last certificate of the collateral.
+    let mut chain = vec![vcek];
+    match (collateral.snp_chain.len(), ext.evidence.chain) {
+        (0 | 1, Some(evidence_chain)) => {
+            chain.extend(evidence_chain.into_iter().take(1));
+            chain.extend(collateral.snp_chain.iter().cloned());
+        }
+        _ => chain.extend(collateral.snp_chain.iter().cloned()),
+    }
+
+    verdict.check(
+        "VCEK certificate chain",
+        match collateral.snp_chain.last() {
+            Some(ark) => pki::verify_chain(&chain, Some(ark), now),
+            None => Err(anyhow!("no trusted AMD ARK certificate given")),
+        },
+    );
+
+    let crls = collateral.crls.as_ref().unwrap_or(&ext.evidence.crl);
+    verdict.check(
+        "Certificate revocation",
+        pki::check_revocation(&chain, crls, now).and_then(|n| {
+            ensure!(n > 0, "no CRL applies to the VCEK certificate chain");
+            Ok(())
+        }),
+    );
+
+    verdict.check("Report signature", report.verify(&chain[0]));
+
+    verdict.check(
+        "VCEK TCB version",
+        verify_vcek(&chain[0], report.reported_tcb(), report.chip_id()).with_context(|| match ext
+            .evidence
+            .tcb
+        {
+            Some(tcb) if tcb != report.reported_tcb() => {
+                format!("the host fetched the VCEK for TCB {tcb:#018x}")
+            }
+            _ => format!("the report has TCB {:#018x}", report.reported_tcb()),
+        }),
+    );
+
+    match report_data {
+        Some(report_data) => verdict.check(
+            "Report data",
+            if report.report_data() == report_data {
+                Ok(())
+            } else {
+                Err(anyhow!("report is not bound to the CSR public key"))
+            },
+        ),
+        None => verdict.claim("report_data", hex::encode(report.report_data())),
+    }
+    verdict.check(
+        "Production guest",
+        if report.policy() & POLICY_DEBUG != 0 {
+            Err(anyhow!("guest policy allows debugging"))
+        } else {
+            Ok(())
+        },
+    );
+
+    if let Some(reference) = reference {
+        verdict.expect("Measurement", &reference.measurement, report.measurement());
+        verdict.expect("Family ID", &reference.family_id, report.family_id());
+        verdict.expect("Image ID", &reference.image_id, report.image_id());
+        verdict.expect("ID key", &reference.id_key_digest, report.id_key_digest());
+        verdict.expect(
+            "Author key",
+            &reference.author_key_digest,
+            report.author_key_digest(),
+        );
+        if let Some(policy) = reference.policy {
+            verdict.check(
+                "Policy",
+                if report.policy() == policy {
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "expected {policy:#x}, found {:#x}",
+                        report.policy()
+                    ))
+                },
+            );
+        }
+        if let Some(guest_svn) = reference.guest_svn {
+            verdict.check(
+                "Guest SVN",
+                if report.guest_svn() >= guest_svn {
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "expected at least {guest_svn}, found {}",
+                        report.guest_svn()
+                    ))
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let mut report = vec![0; REPORT_SIZE];
+        assert!(Report::parse(&report).is_err());
+
+        report[REPORT_VERSION].copy_from_slice(&2u32.to_le_bytes());
+        report[REPORT_SIG_ALGO].copy_from_slice(&1u32.to_le_bytes());
+        report[REPORT_GUEST_SVN].copy_from_slice(&7u32.to_le_bytes());
+        report[REPORT_POLICY].copy_from_slice(&0x30000u64.to_le_bytes());
+        let parsed = Report::parse(&report).unwrap();
+        assert_eq!(parsed.guest_svn(), 7);
+        assert_eq!(parsed.policy(), 0x30000);
+
+        assert!(Report::parse(&report[..REPORT_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn vcek() {
+        let vcek =
+            Certificate::from_der(include_bytes!("../backend/sev/snp/testdata/vcek.der")).unwrap();
+        let chip_id = hex::decode(
+            "8ba826b2dd6ab65e401e0c4d4128ef4b434ed0ccb213f66c5f577b518730ef58\
+             92f78a78be259976973125a3b9b3d19f286c912cf5776fdfcee5260fa4576c4b",
+        )
+        .unwrap();
+
+        // The VCEK was issued for boot loader 0, TEE 0, SNP 3 and microcode 29.
+        let tcb = 0x1d03_0000_0000_0000;
+        verify_vcek(&vcek, tcb, &chip_id).unwrap();
+
+        let err = verify_vcek(&vcek, tcb + 1, &chip_id).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "VCEK was issued for boot loader SPL 0, but the report has 1"
+        );
+        let err = verify_vcek(&vcek, 0x1d04_0000_0000_0000, &chip_id).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "VCEK was issued for SNP SPL 3, but the report has 4"
+        );
+        assert!(verify_vcek(&vcek, tcb, &[0; 64]).is_err());
+    }
+}
@@ -5,7 +5,7 @@ use super::Config;
 use std::convert::TryInto;
 use std::fmt::Formatter;
 
-use anyhow::{anyhow, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
 use goblin::elf::{header::*, note::NoteIterator, program_header::*, Elf};
 use mmarinus::{perms, Map};
 use primordial::Page;
@@ -32,10 +32,27 @@ impl std::fmt::Debug for Segment<'_> {
     }
 }
 
+/// Checks that `bytes` is an x86-64 executable, as the shim and the exec of a Keep must be.
+///
+/// Only the ELF header is checked, the segments are validated on loading.
+pub fn check_header(bytes: &[u8]) -> Result<()> {
+    let header = Elf::parse_header(bytes)?;
+
+    if header.e_type != ET_EXEC && header.e_type != ET_DYN {
+        return Err(anyhow!("unsupported ELF header: e_type"));
+    }
+
+    if header.e_machine != EM_X86_64 {
+        return Err(anyhow!("unsupported ELF header: e_machine"));
+    }
+
+    Ok(())
+}
+
 pub struct Binary<'a>(&'a [u8], Elf<'a>);
 
 impl<'a> Binary<'a> {
-    fn new(bytes: &'a [u8]) -> Result<Self> {
+    pub(super) fn new(bytes: &'a [u8]) -> Result<Self> {
         let elf = Elf::parse(bytes)?;
 
         if elf.header.e_ident[EI_CLASS] != ELFCLASS64 {
@@ -50,6 +67,10 @@ impl<'a> Binary<'a> {
             return Err(anyhow!("unsupported ELF header: e_ident[EI_VERSION]",));
         }
 
+        if elf.header.e_type != ET_EXEC && elf.header.e_type != ET_DYN {
+            return Err(anyhow!("unsupported ELF header: e_type"));
+        }
+
         if elf.header.e_machine != EM_X86_64 {
             return Err(anyhow!("unsupported ELF header: e_machine"));
         }
@@ -158,9 +179,17 @@ impl<T: Mapper> Loader for T {
     ) -> Result<Self::Output> {
         use sallyport::elf;
 
+        // Apply the configured Keep resources to the shim.
+        let shim = super::resources::configure(shim.as_ref())?;
+
         // Parse the ELF files.
-        let sbin = Binary::new(shim.as_ref())?;
-        let ebin = Binary::new(exec.as_ref())?;
+        let sbin = Binary::new(&shim).context("Invalid shim binary")?;
+        let ebin = Binary::new(exec.as_ref()).context("Invalid exec binary")?;
+
+        // The shim and the exec must not be swapped.
+        if ebin.headers(elf::pt::EXEC).next().is_some() {
+            return Err(anyhow!("The exec binary is a shim!"));
+        }
 
         // Find the offset for loading the code.
         let slot = sbin
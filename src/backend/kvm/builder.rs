@@ -2,11 +2,12 @@
 
 use super::config::Config;
 use super::mem::{Region, Slot};
-use super::KvmKeepPersonality;
+use super::{KvmKeepPersonality, MAX_NUM_CPUS};
 
 use std::convert::TryFrom;
 use std::mem::align_of;
 use std::sync::{Arc, RwLock};
+use std::thread::available_parallelism;
 
 use anyhow::{Context, Error, Result};
 use kvm_bindings::fam_wrappers::KVM_MAX_CPUID_ENTRIES;
@@ -97,12 +98,27 @@ impl TryFrom<Builder> for Arc<dyn super::super::Keep> {
             anyhow::bail!("No sallyport blocks defined!");
         }
 
+        // Further vCPUs are created on demand, when the guest spawns threads.
+        let limit = (builder.kvm_fd.get_max_vcpus() as u64).min(MAX_NUM_CPUS);
+        let max_cpus = match builder.config.max_threads {
+            Some(vcpus) if vcpus > limit => {
+                anyhow::bail!("KVM supports at most {limit} vCPUs, but {vcpus} were requested")
+            }
+            Some(vcpus) => vcpus,
+            None => available_parallelism()
+                .map_or(1, |n| n.get() as u64)
+                .min(limit),
+        };
+
         let vcpu_fd = kvm_new_vcpu(&mut builder.kvm_fd, &mut builder.vm_fd, 0)?;
 
         Ok(Arc::new(RwLock::new(super::Keep::<KvmKeepPersonality> {
             kvm_fd: builder.kvm_fd,
             vm_fd: builder.vm_fd,
             num_cpus: 1,
+            max_cpus,
+            ballooned: 0,
+            max_memory: builder.config.max_memory.map(|m| m as usize),
             cpu_fds: vec![vcpu_fd],
             regions: builder.regions,
             sallyport_block_size: builder.config.sallyport_block_size,
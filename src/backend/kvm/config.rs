@@ -7,6 +7,10 @@ use sallyport::elf::{self, pf::kvm::SALLYPORT};
 
 pub struct Config {
     pub sallyport_block_size: usize,
+    /// Maximum number of vCPUs
+    pub max_threads: Option<u64>,
+    /// Maximum memory of the Keep
+    pub max_memory: Option<u64>,
     pub signatures: Option<Signatures>,
 }
 
@@ -35,6 +39,8 @@ impl super::super::Config for Config {
 
         Ok(Self {
             sallyport_block_size,
+            max_threads: shim.resource(elf::note::MAX_THREADS),
+            max_memory: shim.resource(elf::note::MAX_MEMORY),
             signatures,
         })
     }
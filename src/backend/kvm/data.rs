@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use super::builder::kvm_new_vcpu;
 use crate::backend::{probe::x86_64::CpuId, Datum};
 
+use anyhow::Context;
 use kvm_ioctls::Kvm;
 
 pub fn dev_kvm() -> Datum {
@@ -30,6 +32,28 @@ pub fn kvm_version() -> Datum {
     }
 }
 
+pub fn create_vm() -> Datum {
+    let res = Kvm::new()
+        .context("Failed to open '/dev/kvm'")
+        .and_then(|mut kvm_fd| {
+            let mut vm_fd = kvm_fd
+                .create_vm()
+                .context("Failed to create a virtual machine")?;
+            kvm_new_vcpu(&mut kvm_fd, &mut vm_fd, 0)
+        });
+
+    Datum {
+        name: "Virtual Machine".into(),
+        pass: res.is_ok(),
+        info: res.as_ref().err().map(|e| format!("{e:#}")),
+        mesg: res.err().map(|_| {
+            "Enarx failed to create a virtual machine with the supported CPUID entries. \
+            Check that the user can access `/dev/kvm` and that no other hypervisor holds it."
+                .into()
+        }),
+    }
+}
+
 pub const CPUIDS: &[CpuId] = &[
     CpuId {
         name: "CPU",
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::Loader;
-use data::{dev_kvm, kvm_version, CPUIDS};
+use data::{create_vm, dev_kvm, kvm_version, CPUIDS};
 use mem::{Region, Slot};
 
 use std::sync::Arc;
@@ -24,6 +24,9 @@ pub mod thread;
 
 pub(crate) const KVM_HC_MAP_GPA_RANGE: u64 = 12;
 
+/// Maximum number of vCPUs the shim supports, see `MAX_NUM_CPUS` of `enarx-shim-kvm`
+pub(crate) const MAX_NUM_CPUS: u64 = 512;
+
 pub trait KeepPersonality: Send + Sync + 'static {
     fn map(_vm_fd: &mut VmFd, _region: &Region, _is_private: bool) -> std::io::Result<()> {
         Ok(())
@@ -46,6 +49,11 @@ pub struct Keep<P: KeepPersonality + Send> {
     pub kvm_fd: Kvm,
     pub vm_fd: VmFd,
     pub num_cpus: u64,
+    pub max_cpus: u64,
+    /// Memory added with `enarxcall::BalloonMemory` so far
+    pub ballooned: usize,
+    /// Maximum memory that may be added with `enarxcall::BalloonMemory`
+    pub max_memory: Option<usize>,
     pub cpu_fds: Vec<VcpuFd>,
     pub sallyport_block_size: usize,
     pub sallyports: Vec<Option<VirtAddr>>,
@@ -114,6 +122,10 @@ impl crate::backend::Backend for Backend {
         vec![]
     }
 
+    fn checks(&self) -> Vec<super::Datum> {
+        vec![create_vm()]
+    }
+
     #[inline]
     fn keep(
         &self,
@@ -8,11 +8,12 @@ use super::KVM_HC_MAP_GPA_RANGE;
 use crate::backend::execute_gdb;
 use crate::backend::kvm::builder::kvm_new_vcpu;
 use crate::backend::parking::THREAD_PARK;
+use crate::backend::policy::SYSCALL_POLICY;
 use crate::backend::sev::set_memory_attributes;
+use crate::backend::trace;
 use crate::backend::Keep as _;
 
 use std::io;
-use std::iter;
 use std::mem::size_of;
 use std::ops::DerefMut;
 use std::os::fd::AsRawFd;
@@ -55,6 +56,7 @@ impl<P: KeepPersonality> super::super::Keep for RwLock<super::Keep<P>> {
         let mut this = self.write().unwrap();
         let cpu_opt = this.cpu_fds.pop();
         let thread = match cpu_opt {
+            None if this.num_cpus >= this.max_cpus => None,
             None => {
                 let super::Keep {
                     kvm_fd,
@@ -104,8 +106,19 @@ impl<P: KeepPersonality> Thread<P> {
             return Err(libc::EINVAL);
         }
 
+        // Check the maximum memory of the Keep
+        let len = size.checked_mul(npgs).ok_or(libc::EINVAL)?;
+        {
+            let keep = self.keep.read().unwrap();
+            if let Some(max_memory) = keep.max_memory {
+                if keep.ballooned.saturating_add(len) > max_memory {
+                    return Err(libc::ENOMEM);
+                }
+            }
+        }
+
         // Allocate the new memory
-        let pages = Map::bytes(size * npgs)
+        let pages = Map::bytes(len)
             .anywhere()
             .anonymously()
             .with(perms::ReadWrite)
@@ -120,6 +133,8 @@ impl<P: KeepPersonality> Thread<P> {
             .as_virt()
             .start;
 
+        keep.ballooned += len;
+
         Ok(vaddr.as_u64() as _)
     }
 
@@ -361,73 +376,79 @@ impl<P: KeepPersonality> super::super::Thread for Thread<P> {
                 .into();
 
                 for item in block {
-                    match item {
-                        Item::Gdbcall(_gdbcall, _data) => {
-                            #[cfg(feature = "gdb")]
-                            unsafe {
-                                execute_gdb(
-                                    _gdbcall,
-                                    _data,
-                                    &mut self.gdb_fd,
-                                    _gdblisten.as_ref().unwrap(),
-                                )
-                                .map_err(io::Error::from_raw_os_error)
-                                .context("execute_gdb")?;
+                    trace::traced(item, |item| {
+                        match item {
+                            Item::Gdbcall(_gdbcall, _data) => {
+                                #[cfg(feature = "gdb")]
+                                unsafe {
+                                    execute_gdb(
+                                        _gdbcall,
+                                        _data,
+                                        &mut self.gdb_fd,
+                                        _gdblisten.as_ref().unwrap(),
+                                    )
+                                    .map_err(io::Error::from_raw_os_error)
+                                    .context("execute_gdb")?;
+                                }
                             }
-                        }
 
-                        Item::Enarxcall(enarxcall, data) => {
-                            if let Some(Item::Enarxcall(enarxcall, data)) =
-                                self.kvm_enarxcall(enarxcall, data)?
-                            {
-                                let mut keep = self.keep.write().unwrap();
-                                sallyport::host::execute(
-                                    keep.personality.enarxcall(enarxcall, data)?.into_iter(),
-                                )
-                                .map_err(io::Error::from_raw_os_error)
-                                .context("sallyport::host::execute")?;
+                            Item::Enarxcall(enarxcall, data) => {
+                                if let Some(Item::Enarxcall(enarxcall, data)) =
+                                    self.kvm_enarxcall(enarxcall, data)?
+                                {
+                                    let mut keep = self.keep.write().unwrap();
+                                    sallyport::host::execute(
+                                        keep.personality.enarxcall(enarxcall, data)?.into_iter(),
+                                    )
+                                    .map_err(io::Error::from_raw_os_error)
+                                    .context("sallyport::host::execute")?;
+                                }
                             }
-                        }
 
-                        // Catch exit_group for a clean shutdown
-                        Item::Syscall(
-                            item::Syscall {
-                                num,
-                                argv: [code, ..],
-                                ..
-                            },
-                            ..,
-                        ) if (*num == libc::SYS_exit_group as usize) => {
-                            trace!("exit_group({code})");
-                            std::process::exit(*code as _);
-                        }
+                            // Catch exit_group for a clean shutdown
+                            Item::Syscall(
+                                item::Syscall {
+                                    num,
+                                    argv: [code, ..],
+                                    ..
+                                },
+                                ..,
+                            ) if (*num == libc::SYS_exit_group as usize) => {
+                                trace!("exit_group({code})");
+                                std::process::exit(*code as _);
+                            }
 
-                        // Catch exit and exit_group for a clean shutdown
-                        Item::Syscall(syscall, ..) if (syscall.num == libc::SYS_exit as usize) => {
-                            trace!(?syscall);
-                            panic!("unexpected exit syscall!");
-                        }
+                            // Catch exit and exit_group for a clean shutdown
+                            Item::Syscall(syscall, ..)
+                                if (syscall.num == libc::SYS_exit as usize) =>
+                            {
+                                trace!(?syscall);
+                                panic!("unexpected exit syscall!");
+                            }
 
-                        Item::Syscall(ref _syscall, ..) => {
-                            #[cfg(feature = "dbg")]
-                            match (
-                                _syscall.num as libc::c_long,
-                                _syscall.argv[1] as libc::c_int,
-                            ) {
-                                (
-                                    libc::SYS_write | libc::SYS_read,
-                                    libc::STDIN_FILENO | libc::STDOUT_FILENO | libc::STDERR_FILENO,
-                                ) => {}
-                                _ => {
-                                    trace!(?_syscall);
+                            Item::Syscall(syscall, data) => {
+                                #[cfg(feature = "dbg")]
+                                match (syscall.num as libc::c_long, syscall.argv[1] as libc::c_int)
+                                {
+                                    (
+                                        libc::SYS_write | libc::SYS_read,
+                                        libc::STDIN_FILENO
+                                        | libc::STDOUT_FILENO
+                                        | libc::STDERR_FILENO,
+                                    ) => {}
+                                    _ => {
+                                        trace!(?syscall);
+                                    }
                                 }
-                            }
 
-                            sallyport::host::execute(iter::once(item))
-                                .map_err(io::Error::from_raw_os_error)
-                                .context("sallyport::host::execute")?;
+                                SYSCALL_POLICY
+                                    .execute(syscall, data)
+                                    .map_err(io::Error::from_raw_os_error)
+                                    .context("sallyport::host::execute")?;
+                            }
                         }
-                    }
+                        Ok::<_, anyhow::Error>(())
+                    })?;
                 }
 
                 self.keep.write().unwrap().sallyports[block_nr].replace(block_virt);
@@ -20,6 +20,17 @@ pub(crate) mod probe;
 #[cfg(enarx_with_shim)]
 mod parking;
 
+#[cfg(enarx_with_shim)]
+pub(crate) mod policy;
+
+#[cfg(enarx_with_shim)]
+pub(crate) mod resources;
+
+#[cfg(enarx_with_shim)]
+pub(crate) mod trace;
+
+#[cfg(enarx_with_shim)]
+pub use binary::check_header;
 #[cfg(enarx_with_shim)]
 use binary::{Binary, Loader, Mapper};
 use serde_json::json;
@@ -27,17 +38,20 @@ use serde_json::Value;
 
 use std::fs::File;
 use std::io::Read;
+use std::ops::Range;
 use std::panic::UnwindSafe;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::{bail, Context, Error, Result};
+use anyhow::{anyhow, bail, Context, Error, Result};
 use camino::Utf8PathBuf;
+use chrono::{DateTime, Utc};
 #[cfg(windows)]
 use enarx_exec_wasmtime::Args;
 use libc::c_int;
 use once_cell::sync::Lazy;
 use serde::ser::{Serialize, SerializeStruct, Serializer};
+use sha2::{Digest, Sha256, Sha384};
 
 #[cfg(not(enarx_with_shim))]
 #[allow(dead_code)]
@@ -47,19 +61,41 @@ pub struct Binary<'a> {
 
 pub const SIGNATURES_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-#[derive(Default, Debug, serde::Deserialize, serde::Serialize)]
+/// Offset of the RSA modulus in the SGX SIGSTRUCT as per the Intel SDM
+const SIGSTRUCT_MODULUS: Range<usize> = 128..512;
+/// Offset of the ID key in the SEV ID authentication information as per the SEV-SNP firmware ABI
+const ID_AUTH_ID_KEY: Range<usize> = 0x240..0x644;
+
+#[derive(Clone, Default, Debug, serde::Deserialize, serde::Serialize)]
 pub struct SevSignature {
     pub id_block: Vec<u8>,
     pub id_auth: Vec<u8>,
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Signatures {
     pub version: String,
     pub sev: SevSignature,
     pub sgx: Vec<u8>,
 }
 
+impl Signatures {
+    /// Returns the MRSIGNER of the SGX signature, the SHA-256 digest of the modulus of its key.
+    pub fn sgx_mrsigner(&self) -> Option<String> {
+        self.sgx
+            .get(SIGSTRUCT_MODULUS)
+            .map(|modulus| hex::encode(Sha256::digest(modulus)))
+    }
+
+    /// Returns the SHA-384 digest of the ID key of the SEV signature, as in attestation reports.
+    pub fn sev_id_key_digest(&self) -> Option<String> {
+        self.sev
+            .id_auth
+            .get(ID_AUTH_ID_KEY)
+            .map(|id_key| hex::encode(Sha384::digest(id_key)))
+    }
+}
+
 impl Default for Signatures {
     fn default() -> Self {
         Self {
@@ -70,14 +106,86 @@ impl Default for Signatures {
     }
 }
 
-impl Signatures {
+/// A signing key of a [`SignatureBundle`] and the time in which its signatures are valid
+///
+/// Rotating a key adds a new key, whose window overlaps the window of the old key,
+/// so hosts can switch to the new signatures at any time of the overlap.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct BundleKey {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<DateTime<Utc>>,
+    /// MRSIGNER of the SGX key, required for SGX signatures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sgx_mrsigner: Option<String>,
+    /// Digest of the SEV ID key, required for SEV signatures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sev_id_key_digest: Option<String>,
+}
+
+impl BundleKey {
+    /// Whether the signatures of the key are valid at `now`
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        self.not_before.map_or(true, |not_before| not_before <= now)
+            && self.not_after.map_or(true, |not_after| now <= not_after)
+    }
+
+    /// Checks that `signatures` are signed with the key.
+    pub fn check(&self, signatures: &Signatures) -> Result<()> {
+        for (backend, expected, signed) in [
+            ("SGX", &self.sgx_mrsigner, signatures.sgx_mrsigner()),
+            (
+                "SEV",
+                &self.sev_id_key_digest,
+                signatures.sev_id_key_digest(),
+            ),
+        ] {
+            match (expected, signed) {
+                (_, None) => {}
+                (None, Some(_)) => bail!("the signing key has no {backend} fingerprint"),
+                (Some(expected), Some(signed)) if !expected.eq_ignore_ascii_case(&signed) => {
+                    bail!("the {backend} signature is signed with the key {signed}, not {expected}")
+                }
+                (Some(_), Some(_)) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The signatures of an Enarx version in a [`SignatureBundle`]
+///
+/// The measurement of the Keep, which the entry is selected by, is the one of the
+/// signed SGX SIGSTRUCT and SEV ID block.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct BundleEntry {
+    /// ID of the [`BundleKey`] the entry is signed with, valid at any time if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(flatten)]
+    pub signatures: Signatures,
+}
+
+/// The signatures of several Enarx versions and Keep measurements, signed with several keys
+///
+/// A signatures file of a single Enarx version is read as a bundle with a single entry.
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct SignatureBundle {
+    #[serde(default)]
+    pub keys: Vec<BundleKey>,
+    pub entries: Vec<BundleEntry>,
+}
+
+impl SignatureBundle {
     pub fn load(path: Option<Utf8PathBuf>) -> anyhow::Result<Option<Self>> {
         match path {
             None => {
                 // If no path is specified, try to load the system-wide signatures.
                 let path = PathBuf::from("/usr/lib/enarx/enarx.sig");
                 if path.exists() {
-                    Self::load_and_check_file(&path).with_context(|| {
+                    Self::load_file(&path).map(Some).with_context(|| {
                         format!(
                             "Failed to load system-wide signatures from {:?}",
                             path.display()
@@ -87,28 +195,155 @@ impl Signatures {
                     Ok(None)
                 }
             }
-            Some(path) => Self::load_and_check_file(path.as_std_path())
+            Some(path) => Self::load_file(path.as_std_path())
+                .map(Some)
                 .with_context(|| format!("Failed to load signatures from {path}")),
         }
     }
 
-    fn load_and_check_file(path: &Path) -> Result<Option<Signatures>, Error> {
+    fn load_file(path: &Path) -> Result<Self, Error> {
         let mut file = File::open(path).context("Failed to open signatures file")?;
         let mut buffer = String::new();
         file.read_to_string(&mut buffer)?;
-        let ret = serde_json::from_str::<Signatures>(&buffer)
-            .context("serde_json")
-            .map(Some);
-        if let Ok(Some(Signatures { ref version, .. })) = ret {
-            if version != SIGNATURES_VERSION {
-                bail!(
-                    "Signature file version {} does not match current version {}",
-                    version,
-                    SIGNATURES_VERSION
-                );
+        Self::from_json(&buffer)
+    }
+
+    /// Parses a signature bundle or the signatures file of a single Enarx version.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let value: Value = serde_json::from_str(json).context("serde_json")?;
+        let bundle = if value.get("entries").is_some() {
+            serde_json::from_value::<Self>(value).context("serde_json")?
+        } else {
+            let signatures = serde_json::from_value::<Signatures>(value).context("serde_json")?;
+            Self {
+                keys: vec![],
+                entries: vec![BundleEntry {
+                    key: None,
+                    signatures,
+                }],
+            }
+        };
+        bundle.check()?;
+        Ok(bundle)
+    }
+
+    /// Checks that the keys are unique and the entries are signed with their keys.
+    pub fn check(&self) -> Result<()> {
+        for (i, key) in self.keys.iter().enumerate() {
+            if self.keys[..i].iter().any(|k| k.id == key.id) {
+                bail!("Signing key {:?} is defined more than once", key.id);
+            }
+        }
+        for entry in &self.entries {
+            if let Some(ref id) = entry.key {
+                let key = self.key(id).ok_or_else(|| {
+                    anyhow!(
+                        "Signatures for version {} reference the unknown signing key {id:?}",
+                        entry.signatures.version
+                    )
+                })?;
+                key.check(&entry.signatures).with_context(|| {
+                    format!(
+                        "Signatures for version {} are not signed with the signing key {id:?}",
+                        entry.signatures.version
+                    )
+                })?;
             }
         }
-        ret
+        Ok(())
+    }
+
+    /// Returns the signing key `id`.
+    pub fn key(&self, id: &str) -> Option<&BundleKey> {
+        self.keys.iter().find(|key| key.id == id)
+    }
+
+    /// Selects the signatures of a Keep of `backend` for this version of Enarx at `now`.
+    ///
+    /// Of the entries with a signing key valid at `now`, and a signature for `backend`,
+    /// the ones `matches` accepts are candidates, i.e. the ones signing the measurement
+    /// of the Keep. Unless `verify` is set, `matches` is only called to choose between
+    /// several entries. Of several candidates, the one of the most recent key wins.
+    pub fn select(
+        &self,
+        backend: &str,
+        now: DateTime<Utc>,
+        verify: bool,
+        mut matches: impl FnMut(&Signatures) -> Result<bool>,
+    ) -> Result<&Signatures> {
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.signatures.version == SIGNATURES_VERSION)
+            .collect();
+        if entries.is_empty() {
+            let mut versions: Vec<_> = self
+                .entries
+                .iter()
+                .map(|entry| entry.signatures.version.as_str())
+                .collect();
+            versions.sort_unstable();
+            versions.dedup();
+            bail!(
+                "Signature file version {} does not match current version {}",
+                versions.join(", "),
+                SIGNATURES_VERSION
+            );
+        }
+
+        let not_before = |entry: &BundleEntry| {
+            entry
+                .key
+                .as_deref()
+                .and_then(|id| self.key(id))
+                .and_then(|key| key.not_before)
+        };
+        let entries: Vec<_> = entries
+            .into_iter()
+            .filter(|entry| match entry.key {
+                Some(ref id) => self.key(id).map_or(false, |key| key.is_valid_at(now)),
+                None => true,
+            })
+            .collect();
+        if entries.is_empty() {
+            bail!("No signing key of the signatures for version {SIGNATURES_VERSION} is valid at {now}");
+        }
+
+        let entries: Vec<_> = entries
+            .into_iter()
+            .filter(|entry| match backend {
+                "sgx" => !entry.signatures.sgx.is_empty(),
+                "sev" => !entry.signatures.sev.id_block.is_empty(),
+                _ => true,
+            })
+            .collect();
+        if entries.is_empty() {
+            bail!("No {backend} signatures for version {SIGNATURES_VERSION}");
+        }
+
+        let entries = if verify || entries.len() > 1 {
+            let mut matching = vec![];
+            for entry in entries {
+                if matches(&entry.signatures)? {
+                    matching.push(entry);
+                }
+            }
+            matching
+        } else {
+            entries
+        };
+
+        // `max_by_key` returns the last maximum, so the first entry wins a tie.
+        entries
+            .into_iter()
+            .rev()
+            .max_by_key(|entry| not_before(entry))
+            .map(|entry| &entry.signatures)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No {backend} signatures for version {SIGNATURES_VERSION} match the measurement of the Keep"
+                )
+            })
     }
 }
 
@@ -158,6 +393,11 @@ pub trait Backend: Sync + Send {
     /// The tests that show machine configuration support for the backend
     fn config(&self) -> Vec<Datum>;
 
+    /// The live tests that exercise the platform like the launch of a keep
+    fn checks(&self) -> Vec<Datum> {
+        vec![]
+    }
+
     /// Create a keep instance
     fn keep(
         &self,
@@ -478,3 +718,168 @@ pub(super) unsafe fn execute_gdb(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns signatures of `version` and the measurement `sgx`, signed with the key `key`.
+    fn signed(version: &str, sgx: u8, key: u8) -> Signatures {
+        let mut signatures = Signatures {
+            version: version.into(),
+            sev: SevSignature {
+                id_block: vec![sgx; 96],
+                id_auth: vec![0; 4096],
+            },
+            sgx: vec![sgx; 1808],
+        };
+        signatures.sgx[SIGSTRUCT_MODULUS].fill(key);
+        signatures.sev.id_auth[ID_AUTH_ID_KEY].fill(key);
+        signatures
+    }
+
+    fn signatures(version: &str, sgx: u8) -> Signatures {
+        signed(version, sgx, 0x55)
+    }
+
+    /// Returns the bundle key `id` of the key of [`signatures`].
+    fn key(id: &str, not_before: Option<&str>, not_after: Option<&str>) -> BundleKey {
+        let signatures = signatures(SIGNATURES_VERSION, 0);
+        BundleKey {
+            id: id.into(),
+            not_before: not_before.map(time),
+            not_after: not_after.map(time),
+            sgx_mrsigner: signatures.sgx_mrsigner(),
+            sev_id_key_digest: signatures.sev_id_key_digest(),
+        }
+    }
+
+    fn time(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn bundle_legacy() {
+        let json = serde_json::to_string(&signatures(SIGNATURES_VERSION, 1)).unwrap();
+        let bundle = SignatureBundle::from_json(&json).unwrap();
+        assert_eq!(bundle.entries.len(), 1);
+        let selected = bundle
+            .select("sgx", Utc::now(), false, |_| unreachable!())
+            .unwrap();
+        assert_eq!(selected.sgx, signatures(SIGNATURES_VERSION, 1).sgx);
+
+        let json = serde_json::to_string(&signatures("0.0.1", 1)).unwrap();
+        let bundle = SignatureBundle::from_json(&json).unwrap();
+        let err = bundle
+            .select("sgx", Utc::now(), false, |_| Ok(true))
+            .unwrap_err();
+        assert!(err.to_string().contains("0.0.1"));
+    }
+
+    #[test]
+    fn bundle_rotation() {
+        let bundle = SignatureBundle {
+            keys: vec![
+                key("2026", None, Some("2027-01-31T00:00:00Z")),
+                key(
+                    "2027",
+                    Some("2027-01-01T00:00:00Z"),
+                    Some("2028-01-31T00:00:00Z"),
+                ),
+            ],
+            entries: vec![
+                BundleEntry {
+                    key: Some("2026".into()),
+                    signatures: signatures(SIGNATURES_VERSION, 1),
+                },
+                BundleEntry {
+                    key: Some("2027".into()),
+                    signatures: signatures(SIGNATURES_VERSION, 2),
+                },
+                BundleEntry {
+                    key: Some("2027".into()),
+                    signatures: signatures("0.0.1", 3),
+                },
+            ],
+        };
+        let json = serde_json::to_string(&bundle).unwrap();
+        let bundle = SignatureBundle::from_json(&json).unwrap();
+
+        let select = |now| {
+            bundle
+                .select("sev", time(now), false, |_| Ok(true))
+                .map(|signatures| signatures.sev.id_block[0])
+        };
+        assert_eq!(select("2026-06-01T00:00:00Z").unwrap(), 1);
+        // The newer key wins while both keys are valid.
+        assert_eq!(select("2027-01-15T00:00:00Z").unwrap(), 2);
+        assert_eq!(select("2027-06-01T00:00:00Z").unwrap(), 2);
+        assert!(select("2028-06-01T00:00:00Z").is_err());
+
+        // Only the signatures of the measured Keep are candidates.
+        let selected = bundle
+            .select("sgx", time("2027-01-15T00:00:00Z"), true, |signatures| {
+                Ok(signatures.sgx[0] == 1)
+            })
+            .unwrap();
+        assert_eq!(selected.sgx[0], 1);
+        assert!(bundle
+            .select("sgx", time("2027-01-15T00:00:00Z"), true, |_| Ok(false))
+            .is_err());
+    }
+
+    #[test]
+    fn bundle_unknown_key() {
+        let bundle = SignatureBundle {
+            keys: vec![],
+            entries: vec![BundleEntry {
+                key: Some("2026".into()),
+                signatures: signatures(SIGNATURES_VERSION, 1),
+            }],
+        };
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(SignatureBundle::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn bundle_key_mismatch() {
+        let bundle = |key: BundleKey, signatures: Signatures| {
+            let bundle = SignatureBundle {
+                keys: vec![key],
+                entries: vec![BundleEntry {
+                    key: Some("2026".into()),
+                    signatures,
+                }],
+            };
+            SignatureBundle::from_json(&serde_json::to_string(&bundle).unwrap())
+        };
+        bundle(key("2026", None, None), signatures(SIGNATURES_VERSION, 1)).unwrap();
+
+        // Signatures of another SGX or SEV key are rejected.
+        let mut other = signatures(SIGNATURES_VERSION, 1);
+        other.sgx = signed(SIGNATURES_VERSION, 1, 0xaa).sgx;
+        let err = bundle(key("2026", None, None), other).unwrap_err();
+        assert!(format!("{err:#}").contains("SGX signature is signed with the key"));
+
+        let mut other = signatures(SIGNATURES_VERSION, 1);
+        other.sev = signed(SIGNATURES_VERSION, 1, 0xaa).sev;
+        let err = bundle(key("2026", None, None), other).unwrap_err();
+        assert!(format!("{err:#}").contains("SEV signature is signed with the key"));
+
+        // A key without the fingerprint of a signature is rejected.
+        let unknown = BundleKey {
+            sgx_mrsigner: None,
+            ..key("2026", None, None)
+        };
+        assert!(bundle(unknown, signatures(SIGNATURES_VERSION, 1)).is_err());
+
+        // Signatures without SGX signature only need the SEV fingerprint.
+        let sev_only = BundleKey {
+            sgx_mrsigner: None,
+            ..key("2026", None, None)
+        };
+        let mut signatures = signatures(SIGNATURES_VERSION, 1);
+        signatures.sgx.clear();
+        bundle(sev_only, signatures).unwrap();
+    }
+}
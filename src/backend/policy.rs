@@ -0,0 +1,330 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Host-side policy for the syscalls proxied through the sallyport
+//!
+//! A Keep may only make the host issue the syscalls the shims proxy and only on
+//! the file descriptors it owns: the standard streams, the file descriptors
+//! granted to it on startup and those it created through proxied syscalls.
+//! `ioctl` and `fcntl` are further limited to the requests the shims issue.
+//! Anything else is answered with an error without reaching the host kernel.
+
+use std::collections::BTreeSet;
+use std::iter;
+use std::mem::size_of;
+use std::sync::Mutex;
+
+use libc::{
+    c_int, c_long, pollfd, EACCES, EBADF, EINVAL, ENOSYS, ENOTTY, FIONBIO, FIONREAD, F_DUPFD,
+    F_DUPFD_CLOEXEC, F_GETFD, F_GETFL, F_SETFD, F_SETFL, O_CLOEXEC, O_RDONLY, TCGETS, TIOCGWINSZ,
+};
+use once_cell::sync::Lazy;
+use sallyport::item::{self, Item};
+use tracing::warn;
+
+pub(crate) static SYSCALL_POLICY: Lazy<Policy> = Lazy::new(Policy::default);
+
+/// The only file the shims allow a Keep to open
+const RESOLV_CONF: &[u8] = b"/etc/resolv.conf\0";
+
+/// The `ioctl` requests a Keep may issue, which the host kernel truncates to 32 bits
+const IOCTLS: &[u32] = &[FIONBIO as _, FIONREAD as _, TCGETS as _, TIOCGWINSZ as _];
+
+/// The `fcntl` commands a Keep may issue
+const FCNTLS: &[c_int] = &[F_DUPFD, F_DUPFD_CLOEXEC, F_GETFD, F_SETFD, F_GETFL, F_SETFL];
+
+/// File descriptor arguments of a syscall
+enum Fds {
+    /// The syscall takes no file descriptors
+    None,
+    /// The syscall is not proxied by the shims
+    Unsupported,
+    /// The argument at the given index is a file descriptor
+    Arg(usize),
+    /// The arguments at the given indices are file descriptors
+    Args(usize, usize),
+}
+
+/// Returns the file descriptor arguments of the syscall `num`.
+fn fds(num: c_long) -> Fds {
+    match num {
+        libc::SYS_clock_getres
+        | libc::SYS_clock_gettime
+        | libc::SYS_epoll_create1
+        | libc::SYS_eventfd2
+        | libc::SYS_exit
+        | libc::SYS_exit_group
+        | libc::SYS_nanosleep
+        | libc::SYS_open
+        | libc::SYS_poll
+        | libc::SYS_socket
+        | libc::SYS_sync => Fds::None,
+
+        libc::SYS_accept
+        | libc::SYS_accept4
+        | libc::SYS_bind
+        | libc::SYS_close
+        | libc::SYS_connect
+        | libc::SYS_dup
+        | libc::SYS_epoll_pwait
+        | libc::SYS_epoll_wait
+        | libc::SYS_fcntl
+        | libc::SYS_getsockname
+        | libc::SYS_ioctl
+        | libc::SYS_listen
+        | libc::SYS_read
+        | libc::SYS_recvfrom
+        | libc::SYS_sendto
+        | libc::SYS_setsockopt
+        | libc::SYS_write => Fds::Arg(0),
+
+        libc::SYS_epoll_ctl => Fds::Args(0, 2),
+        libc::SYS_dup2 | libc::SYS_dup3 => Fds::Args(0, 1),
+
+        _ => Fds::Unsupported,
+    }
+}
+
+/// Returns whether `fd` is open in the host process.
+fn is_open(fd: c_int) -> bool {
+    unsafe { libc::fcntl(fd, libc::F_GETFD) != -1 }
+}
+
+/// Tracks the file descriptors owned by the Keep.
+#[derive(Debug)]
+pub(crate) struct Policy {
+    fds: Mutex<BTreeSet<c_int>>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            fds: Mutex::new(BTreeSet::from([
+                libc::STDIN_FILENO,
+                libc::STDOUT_FILENO,
+                libc::STDERR_FILENO,
+            ])),
+        }
+    }
+}
+
+impl Policy {
+    /// Grants the Keep access to the host file descriptor `fd`.
+    pub fn grant(&self, fd: c_int) {
+        self.fds.lock().unwrap().insert(fd);
+    }
+
+    fn owns(&self, fd: usize) -> bool {
+        c_int::try_from(fd).is_ok_and(|fd| self.fds.lock().unwrap().contains(&fd))
+    }
+
+    /// Checks whether the Keep may issue `call`, returning the error to answer it with otherwise.
+    fn check(&self, call: &item::Syscall, data: &[u8]) -> Result<(), c_int> {
+        match fds(call.num as _) {
+            Fds::Unsupported => return Err(ENOSYS),
+            Fds::None => {}
+            Fds::Arg(i) => {
+                if !self.owns(call.argv[i]) {
+                    return Err(EBADF);
+                }
+            }
+            Fds::Args(i, j) => {
+                if !self.owns(call.argv[i]) {
+                    return Err(EBADF);
+                }
+                // `dup2` and `dup3` may target file descriptors not open in the host.
+                let dup = matches!(call.num as c_long, libc::SYS_dup2 | libc::SYS_dup3);
+                let free = dup && c_int::try_from(call.argv[j]).is_ok_and(|fd| !is_open(fd));
+                if !free && !self.owns(call.argv[j]) {
+                    return Err(EBADF);
+                }
+            }
+        }
+
+        match call.num as c_long {
+            libc::SYS_open => {
+                let [offset, len, flags, ..] = call.argv;
+                let path = offset
+                    .checked_add(len)
+                    .and_then(|end| data.get(offset..end));
+                if path != Some(RESOLV_CONF) || flags as c_int & !(O_RDONLY | O_CLOEXEC) != 0 {
+                    return Err(EACCES);
+                }
+            }
+            libc::SYS_ioctl => {
+                if !IOCTLS.contains(&(call.argv[1] as u32)) {
+                    return Err(ENOTTY);
+                }
+            }
+            libc::SYS_fcntl => {
+                if !FCNTLS.contains(&(call.argv[1] as c_int)) {
+                    return Err(EINVAL);
+                }
+            }
+            libc::SYS_poll => {
+                let [offset, nfds, ..] = call.argv;
+                let fds = nfds
+                    .checked_mul(size_of::<pollfd>())
+                    .and_then(|len| offset.checked_add(len))
+                    .and_then(|end| data.get(offset..end))
+                    .ok_or(libc::EFAULT)?;
+                // Negative file descriptors are ignored by `poll`.
+                for fd in fds.chunks_exact(size_of::<pollfd>()) {
+                    let fd = c_int::from_ne_bytes(fd[..size_of::<c_int>()].try_into().unwrap());
+                    if fd >= 0 && !self.owns(fd as _) {
+                        return Err(EBADF);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Records the file descriptors created or released by the executed `call`.
+    fn record(&self, call: &item::Syscall) {
+        let ret = call.ret[0] as isize;
+        let mut fds = self.fds.lock().unwrap();
+        match call.num as c_long {
+            // The file descriptor is released even if `close` fails.
+            libc::SYS_close => {
+                fds.remove(&(call.argv[0] as c_int));
+            }
+            libc::SYS_fcntl if !matches!(call.argv[1] as c_int, F_DUPFD | F_DUPFD_CLOEXEC) => {}
+            libc::SYS_accept
+            | libc::SYS_accept4
+            | libc::SYS_dup
+            | libc::SYS_dup2
+            | libc::SYS_dup3
+            | libc::SYS_epoll_create1
+            | libc::SYS_eventfd2
+            | libc::SYS_fcntl
+            | libc::SYS_open
+            | libc::SYS_socket
+                if ret >= 0 =>
+            {
+                fds.insert(ret as c_int);
+            }
+            _ => {}
+        }
+    }
+
+    /// Executes the syscall `call` of the Keep, if the policy allows it.
+    pub fn execute(&self, call: &mut item::Syscall, data: &mut [u8]) -> sallyport::Result<()> {
+        if let Err(e) = self.check(call, data) {
+            warn!(?call, "denied syscall of the Keep");
+            call.ret = [-e as usize, 0];
+            return Ok(());
+        }
+        sallyport::host::execute(iter::once(Item::Syscall(call, data)))?;
+        self.record(call);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn syscall(num: c_long, argv: [usize; 6]) -> item::Syscall {
+        item::Syscall {
+            num: num as _,
+            argv,
+            ret: [0, 0],
+        }
+    }
+
+    #[test]
+    fn file_descriptors() {
+        let policy = Policy::default();
+        let write = |fd| syscall(libc::SYS_write, [fd, 0, 0, 0, 0, 0]);
+
+        assert_eq!(policy.check(&write(1), &[]), Ok(()));
+        assert_eq!(policy.check(&write(42), &[]), Err(EBADF));
+        assert_eq!(policy.check(&write(usize::MAX), &[]), Err(EBADF));
+
+        let mut socket = syscall(libc::SYS_socket, [0; 6]);
+        socket.ret[0] = 42;
+        policy.record(&socket);
+        assert_eq!(policy.check(&write(42), &[]), Ok(()));
+
+        // Failed syscalls and `fcntl` commands other than `F_DUPFD` create no file descriptors.
+        let mut dup = syscall(libc::SYS_dup, [42, 0, 0, 0, 0, 0]);
+        dup.ret[0] = -libc::EMFILE as usize;
+        policy.record(&dup);
+        policy.record(&syscall(
+            libc::SYS_fcntl,
+            [42, libc::F_SETFL as _, 0, 0, 0, 0],
+        ));
+        assert_eq!(policy.fds.lock().unwrap().len(), 4);
+
+        policy.record(&syscall(libc::SYS_close, [42, 0, 0, 0, 0, 0]));
+        assert_eq!(policy.check(&write(42), &[]), Err(EBADF));
+
+        let epoll_ctl = syscall(libc::SYS_epoll_ctl, [1, 0, 42, 0, 0, 0]);
+        assert_eq!(policy.check(&epoll_ctl, &[]), Err(EBADF));
+
+        policy.grant(42);
+        assert_eq!(policy.check(&epoll_ctl, &[]), Ok(()));
+    }
+
+    #[test]
+    fn syscalls() {
+        let policy = Policy::default();
+        assert_eq!(
+            policy.check(&syscall(libc::SYS_execve, [0; 6]), &[]),
+            Err(ENOSYS)
+        );
+        assert_eq!(
+            policy.check(&syscall(libc::SYS_clock_gettime, [0; 6]), &[]),
+            Ok(())
+        );
+
+        let mut data = b"xx/etc/resolv.conf\0".to_vec();
+        let open = |flags: c_int| syscall(libc::SYS_open, [2, 17, flags as _, 0, 0, 0]);
+        assert_eq!(policy.check(&open(O_RDONLY | O_CLOEXEC), &data), Ok(()));
+        assert_eq!(policy.check(&open(libc::O_RDWR), &data), Err(EACCES));
+        data[3] = b'x';
+        assert_eq!(policy.check(&open(O_RDONLY), &data), Err(EACCES));
+        assert_eq!(policy.check(&open(O_RDONLY), &[]), Err(EACCES));
+    }
+
+    #[test]
+    fn ioctl_fcntl() {
+        let policy = Policy::default();
+        let ioctl = |request: usize| syscall(libc::SYS_ioctl, [0, request, 0, 0, 0, 0]);
+        let fcntl = |cmd: c_int| syscall(libc::SYS_fcntl, [0, cmd as _, 0, 0, 0, 0]);
+
+        assert_eq!(policy.check(&ioctl(TIOCGWINSZ as _), &[]), Ok(()));
+        assert_eq!(policy.check(&ioctl(libc::TIOCSTI as _), &[]), Err(ENOTTY));
+        assert_eq!(policy.check(&ioctl(libc::TIOCSCTTY as _), &[]), Err(ENOTTY));
+
+        assert_eq!(policy.check(&fcntl(F_SETFL), &[]), Ok(()));
+        assert_eq!(policy.check(&fcntl(F_DUPFD_CLOEXEC), &[]), Ok(()));
+        assert_eq!(policy.check(&fcntl(libc::F_SETOWN), &[]), Err(EINVAL));
+        assert_eq!(policy.check(&fcntl(1234), &[]), Err(EINVAL));
+
+        // The file descriptor is checked first.
+        let mut fcntl = fcntl(F_GETFL);
+        fcntl.argv[0] = 42;
+        assert_eq!(policy.check(&fcntl, &[]), Err(EBADF));
+    }
+
+    #[test]
+    fn poll() {
+        let policy = Policy::default();
+        let fds = |fds: &[c_int]| -> Vec<u8> {
+            fds.iter()
+                .flat_map(|fd| {
+                    let mut entry = [0; size_of::<pollfd>()];
+                    entry[..size_of::<c_int>()].copy_from_slice(&fd.to_ne_bytes());
+                    entry
+                })
+                .collect()
+        };
+        let poll = |nfds| syscall(libc::SYS_poll, [0, nfds, 0, 0, 0, 0]);
+
+        assert_eq!(policy.check(&poll(3), &fds(&[0, -1, 2])), Ok(()));
+        assert_eq!(policy.check(&poll(2), &fds(&[0, 42])), Err(EBADF));
+        assert_eq!(policy.check(&poll(3), &fds(&[0, 1])), Err(libc::EFAULT));
+    }
+}
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resources of the Keeps created by this process
+//!
+//! The memory and thread limits of a Keep are passed to the shim by overriding
+//! its resource notes before loading it. As the notes are loaded with the shim,
+//! the resources of SGX and SEV Keeps are part of their launch measurement.
+
+use super::kvm::MAX_NUM_CPUS;
+use super::Binary;
+
+use std::num::NonZeroU64;
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Result};
+use enarx_config::Keep;
+use once_cell::sync::OnceCell;
+use sallyport::elf::note;
+
+static RESOURCES: OnceCell<Keep> = OnceCell::new();
+
+/// Sets the resources of the Keeps created by this process.
+pub fn init(resources: Keep) -> Result<()> {
+    if let Some(threads) = resources.threads {
+        if threads.get() > MAX_NUM_CPUS {
+            bail!("A Keep supports at most {MAX_NUM_CPUS} threads");
+        }
+    }
+    if let (Some(memory), Some(max_memory)) = (resources.memory, resources.max_memory) {
+        if memory > max_memory {
+            bail!("The Keep memory of {memory} bytes exceeds the maximum of {max_memory} bytes");
+        }
+    }
+    RESOURCES
+        .set(resources)
+        .map_err(|_| anyhow!("the Keep resources were already set"))
+}
+
+/// Returns the byte ranges and values of the resource notes of `shim` to override.
+fn overrides(shim: &[u8], resources: &Keep) -> Result<Vec<(Range<usize>, u64)>> {
+    let sbin = Binary::new(shim)?;
+    let base = shim.as_ptr() as usize;
+
+    [
+        (note::MAX_THREADS, resources.threads.map(NonZeroU64::get)),
+        (note::MEMORY, resources.memory.map(|m| m.0)),
+        (note::MAX_MEMORY, resources.max_memory.map(|m| m.0)),
+    ]
+    .into_iter()
+    .filter_map(|(kind, value)| value.map(|value| (kind, value)))
+    .map(|(kind, value)| {
+        let desc = sbin
+            .notes(note::NAME, kind)
+            .find(|desc| desc.len() == std::mem::size_of::<u64>())
+            .ok_or_else(|| anyhow!("The shim does not support configuring the Keep resources"))?;
+        let start = desc.as_ptr() as usize - base;
+        Ok((start..start + desc.len(), value))
+    })
+    .collect()
+}
+
+/// Returns a copy of `shim` with the resource notes set to the configured resources.
+pub(super) fn configure(shim: &[u8]) -> Result<Vec<u8>> {
+    let mut shim = shim.to_vec();
+    if let Some(resources) = RESOURCES.get() {
+        for (range, value) in overrides(&shim, resources)? {
+            shim[range].copy_from_slice(&value.to_ne_bytes());
+        }
+    }
+    Ok(shim)
+}
+
+impl Binary<'_> {
+    /// Returns the value of the resource note `kind`, if set.
+    pub fn resource(&self, kind: u32) -> Option<u64> {
+        // Safety: converting 8 bytes into u64 should not produce any unsound behavior.
+        unsafe { self.note::<u64>(note::NAME, kind) }.filter(|value| *value != 0)
+    }
+}
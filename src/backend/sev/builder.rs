@@ -209,7 +209,16 @@ impl TryFrom<Builder> for Arc<dyn super::super::Keep> {
             anyhow::bail!("No sallyport blocks defined!");
         }
 
-        let vcpu_fd = kvm_new_vcpu(&mut kvm_fd, launcher.as_mut(), 0)?;
+        // The VMSAs of all vCPUs are measured on launch, so none can be added later.
+        let num_cpus = config.num_cpus;
+        let limit = kvm_fd.get_max_vcpus() as u64;
+        if num_cpus > limit {
+            anyhow::bail!("KVM supports at most {limit} vCPUs, but {num_cpus} were requested");
+        }
+        let cpu_fds = (0..num_cpus)
+            .rev()
+            .map(|id| kvm_new_vcpu(&mut kvm_fd, launcher.as_mut(), id))
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
         let finish = if let Some(signatures) = signatures {
             let sig_blob = signatures.sev;
@@ -231,8 +240,11 @@ impl TryFrom<Builder> for Arc<dyn super::super::Keep> {
         Ok(Arc::new(RwLock::new(super::Keep::<SnpKeepPersonality> {
             kvm_fd,
             vm_fd,
-            num_cpus: 1,
-            cpu_fds: vec![vcpu_fd],
+            num_cpus,
+            max_cpus: num_cpus,
+            ballooned: 0,
+            max_memory: config.max_memory.map(|m| m as usize),
+            cpu_fds,
             regions,
             sallyport_block_size,
             sallyports,
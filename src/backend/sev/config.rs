@@ -2,13 +2,17 @@
 
 use super::snp::Parameters;
 use crate::backend::sev::snp::launch::IdBlock;
-use crate::backend::Signatures;
+use crate::backend::{ByteSized, Signatures};
 use anyhow::{anyhow, Result};
 use goblin::elf64::program_header::PT_LOAD;
 use sallyport::elf::{self, pf::kvm::SALLYPORT};
 
 pub struct Config {
     pub sallyport_block_size: usize,
+    /// Number of vCPUs created on launch
+    pub num_cpus: u64,
+    /// Maximum memory of the Keep
+    pub max_memory: Option<u64>,
     pub signatures: Option<Signatures>,
     pub parameters: Parameters,
 }
@@ -66,8 +70,25 @@ impl super::super::Config for Config {
             }
         };
 
+        // The signed ID block sets the ID and the policy of the guest.
+        let parameters = match signatures.as_ref().map(|s| &s.sev.id_block) {
+            Some(id_block) if !id_block.is_empty() => {
+                let id_block = IdBlock::from_bytes(id_block)
+                    .ok_or_else(|| anyhow!("Invalid SEV signature IdBlock blob size."))?;
+                Parameters {
+                    policy: id_block.policy,
+                    family_id: id_block.family_id,
+                    image_id: id_block.image_id,
+                    guest_svn: id_block.guest_svn,
+                }
+            }
+            _ => parameters,
+        };
+
         Ok(Self {
             sallyport_block_size,
+            num_cpus: shim.resource(elf::note::MAX_THREADS).unwrap_or(1),
+            max_memory: shim.resource(elf::note::MAX_MEMORY),
             signatures,
             parameters,
         })
@@ -6,11 +6,12 @@ use crate::backend::probe::x86_64::{CpuId, Vendor};
 use crate::backend::Datum;
 use crate::caching::CrlList;
 
-use crate::backend::sev::snp::vcek::{
-    get_crl_reader_with_path, get_vcek_reader_with_path, sev_cache_dir,
-};
+use crate::backend::sev::snp::vcek::{get_crl_reader_with_path, get_vcek_reader_with_path};
+use crate::backend::sev::snp::Version;
+use crate::backend::sev::Firmware;
 use std::arch::x86_64::__cpuid_count;
 use std::fs::OpenOptions;
+use std::io;
 use std::mem::MaybeUninit;
 use std::time::SystemTime;
 
@@ -80,23 +81,7 @@ pub fn has_crl_cache() -> Result<Datum, Datum> {
 pub fn has_vcek_cache() -> Datum {
     let name = "SEV-SNP VCEK key cache file".to_string();
 
-    let cache_dir =
-        match sev_cache_dir().and_then(|p| p.metadata().map(|_| p).map_err(anyhow::Error::from)) {
-            Ok(cache_dir) => cache_dir,
-            Err(e) => {
-                return Datum {
-                    name,
-                    pass: false,
-                    info: Some(e.to_string()),
-                    mesg: Some(
-                        "enarx expects the directory `/var/cache/amd-sev` to exist and be readable"
-                            .to_string(),
-                    ),
-                }
-            }
-        };
-
-    match get_vcek_reader_with_path(cache_dir) {
+    match get_vcek_reader_with_path() {
         Ok((path, _)) => Datum {
             name,
             pass: true,
@@ -112,6 +97,51 @@ pub fn has_vcek_cache() -> Datum {
     }
 }
 
+/// The first SEV firmware API version supporting SEV-SNP
+const MIN_FIRMWARE_VERSION: Version = Version {
+    major: 1,
+    minor: 51,
+};
+
+pub fn firmware_version() -> Datum {
+    const NAME: &str = "SEV-SNP firmware";
+
+    let status =
+        Firmware::open().and_then(|mut sev| sev.platform_status().map_err(io::Error::from));
+    match status {
+        Ok(status) => {
+            let version_ok = status.build.version >= MIN_FIRMWARE_VERSION;
+            let info = if status.is_rmp_init {
+                format!("{}", status.build)
+            } else {
+                format!("{}, RMP not initialized", status.build)
+            };
+            let pass = version_ok && status.is_rmp_init;
+            Datum {
+                name: NAME.into(),
+                pass,
+                info: Some(info),
+                mesg: (!pass).then(|| {
+                    format!(
+                        "Enarx requires the SEV firmware API version {MIN_FIRMWARE_VERSION} or later \
+                        with SEV-SNP initialized. Update the firmware and enable SEV-SNP in the BIOS."
+                    )
+                }),
+            }
+        }
+        Err(e) => Datum {
+            name: NAME.into(),
+            pass: false,
+            info: Some(e.to_string()),
+            mesg: Some(
+                "Failed to query the platform status from `/dev/sev`. \
+                Check that the `ccp` kernel module is loaded with SEV-SNP support."
+                    .into(),
+            ),
+        },
+    }
+}
+
 pub fn has_reasonable_memlock_rlimit() -> Datum {
     let mut rlimits = MaybeUninit::uninit();
     let res = unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, rlimits.as_mut_ptr()) };
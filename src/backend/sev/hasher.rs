@@ -50,16 +50,21 @@ impl Hasher {
     }
 
     pub fn update_finish(&mut self) {
-        let page_info = PageInfo {
-            digest_cur: self.digest,
-            page_type: PageType::Vmsa as _,
-            gpa: 0xFFFFFFFFF000,
-            contents: SEV_SNP_VMSA_SHA384,
-            ..Default::default()
-        };
-
-        let page_info_digest = digest::digest(&digest::SHA384, page_info.as_bytes());
-        self.digest.copy_from_slice(page_info_digest.as_ref());
+        // The firmware measures the VMSA of every vCPU created by the `Builder`
+        // at launch finish. KVM resets all vCPUs to the same state, so their
+        // VMSAs have the same contents, and APs take the same reset vector.
+        for _ in 0..self.config.num_cpus {
+            let page_info = PageInfo {
+                digest_cur: self.digest,
+                page_type: PageType::Vmsa as _,
+                gpa: 0xFFFFFFFFF000,
+                contents: SEV_SNP_VMSA_SHA384,
+                ..Default::default()
+            };
+
+            let page_info_digest = digest::digest(&digest::SHA384, page_info.as_bytes());
+            self.digest.copy_from_slice(page_info_digest.as_ref());
+        }
     }
 }
 
@@ -105,3 +110,40 @@ impl TryFrom<Hasher> for Vec<u8> {
         Ok(id_block.as_bytes().into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch_digest(num_cpus: u64) -> [u8; 48] {
+        let mut hasher = Hasher::try_from(Config {
+            sallyport_block_size: 0,
+            num_cpus,
+            max_memory: None,
+            signatures: None,
+            parameters: Default::default(),
+        })
+        .unwrap();
+        hasher.update(PageType::Normal, 0x1000, Some(&[0u8; Page::SIZE]));
+        hasher.update_finish();
+        hasher.digest
+    }
+
+    #[test]
+    fn vmsa_per_vcpu() {
+        let one = launch_digest(1);
+        let two = launch_digest(2);
+        assert_ne!(one, two);
+
+        // The second VMSA extends the digest of a single vCPU Keep.
+        let page_info = PageInfo {
+            digest_cur: one,
+            page_type: PageType::Vmsa as _,
+            gpa: 0xFFFFFFFFF000,
+            contents: SEV_SNP_VMSA_SHA384,
+            ..Default::default()
+        };
+        let expected = digest::digest(&digest::SHA384, page_info.as_bytes());
+        assert_eq!(&two[..], expected.as_ref());
+    }
+}
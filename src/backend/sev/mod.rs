@@ -17,7 +17,7 @@ use super::kvm::mem::Region;
 use super::kvm::{Keep, KeepPersonality};
 use super::Loader;
 use data::{
-    dev_kvm, dev_sev, dev_sev_readable, dev_sev_writable, has_crl_cache,
+    dev_kvm, dev_sev, dev_sev_readable, dev_sev_writable, firmware_version, has_crl_cache,
     has_reasonable_memlock_rlimit, kvm_version, sev_enabled_in_kernel, CPUIDS,
 };
 
@@ -171,6 +171,10 @@ impl super::Backend for Backend {
         ]
     }
 
+    fn checks(&self) -> Vec<super::Datum> {
+        vec![firmware_version()]
+    }
+
     #[inline]
     fn keep(
         &self,
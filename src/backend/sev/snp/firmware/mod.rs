@@ -56,6 +56,9 @@ bitflags! {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Identifier(Vec<u8>);
 
+/// The processor model, whose certificates are fetched from the AMD KDS
+pub const PRODUCT: &str = "Milan";
+
 impl Identifier {
     // Get a unique identifier for the VCEK key to be used in linux file names
     pub fn vcek_cache_name(&self, version: &TcbVersion) -> String {
@@ -65,15 +68,11 @@ impl Identifier {
         )
     }
 
-    /// Get the URL to download the VCEK.
-    pub fn vcek_url(&self, version: &TcbVersion) -> String {
+    /// Get the URL to download the VCEK from the AMD KDS at `kds`.
+    pub fn vcek_url(&self, kds: &str, version: &TcbVersion) -> String {
         format!(
-            "https://kdsintf.amd.com/vcek/v1/Milan/{:x}?blSPL={:02}&teeSPL={:02}&snpSPL={:02}&ucodeSPL={:02}",
-            self,
-            version.bootloader,
-            version.tee,
-            version.snp,
-            version.microcode,
+            "{kds}/vcek/v1/{PRODUCT}/{:x}?blSPL={:02}&teeSPL={:02}&snpSPL={:02}&ucodeSPL={:02}",
+            self, version.bootloader, version.tee, version.snp, version.microcode,
         )
     }
 }
@@ -180,6 +179,22 @@ pub struct TcbVersion {
     pub microcode: u8,
 }
 
+impl From<&TcbVersion> for u64 {
+    /// Encodes the version like the reported TCB of an attestation report.
+    fn from(version: &TcbVersion) -> Self {
+        u64::from_le_bytes([
+            version.bootloader,
+            version.tee,
+            version._reserved[0],
+            version._reserved[1],
+            version._reserved[2],
+            version._reserved[3],
+            version.snp,
+            version.microcode,
+        ])
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -206,12 +221,21 @@ mod test {
             ..Default::default()
         };
 
-        assert_eq!(URL, id.vcek_url(&tcb));
+        assert_eq!(URL, id.vcek_url("https://kdsintf.amd.com", &tcb));
     }
 
     #[test]
     fn test_tcbversion() {
-        assert_eq!(size_of::<TcbVersion>(), size_of::<u64>())
+        assert_eq!(size_of::<TcbVersion>(), size_of::<u64>());
+
+        let version = TcbVersion {
+            bootloader: 3,
+            tee: 0,
+            _reserved: [0; 4],
+            snp: 8,
+            microcode: 115,
+        };
+        assert_eq!(u64::from(&version), 0x7308_0000_0000_0003);
     }
 
     testaso! {
@@ -228,6 +228,30 @@ impl<'a, 'b> Finish<'a, 'b> {
     }
 }
 
+/// Bits of the SNP guest policy, see Table 9 of the SEV-SNP firmware ABI specification.
+pub mod policy {
+    /// Minimum minor version of the firmware ABI
+    pub const ABI_MINOR: u64 = 0xff;
+
+    /// Minimum major version of the firmware ABI
+    pub const ABI_MAJOR: u64 = 0xff << 8;
+
+    /// SMT is allowed
+    pub const SMT: u64 = 1 << 16;
+
+    /// Reserved, must be one
+    pub const RESERVED: u64 = 1 << 17;
+
+    /// Association with a migration agent is allowed
+    pub const MIGRATE_MA: u64 = 1 << 18;
+
+    /// Debugging is allowed
+    pub const DEBUG: u64 = 1 << 19;
+
+    /// The guest can only be activated on one socket
+    pub const SINGLE_SOCKET: u64 = 1 << 20;
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct IdBlock {
@@ -1,26 +1,42 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::attestation::{pki, roots};
+use crate::backend::sev::snp::firmware::{Identifier, TcbVersion, PRODUCT};
 use crate::backend::sev::Firmware;
+use crate::caching::{fetch_certificates, store_once, Cache, CRLS};
+use crate::endpoints::Endpoints;
 
-use std::fs::{self, remove_file};
+use std::fs;
 use std::io::{self, ErrorKind, Read};
-use std::os::unix::fs::OpenOptionsExt;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
-use anyhow::Context;
-use colorful::core::StrMarker;
-use der::{Decode, Document, Sequence};
+use anyhow::{ensure, Context};
+use der::{Decode, Document, Encode, Sequence};
+use x509_cert::Certificate;
 
+/// Name of the cached AMD certificate chain of the VCEK, the ASK followed by the ARK
+pub const CERT_CHAIN: &str = "cert_chain.der";
+
+/// Evidence of the platform, which the shim embeds in the CSR of an SNP Keep
 #[derive(Sequence)]
 pub struct SnpEvidence {
     pub vcek: Document,
     pub crl: Document,
+    /// The ASK and ARK, which issued the VCEK, if cached
+    #[asn1(context_specific = "0", optional = "true")]
+    pub chain: Option<Vec<Certificate>>,
+    /// The reported TCB version of the platform, which identifies the VCEK
+    #[asn1(context_specific = "1", optional = "true")]
+    pub tcb: Option<u64>,
 }
 
 impl SnpEvidence {
     pub fn read() -> anyhow::Result<Self> {
+        let (id, tcb) = platform_id()?;
+
         let mut vcek = Vec::new();
-        let mut vcek_reader = get_vcek_reader()?;
+        let (_, mut vcek_reader) = read(Cache::AMD.find(&id.vcek_cache_name(&tcb))?)?;
         io::copy(&mut vcek_reader, &mut vcek)?;
 
         let mut crl = Vec::new();
@@ -30,46 +46,79 @@ impl SnpEvidence {
         Ok(Self {
             vcek: Document::from_der(&vcek)?,
             crl: Document::from_der(&crl)?,
+            chain: read_cert_chain()?,
+            tcb: Some(u64::from(&tcb)),
         })
     }
 }
 
+/// Returns the cached AMD certificate chain of the VCEK, if any
+pub fn read_cert_chain() -> anyhow::Result<Option<Vec<Certificate>>> {
+    let path = match Cache::AMD.find(CERT_CHAIN) {
+        Ok(path) => path,
+        Err(e)
+            if e.downcast_ref::<io::Error>().map(io::Error::kind) == Some(ErrorKind::NotFound) =>
+        {
+            return Ok(None)
+        }
+        Err(e) => return Err(e),
+    };
+    let chain = fs::read(&path).with_context(|| format!("Error reading `{}`", path.display()))?;
+    let chain = Vec::<Certificate>::from_der(&chain)
+        .with_context(|| format!("Error decoding `{}`", path.display()))?;
+    Ok(Some(chain))
+}
+
+/// Fetches the AMD certificate chain of the VCEK and verifies it against the trusted `arks`.
+pub fn fetch_cert_chain(arks: &[Certificate]) -> anyhow::Result<Vec<Certificate>> {
+    let url = Endpoints::get()?.amd.cert_chain(PRODUCT);
+    let chain = fetch_certificates(&url)?;
+    ensure!(
+        chain.len() == 2,
+        "expected the ASK and ARK at `{url}`, found {} certificates",
+        chain.len()
+    );
+    pki::verify_chain_to(&chain, arks, SystemTime::now())
+        .context("Invalid AMD certificate chain")?;
+    Ok(chain)
+}
+
 /// Return a reader, which provides the VCEK certificate
 pub fn get_vcek_reader() -> anyhow::Result<Box<dyn Read>> {
-    get_vcek_reader_with_path(sev_cache_dir()?).map(|(_, r)| r)
+    get_vcek_reader_with_path().map(|(_, r)| r)
 }
 
-/// Update the global VCEK cache file
+/// Update the VCEK cache file, unless it exists in any cache directory
 pub fn vcek_write() -> anyhow::Result<()> {
-    vcek_write_with_path(sev_cache_dir()?)?;
+    if get_vcek_reader_with_path().is_err() {
+        vcek_write_with_path(Cache::AMD.write_dir()?)?;
+    }
     Ok(())
 }
 
-/// Returns a reader and a path, which provides the AMD CRLs
-pub fn get_crl_reader_with_path() -> anyhow::Result<(PathBuf, Box<dyn Read>)> {
-    read(sev_cache_dir()?, "crls.der".to_str())
+/// Update the certificate chain cache file, unless it exists in any cache directory
+///
+/// The chain is optional, Keeps only embed it in their evidence if it is cached.
+pub fn cert_chain_write() -> anyhow::Result<()> {
+    if Cache::AMD.find(CERT_CHAIN).is_err() {
+        let chain = fetch_cert_chain(&roots::amd_arks()?)?.to_der()?;
+        Cache::AMD
+            .store(CERT_CHAIN, &chain)
+            .context("Failed to cache the AMD certificate chain")?;
+    }
+    Ok(())
 }
 
-/// Returns the "system-level" search path for the SEV
-/// certificate chain (`/var/cache/amd-sev`).
-pub fn sev_cache_dir() -> anyhow::Result<PathBuf> {
-    const CACHE_DIR: &str = "/var/cache";
-
-    let mut sys = PathBuf::from(CACHE_DIR);
-    if sys.exists() && sys.is_dir() {
-        sys.push("amd-sev");
-        Ok(sys)
-    } else {
-        Err(io::Error::from(ErrorKind::NotFound))
-            .with_context(|| format!("Directory `{CACHE_DIR}` does not exist!"))
-    }
+/// Returns a reader and a path, which provides the AMD CRLs
+pub fn get_crl_reader_with_path() -> anyhow::Result<(PathBuf, Box<dyn Read>)> {
+    read(Cache::AMD.find(CRLS)?)
 }
 
 /// Returns a reader and a path, which provides the VCEK certificate
-pub fn get_vcek_reader_with_path(cache_dir: PathBuf) -> anyhow::Result<(PathBuf, Box<dyn Read>)> {
-    let (_, path) = get_vcek_url_path()?;
+pub fn get_vcek_reader_with_path() -> anyhow::Result<(PathBuf, Box<dyn Read>)> {
+    let (_, name) = get_vcek_url_path()?;
 
-    read(cache_dir, path)
+    read(Cache::AMD.find(&name)?)
 }
 
 /// Write the VCEK certificate to a cache directory
@@ -88,49 +137,33 @@ pub fn vcek_write_with_path(cache_dir: PathBuf) -> anyhow::Result<PathBuf> {
     })
 }
 
-fn get_vcek_url_path() -> anyhow::Result<(String, String)> {
-    // Get the platform information.
+/// Returns the identifier and the reported TCB version of the platform, which identify its VCEK.
+pub fn platform_id() -> anyhow::Result<(Identifier, TcbVersion)> {
     let mut sev = Firmware::open().context("failed to open /dev/sev")?;
     let id = sev.identifier().context("failed to query identifier")?;
     let status = sev
         .platform_status()
         .context("failed to query platform status")?;
+    Ok((id, status.tcb.reported_version))
+}
 
-    let url = id.vcek_url(&status.tcb.reported_version);
+fn get_vcek_url_path() -> anyhow::Result<(String, String)> {
+    let (id, version) = platform_id()?;
+
+    let url = id.vcek_url(Endpoints::get()?.amd.base(), &version);
 
-    let path = id.vcek_cache_name(&status.tcb.reported_version);
+    let path = id.vcek_cache_name(&version);
     Ok((url, path))
 }
 
 // read the cached file
-fn read(cache_dir: PathBuf, name: String) -> anyhow::Result<(PathBuf, Box<dyn Read>)> {
-    let mut path_locked = cache_dir.clone();
-    path_locked.push(name.clone() + ".lck");
-
-    let mut path = cache_dir;
-    path.push(&name);
-
-    let mut retries = 100;
-
-    while retries > 0 {
-        let file =
-            fs::File::open(&path).with_context(|| format!("Error reading `{}`", path.display()))?;
-
-        if !path_locked.exists() {
-            // no write in progress
-            return Ok((path, Box::new(file)));
-        }
-
-        // sleep, write may be in progress
-        std::thread::sleep(std::time::Duration::from_millis(200));
-        retries -= 1;
-    }
-
-    Err(io::Error::from(ErrorKind::InvalidData))
-        .with_context(|| format!("Potential stale lock file {:?}", &path_locked))
+fn read(path: PathBuf) -> anyhow::Result<(PathBuf, Box<dyn Read>)> {
+    let file =
+        fs::File::open(&path).with_context(|| format!("Error reading `{}`", path.display()))?;
+    Ok((path, Box::new(file)))
 }
 
-// write the file with the reader provided by the contents function
+// write the file with the reader provided by the contents function, unless it exists
 fn write(
     cache_dir: PathBuf,
     name: String,
@@ -139,46 +172,14 @@ fn write(
     // ignore error, error is handled in lock file creation
     let _ = fs::create_dir_all(&cache_dir);
 
-    let mut path_locked = cache_dir.clone();
-    path_locked.push(name.clone() + ".lck");
-
     let mut path = cache_dir;
     path.push(&name);
 
-    if path.exists() {
-        return Ok(path);
-    }
-
-    let _lock = fs::OpenOptions::new()
-        .mode(0o644)
-        .write(true)
-        .create_new(true)
-        .open(&path_locked)
-        .with_context(|| format!("Error creating lockfile {}", path_locked.display()))?;
-
-    let mut file = fs::OpenOptions::new()
-        .mode(0o644)
-        .write(true)
-        .read(true)
-        .create_new(true)
-        .open(&path)
-        .with_context(|| format!("Error creating {}", path.display()))
-        .map_err(|err| {
-            let _ = remove_file(&path_locked);
-            err
-        })?;
-
-    let _ = contents()
-        .and_then(|mut contents| {
-            io::copy(&mut contents, &mut file).with_context(|| format!("Error writing {:?}", &path))
-        })
-        .map_err(|e| {
-            let _ = remove_file(&path);
-            let _ = remove_file(&path_locked);
-            e
-        })?;
-
-    let _ = remove_file(&path_locked);
+    store_once(&path, || {
+        let mut buf = Vec::new();
+        io::copy(&mut contents()?, &mut buf).with_context(|| format!("Error writing {path:?}"))?;
+        Ok(buf)
+    })?;
 
     Ok(path)
 }
@@ -220,7 +221,7 @@ mod tests {
                 let mut retries = 100;
 
                 while retries > 0 {
-                    match read(tmp_dir_path.clone(), "test".to_string()) {
+                    match read(tmp_dir_path.join("test")) {
                         Err(e)
                             if matches!(
                                 e.downcast_ref::<io::Error>().map(io::Error::kind),
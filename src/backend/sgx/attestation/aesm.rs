@@ -0,0 +1,479 @@
+// SPDX-License-Identifier: Apache-2.0
+//
+// CREDITS
+// * https://github.com/fortanix/rust-sgx for examples of AESM requests.
+
+use super::{
+    get_algorithm_id, Provider, QuoteProvider, SGX_KEY_ID_SIZE, SGX_QL_ALG_ECDSA_P256,
+    SGX_REPORT_SIZE, SGX_TI_SIZE,
+};
+use crate::backend::sgx::AESM_SOCKET;
+use crate::protobuf::aesm_proto::{
+    Request, Request_GetQuoteExRequest, Request_GetQuoteSizeExRequest,
+    Request_GetSupportedAttKeyIDNumRequest, Request_GetSupportedAttKeyIDsRequest,
+    Request_InitQuoteExRequest, Response,
+};
+
+use std::env;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::ops::{Deref, DerefMut};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+use protobuf::Message;
+
+/// Environment variable overriding the path of the AESM daemon socket
+const AESM_SOCKET_VAR: &str = "ENARX_AESM_SOCKET";
+
+const AESM_REQUEST_TIMEOUT: u32 = 1_000_000;
+
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AesmError {
+    UnexpectedError,
+    NoDeviceError,
+    ParameterError,
+    EpidblobError,
+    EpidRevokedError,
+    GetLicensetokenError,
+    SessionInvalid,
+    MaxNumSessionReached,
+    PsdaUnavailable,
+    EphSessionFailed,
+    LongTermPairingFailed,
+    NetworkError,
+    NetworkBusyError,
+    ProxySettingAssist,
+    FileAccessError,
+    SgxProvisionFailed,
+    ServiceStopped,
+    Busy,
+    BackendServerBusy,
+    UpdateAvailable,
+    OutOfMemoryError,
+    MsgError,
+    ThreadError,
+    SgxDeviceNotAvailable,
+    EnableSgxDeviceFailed,
+    PlatformInfoBlobInvalidSig,
+    ServiceNotAvailable,
+    KdfMismatch,
+    OutOfEpc,
+    ServiceUnavailable,
+    UnrecognizedPlatform,
+    EcdsaIdMismatch,
+    PathnameBufferOverflowError,
+    ErrorStoredKey,
+    PubKeyIdMismatch,
+    InvalidPceSigScheme,
+    AttKeyBlobError,
+    UnsupportedAttKeyId,
+    UnsupportedLoadingPolicy,
+    InterfaceUnavailable,
+    PlatformLibUnavailable,
+    AttKeyNotInitialized,
+    AttKeyCertDataInvalid,
+    NoPlatformCertData,
+    ErrorReport,
+    EnclaveLost,
+    InvalidReport,
+    EnclaveLoadError,
+    UnableToGenerateQeReport,
+    KeyCertificationError,
+    ConfigUnsupported,
+    Unknown(u32),
+}
+
+impl From<u32> for AesmError {
+    fn from(n: u32) -> AesmError {
+        use self::AesmError::*;
+        match n {
+            1 => UnexpectedError,
+            2 => NoDeviceError,
+            3 => ParameterError,
+            4 => EpidblobError,
+            5 => EpidRevokedError,
+            6 => GetLicensetokenError,
+            7 => SessionInvalid,
+            8 => MaxNumSessionReached,
+            9 => PsdaUnavailable,
+            10 => EphSessionFailed,
+            11 => LongTermPairingFailed,
+            12 => NetworkError,
+            13 => NetworkBusyError,
+            14 => ProxySettingAssist,
+            15 => FileAccessError,
+            16 => SgxProvisionFailed,
+            17 => ServiceStopped,
+            18 => Busy,
+            19 => BackendServerBusy,
+            20 => UpdateAvailable,
+            21 => OutOfMemoryError,
+            22 => MsgError,
+            23 => ThreadError,
+            24 => SgxDeviceNotAvailable,
+            25 => EnableSgxDeviceFailed,
+            26 => PlatformInfoBlobInvalidSig,
+            27 => ServiceNotAvailable,
+            28 => KdfMismatch,
+            29 => OutOfEpc,
+            30 => ServiceUnavailable,
+            31 => UnrecognizedPlatform,
+            32 => EcdsaIdMismatch,
+            33 => PathnameBufferOverflowError,
+            34 => ErrorStoredKey,
+            35 => PubKeyIdMismatch,
+            36 => InvalidPceSigScheme,
+            37 => AttKeyBlobError,
+            38 => UnsupportedAttKeyId,
+            39 => UnsupportedLoadingPolicy,
+            40 => InterfaceUnavailable,
+            41 => PlatformLibUnavailable,
+            42 => AttKeyNotInitialized,
+            43 => AttKeyCertDataInvalid,
+            44 => NoPlatformCertData,
+            45 => ErrorReport,
+            46 => EnclaveLost,
+            47 => InvalidReport,
+            48 => EnclaveLoadError,
+            49 => UnableToGenerateQeReport,
+            50 => KeyCertificationError,
+            51 => ConfigUnsupported,
+            _ => Unknown(n),
+        }
+    }
+}
+
+struct AesmTransaction(Request);
+
+impl Deref for AesmTransaction {
+    type Target = Request;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for AesmTransaction {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl AesmTransaction {
+    fn new() -> Self {
+        Self(Request::new())
+    }
+
+    fn request(&self, socket: &Path) -> Result<Response, Error> {
+        let mut request = Vec::<u8>::new();
+
+        self.write_to_vec(&mut request).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("Invalid protobuf request: {:?}. Error: {:?}", self.0, e),
+            )
+        })?;
+
+        let mut stream = UnixStream::connect(socket)?;
+
+        let request_len = request.len() as u32;
+
+        // AESM daemon requires the length prepended before the request.
+        stream.write_all(&request_len.to_le_bytes())?;
+        stream.write_all(&request)?;
+        stream.flush()?;
+
+        let mut response_len_bytes = [0u8; 4];
+        stream.read_exact(&mut response_len_bytes)?;
+        let response_len = u32::from_le_bytes(response_len_bytes);
+
+        let mut response_bytes = vec![0; response_len as usize];
+        stream.read_exact(&mut response_bytes)?;
+
+        let response = Message::parse_from_bytes(&response_bytes)?;
+
+        Ok(response)
+    }
+}
+
+/// Returns an error for the AESM error `code` in the response to `request`.
+fn aesm_error(kind: ErrorKind, request: &str, code: u32) -> Error {
+    Error::new(
+        kind,
+        format!("{request}: error: {:?}", AesmError::from(code)),
+    )
+}
+
+/// The quote provider of the AESM daemon, which talks protobuf on a Unix socket
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Aesm {
+    socket: PathBuf,
+}
+
+impl Default for Aesm {
+    /// Connects to the socket in `ENARX_AESM_SOCKET`, which defaults to [`AESM_SOCKET`].
+    fn default() -> Self {
+        match env::var_os(AESM_SOCKET_VAR) {
+            Some(socket) if !socket.is_empty() => Self::new(socket),
+            _ => Self::new(AESM_SOCKET),
+        }
+    }
+}
+
+impl Aesm {
+    /// Connects to the AESM daemon at `socket`.
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+        }
+    }
+
+    /// The path of the AESM daemon socket
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    fn get_key_id_num(&self) -> Result<u32, Error> {
+        let mut transaction = AesmTransaction::new();
+
+        let mut msg = Request_GetSupportedAttKeyIDNumRequest::new();
+        msg.set_timeout(AESM_REQUEST_TIMEOUT);
+        transaction.set_getSupportedAttKeyIDNumReq(msg);
+
+        let pb_msg = transaction.request(&self.socket)?;
+
+        let res = pb_msg.get_getSupportedAttKeyIDNumRes();
+
+        if res.get_errorCode() != 0 {
+            return Err(aesm_error(
+                ErrorKind::Other,
+                "GetSupportedAttKeyIDNum",
+                res.get_errorCode(),
+            ));
+        }
+
+        Ok(res.get_att_key_id_num())
+    }
+
+    fn get_key_ids(&self, num_key_ids: u32) -> Result<Vec<Vec<u8>>, Error> {
+        let expected_buffer_size = num_key_ids * SGX_KEY_ID_SIZE as u32;
+
+        let mut transaction = AesmTransaction::new();
+
+        let mut msg = Request_GetSupportedAttKeyIDsRequest::new();
+        msg.set_timeout(AESM_REQUEST_TIMEOUT);
+        msg.set_buf_size(expected_buffer_size);
+        transaction.set_getSupportedAttKeyIDsReq(msg);
+
+        let pb_msg = transaction.request(&self.socket)?;
+
+        let res = pb_msg.get_getSupportedAttKeyIDsRes();
+
+        if res.get_errorCode() != 0 {
+            return Err(aesm_error(
+                ErrorKind::InvalidData,
+                "GetSupportedAttKeyIDs",
+                res.get_errorCode(),
+            ));
+        }
+
+        let key_ids_blob = res.get_att_key_ids();
+        Ok(key_ids_blob
+            .chunks_exact(SGX_KEY_ID_SIZE)
+            .map(Vec::from)
+            .collect())
+    }
+}
+
+impl QuoteProvider for Aesm {
+    fn description(&self) -> String {
+        format!("AESM ({})", self.socket.display())
+    }
+
+    fn kind(&self) -> Provider {
+        Provider::Aesm
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        if self.socket.exists() {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("socket {} does not exist", self.socket.display()),
+            ))
+        }
+    }
+
+    fn get_attestation_key_id(&self) -> Result<Vec<u8>, Error> {
+        let num_key_ids = self.get_key_id_num()?;
+
+        if num_key_ids == 0 {
+            return Err(Error::new(ErrorKind::Other, "No attestation key IDs"));
+        }
+
+        let key_ids = self.get_key_ids(num_key_ids)?;
+
+        // Select the ECDSA key that will be used later, if ECDSA is not supported the key id is still present - https://github.com/intel/linux-sgx/issues/536
+        let ecdsa_key_id = key_ids
+            .into_iter()
+            .find(|id| SGX_QL_ALG_ECDSA_P256 == get_algorithm_id(id))
+            .ok_or_else(|| Error::new(ErrorKind::Other, "ECDSA attestation key not available"))?;
+
+        Ok(ecdsa_key_id)
+    }
+
+    fn get_target_info(
+        &self,
+        akid: Vec<u8>,
+        size: usize,
+        out_buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        if out_buf.len() != SGX_TI_SIZE {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "Invalid output buffer size: {} != {}",
+                    out_buf.len(),
+                    SGX_TI_SIZE
+                ),
+            ));
+        }
+
+        let mut transaction = AesmTransaction::new();
+        let mut msg = Request_InitQuoteExRequest::new();
+
+        msg.set_timeout(AESM_REQUEST_TIMEOUT);
+        msg.set_b_pub_key_id(true);
+        msg.set_att_key_id(akid);
+        msg.set_buf_size(size as _);
+        transaction.set_initQuoteExReq(msg);
+
+        let pb_msg = transaction.request(&self.socket)?;
+
+        let res = pb_msg.get_initQuoteExRes();
+
+        if res.get_errorCode() != 0 {
+            return Err(aesm_error(
+                ErrorKind::InvalidData,
+                "InitQuoteExRequest",
+                res.get_errorCode(),
+            ));
+        }
+
+        let ti = res.get_target_info();
+
+        if ti.len() != SGX_TI_SIZE {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "InitQuoteEx: Invalid TARGETINFO size: {} != {}",
+                    ti.len(),
+                    SGX_TI_SIZE
+                ),
+            ));
+        }
+
+        out_buf.copy_from_slice(ti);
+
+        Ok(ti.len())
+    }
+
+    fn get_key_size(&self, akid: Vec<u8>) -> Result<usize, Error> {
+        let mut transaction = AesmTransaction::new();
+        let mut msg = Request_InitQuoteExRequest::new();
+
+        msg.set_timeout(AESM_REQUEST_TIMEOUT);
+        msg.set_b_pub_key_id(false);
+        msg.set_att_key_id(akid);
+        transaction.set_initQuoteExReq(msg);
+
+        let pb_msg = transaction.request(&self.socket)?;
+
+        let res = pb_msg.get_initQuoteExRes();
+
+        if res.get_errorCode() != 0 {
+            return Err(aesm_error(
+                ErrorKind::InvalidData,
+                "InitQuoteEx",
+                res.get_errorCode(),
+            ));
+        }
+
+        Ok(res.get_pub_key_id_size() as usize)
+    }
+
+    fn get_quote_size(&self, akid: Vec<u8>) -> Result<usize, Error> {
+        let mut transaction = AesmTransaction::new();
+        let mut msg = Request_GetQuoteSizeExRequest::new();
+
+        msg.set_timeout(AESM_REQUEST_TIMEOUT);
+        msg.set_att_key_id(akid);
+        transaction.set_getQuoteSizeExReq(msg);
+
+        let pb_msg = transaction.request(&self.socket)?;
+
+        let res = pb_msg.get_getQuoteSizeExRes();
+
+        if res.get_errorCode() != 0 {
+            return Err(aesm_error(
+                ErrorKind::InvalidData,
+                "GetQuoteSizeEx",
+                res.get_errorCode(),
+            ));
+        }
+
+        Ok(res.get_quote_size() as usize)
+    }
+
+    fn get_quote(&self, report: &[u8], akid: Vec<u8>, out_buf: &mut [u8]) -> Result<usize, Error> {
+        if report.len() < SGX_REPORT_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid REPORT size: {} < {}",
+                    report.len(),
+                    SGX_REPORT_SIZE
+                ),
+            ));
+        }
+
+        let mut transaction = AesmTransaction::new();
+
+        let mut msg = Request_GetQuoteExRequest::new();
+        msg.set_timeout(AESM_REQUEST_TIMEOUT);
+        msg.set_report(report[0..SGX_REPORT_SIZE].to_vec());
+        msg.set_att_key_id(akid);
+        msg.set_buf_size(out_buf.len() as u32);
+        transaction.set_getQuoteExReq(msg);
+
+        let pb_msg = transaction.request(&self.socket)?;
+
+        let res = pb_msg.get_getQuoteExRes();
+
+        if res.get_errorCode() != 0 {
+            return Err(aesm_error(
+                ErrorKind::InvalidData,
+                "GetQuoteEx",
+                res.get_errorCode(),
+            ));
+        }
+
+        let quote = res.get_quote();
+
+        if quote.len() != out_buf.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "GetQuoteEx: Invalid QUOTE size: {} != {}",
+                    quote.len(),
+                    out_buf.len()
+                ),
+            ));
+        }
+
+        out_buf.copy_from_slice(quote);
+        Ok(quote.len())
+    }
+}
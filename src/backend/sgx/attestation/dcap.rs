@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{key_id, Provider, QuoteProvider, SGX_QL_ALG_ECDSA_P256, SGX_REPORT_SIZE, SGX_TI_SIZE};
+
+use std::ffi::{c_void, CStr};
+use std::io::{Error, ErrorKind};
+use std::os::raw::c_char;
+
+/// The DCAP quote library, which loads the quoting enclave in-process
+pub const DCAP_QL: &str = "libsgx_dcap_ql.so.1";
+
+const SGX_QL_SUCCESS: u32 = 0;
+
+type GetTargetInfo = unsafe extern "C" fn(target_info: *mut u8) -> u32;
+type GetQuoteSize = unsafe extern "C" fn(quote_size: *mut u32) -> u32;
+type GetQuote = unsafe extern "C" fn(report: *const u8, quote_size: u32, quote: *mut u8) -> u32;
+
+/// Returns an error for the `quote3_error_t` `code` returned by `function`.
+fn quote3_error(function: &str, code: u32) -> Error {
+    Error::new(
+        ErrorKind::Other,
+        format!("{function}: error: quote3_error_t {code:#06x}"),
+    )
+}
+
+/// Returns the last error of the dynamic linker.
+fn dl_error() -> String {
+    // Safety: `dlerror` returns NULL or a C string, which is valid until the next `dl*` call.
+    let err = unsafe { libc::dlerror() };
+    if err.is_null() {
+        "unknown error".into()
+    } else {
+        unsafe { CStr::from_ptr(err) }.to_string_lossy().into()
+    }
+}
+
+/// The quote provider of the Intel DCAP quote library, which quotes without the AESM daemon
+pub struct Dcap {
+    lib: *mut c_void,
+    get_target_info: GetTargetInfo,
+    get_quote_size: GetQuoteSize,
+    get_quote: GetQuote,
+}
+
+// Safety: the DCAP quote library serializes the calls into the quoting enclave.
+unsafe impl Send for Dcap {}
+unsafe impl Sync for Dcap {}
+
+impl Drop for Dcap {
+    fn drop(&mut self) {
+        // Safety: `lib` is a handle of `dlopen`, which is not used afterwards.
+        unsafe { libc::dlclose(self.lib) };
+    }
+}
+
+impl Dcap {
+    /// Loads the DCAP quote library.
+    pub fn open() -> Result<Self, Error> {
+        let name = format!("{DCAP_QL}\0");
+
+        // Safety: `name` is NUL-terminated.
+        let lib = unsafe { libc::dlopen(name.as_ptr() as *const c_char, libc::RTLD_NOW) };
+        if lib.is_null() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("failed to load {DCAP_QL}: {}", dl_error()),
+            ));
+        }
+
+        let symbol = |name: &str| {
+            let symbol = format!("{name}\0");
+            // Safety: `lib` is a valid handle and `symbol` is NUL-terminated.
+            let ptr = unsafe { libc::dlsym(lib, symbol.as_ptr() as *const c_char) };
+            if ptr.is_null() {
+                Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("{DCAP_QL} does not export {name}: {}", dl_error()),
+                ))
+            } else {
+                Ok(ptr)
+            }
+        };
+
+        let symbols = symbol("sgx_qe_get_target_info").and_then(|get_target_info| {
+            Ok((
+                get_target_info,
+                symbol("sgx_qe_get_quote_size")?,
+                symbol("sgx_qe_get_quote")?,
+            ))
+        });
+        let (get_target_info, get_quote_size, get_quote) = match symbols {
+            Ok(symbols) => symbols,
+            Err(e) => {
+                // Safety: `lib` is a handle of `dlopen`, which is not used afterwards.
+                unsafe { libc::dlclose(lib) };
+                return Err(e);
+            }
+        };
+
+        // Safety: the symbols are the functions of the DCAP quote library API with these signatures.
+        unsafe {
+            Ok(Self {
+                lib,
+                get_target_info: std::mem::transmute::<*mut c_void, GetTargetInfo>(get_target_info),
+                get_quote_size: std::mem::transmute::<*mut c_void, GetQuoteSize>(get_quote_size),
+                get_quote: std::mem::transmute::<*mut c_void, GetQuote>(get_quote),
+            })
+        }
+    }
+}
+
+impl QuoteProvider for Dcap {
+    fn description(&self) -> String {
+        format!("DCAP ({DCAP_QL})")
+    }
+
+    fn kind(&self) -> Provider {
+        Provider::Dcap
+    }
+
+    /// Loads the quoting enclave and asks it for the size of its quotes, which fails
+    /// if the quoting enclave cannot certify its attestation key.
+    fn check(&self) -> Result<(), Error> {
+        self.get_target_info(vec![], 0, &mut [0; SGX_TI_SIZE])?;
+        self.get_quote_size(vec![])?;
+        Ok(())
+    }
+
+    /// The DCAP quote library always quotes with the ECDSA P-256 attestation key of the
+    /// quoting enclave, which has no other key ID.
+    fn get_attestation_key_id(&self) -> Result<Vec<u8>, Error> {
+        self.check()?;
+        Ok(key_id(SGX_QL_ALG_ECDSA_P256))
+    }
+
+    fn get_target_info(
+        &self,
+        _akid: Vec<u8>,
+        _size: usize,
+        out_buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        if out_buf.len() != SGX_TI_SIZE {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "Invalid output buffer size: {} != {}",
+                    out_buf.len(),
+                    SGX_TI_SIZE
+                ),
+            ));
+        }
+
+        // `sgx_target_info_t` has 64-bit fields, which `out_buf` might not align.
+        let mut ti = [0u64; SGX_TI_SIZE / 8];
+
+        // Safety: `ti` has the size and alignment of `sgx_target_info_t`.
+        let ret = unsafe { (self.get_target_info)(ti.as_mut_ptr().cast()) };
+        if ret != SGX_QL_SUCCESS {
+            return Err(quote3_error("sgx_qe_get_target_info", ret));
+        }
+
+        for (out, ti) in out_buf.chunks_exact_mut(8).zip(ti) {
+            out.copy_from_slice(&ti.to_ne_bytes());
+        }
+
+        Ok(SGX_TI_SIZE)
+    }
+
+    fn get_key_size(&self, _akid: Vec<u8>) -> Result<usize, Error> {
+        Ok(0)
+    }
+
+    fn get_quote_size(&self, _akid: Vec<u8>) -> Result<usize, Error> {
+        let mut size = 0;
+
+        // Safety: `size` is a valid `uint32_t`.
+        let ret = unsafe { (self.get_quote_size)(&mut size) };
+        if ret != SGX_QL_SUCCESS {
+            return Err(quote3_error("sgx_qe_get_quote_size", ret));
+        }
+
+        Ok(size as usize)
+    }
+
+    fn get_quote(&self, report: &[u8], akid: Vec<u8>, out_buf: &mut [u8]) -> Result<usize, Error> {
+        if report.len() < SGX_REPORT_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid REPORT size: {} < {}",
+                    report.len(),
+                    SGX_REPORT_SIZE
+                ),
+            ));
+        }
+
+        let size = self.get_quote_size(akid)?;
+        if size != out_buf.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "sgx_qe_get_quote: Invalid QUOTE size: {} != {}",
+                    size,
+                    out_buf.len()
+                ),
+            ));
+        }
+
+        // `sgx_report_t` has 64-bit fields, which `report` might not align.
+        let mut aligned = [0u64; SGX_REPORT_SIZE / 8];
+        for (aligned, report) in aligned.iter_mut().zip(report.chunks_exact(8)) {
+            *aligned = u64::from_ne_bytes(report.try_into().unwrap());
+        }
+
+        // Safety: `aligned` holds a `sgx_report_t` and `out_buf` has the size of the quote.
+        let ret =
+            unsafe { (self.get_quote)(aligned.as_ptr().cast(), size as u32, out_buf.as_mut_ptr()) };
+        if ret != SGX_QL_SUCCESS {
+            return Err(quote3_error("sgx_qe_get_quote", ret));
+        }
+
+        Ok(size)
+    }
+}
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A mock of the AESM daemon, which speaks its protobuf protocol on a Unix socket
+
+use super::Aesm;
+use crate::protobuf::aesm_proto::{
+    Request, Response, Response_GetQuoteExResponse, Response_GetQuoteSizeExResponse,
+    Response_GetSupportedAttKeyIDNumResponse, Response_GetSupportedAttKeyIDsResponse,
+    Response_InitQuoteExResponse,
+};
+
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::thread;
+
+use protobuf::Message;
+use tempfile::TempDir;
+
+/// Size of the public key ID reported by [`daemon`]
+pub const PUB_KEY_ID_SIZE: usize = 384;
+
+/// Byte of the Target Info returned by [`daemon`]
+pub const TARGET_INFO: u8 = 0x5a;
+
+/// Returns an attestation key ID of the algorithm `algorithm_id`.
+pub fn key_id(algorithm_id: u32) -> Vec<u8> {
+    let mut key_id = vec![0; 256];
+    key_id[154..158].copy_from_slice(&algorithm_id.to_le_bytes());
+    key_id
+}
+
+/// An AESM daemon serving requests with a handler on a socket in a temporary directory
+pub struct MockAesm {
+    socket: PathBuf,
+    _dir: TempDir,
+}
+
+impl MockAesm {
+    /// Starts serving the requests on a new socket with `handler`.
+    pub fn start(handler: impl Fn(Request) -> Response + Send + 'static) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("aesm.socket");
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        // The client has gone away, if the exchange fails.
+                        let _ = serve(stream, &handler);
+                    }
+                    Err(_) => return,
+                }
+            }
+        });
+
+        Self { socket, _dir: dir }
+    }
+
+    /// Returns a client of the daemon.
+    pub fn client(&self) -> Aesm {
+        Aesm::new(&self.socket)
+    }
+}
+
+/// Serves a single length-prefixed request like the AESM daemon.
+fn serve(
+    mut stream: UnixStream,
+    handler: &impl Fn(Request) -> Response,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let mut request = vec![0; u32::from_le_bytes(len) as usize];
+    stream.read_exact(&mut request)?;
+
+    let response = handler(Message::parse_from_bytes(&request)?).write_to_bytes()?;
+    stream.write_all(&(response.len() as u32).to_le_bytes())?;
+    stream.write_all(&response)?;
+    Ok(())
+}
+
+/// Answers like an AESM daemon with an ECDSA attestation key, which returns `quote`.
+pub fn daemon(quote: Vec<u8>) -> impl Fn(Request) -> Response + Send + 'static {
+    // Like on real hardware, the EPID key ID comes first.
+    daemon_with_key_ids(vec![key_id(0), key_id(2)], quote)
+}
+
+/// Answers like an AESM daemon with the attestation keys `key_ids`, which return `quote`.
+pub fn daemon_with_key_ids(
+    key_ids: Vec<Vec<u8>>,
+    quote: Vec<u8>,
+) -> impl Fn(Request) -> Response + Send + 'static {
+    move |request| {
+        let mut response = Response::new();
+
+        if request.has_getSupportedAttKeyIDNumReq() {
+            let mut res = Response_GetSupportedAttKeyIDNumResponse::new();
+            res.set_errorCode(0);
+            res.set_att_key_id_num(key_ids.len() as u32);
+            response.set_getSupportedAttKeyIDNumRes(res);
+        } else if request.has_getSupportedAttKeyIDsReq() {
+            let mut res = Response_GetSupportedAttKeyIDsResponse::new();
+            res.set_errorCode(0);
+            res.set_att_key_ids(key_ids.concat());
+            response.set_getSupportedAttKeyIDsRes(res);
+        } else if request.has_initQuoteExReq() {
+            let mut res = Response_InitQuoteExResponse::new();
+            res.set_errorCode(0);
+            res.set_pub_key_id_size(PUB_KEY_ID_SIZE as u64);
+            if request.get_initQuoteExReq().get_b_pub_key_id() {
+                res.set_target_info(vec![TARGET_INFO; super::SGX_TI_SIZE]);
+            }
+            response.set_initQuoteExRes(res);
+        } else if request.has_getQuoteSizeExReq() {
+            let mut res = Response_GetQuoteSizeExResponse::new();
+            res.set_errorCode(0);
+            res.set_quote_size(quote.len() as u32);
+            response.set_getQuoteSizeExRes(res);
+        } else if request.has_getQuoteExReq() {
+            let mut res = Response_GetQuoteExResponse::new();
+            res.set_errorCode(0);
+            res.set_quote(quote.clone());
+            response.set_getQuoteExRes(res);
+        }
+
+        response
+    }
+}
+
+/// Answers every request with the AESM error `code`.
+pub fn failing(code: u32) -> impl Fn(Request) -> Response + Send + 'static {
+    move |_| {
+        let mut response = Response::new();
+
+        let mut res = Response_GetSupportedAttKeyIDNumResponse::new();
+        res.set_errorCode(code);
+        response.set_getSupportedAttKeyIDNumRes(res);
+
+        let mut res = Response_GetSupportedAttKeyIDsResponse::new();
+        res.set_errorCode(code);
+        response.set_getSupportedAttKeyIDsRes(res);
+
+        let mut res = Response_InitQuoteExResponse::new();
+        res.set_errorCode(code);
+        response.set_initQuoteExRes(res);
+
+        let mut res = Response_GetQuoteSizeExResponse::new();
+        res.set_errorCode(code);
+        response.set_getQuoteSizeExRes(res);
+
+        let mut res = Response_GetQuoteExResponse::new();
+        res.set_errorCode(code);
+        response.set_getQuoteExRes(res);
+
+        response
+    }
+}
@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod aesm;
+mod dcap;
+#[cfg(test)]
+mod mock;
+
+pub use aesm::{Aesm, AesmError};
+pub use dcap::{Dcap, DCAP_QL};
+
+use crate::backend::sgx::TCB_FILE;
+use crate::caching::{Cache, CRLS};
+
+use std::env;
+use std::io::{Error, ErrorKind};
+use std::mem::size_of;
+use std::str::FromStr;
+
+use anyhow::Context;
+use der::{Document, Encode, Sequence};
+use sallyport::item::enarxcall::sgx::TargetInfo;
+
+/// Environment variable selecting the quote provider
+const QUOTE_PROVIDER_VAR: &str = "ENARX_SGX_QUOTE_PROVIDER";
+
+const SGX_TI_SIZE: usize = size_of::<TargetInfo>();
+const SGX_REPORT_SIZE: usize = 432;
+const SGX_QL_ALG_ECDSA_P256: u32 = 2;
+
+/// Size of an attestation key ID, a `sgx_att_key_id_ext_t`
+const SGX_KEY_ID_SIZE: usize = 256;
+/// Offset of the `algorithm_id` in an attestation key ID
+const SGX_KEY_ID_ALGORITHM_OFFSET: usize = 154;
+
+#[derive(Sequence)]
+pub struct SgxEvidence<'a> {
+    #[asn1(type = "OCTET STRING")]
+    pub quote: &'a [u8],
+    // CRL & TCB data are already ASN.1 encoded on disk
+    pub crl: Document,
+    pub tcb: Document,
+}
+
+pub fn get_algorithm_id(key_id: &[u8]) -> u32 {
+    const ALGORITHM_OFFSET: usize = SGX_KEY_ID_ALGORITHM_OFFSET;
+
+    if key_id.len() < ALGORITHM_OFFSET + 4 {
+        return u32::MAX;
+    }
+
+    let mut bytes: [u8; 4] = Default::default();
+    bytes.copy_from_slice(&key_id[ALGORITHM_OFFSET..ALGORITHM_OFFSET + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+/// Returns an attestation key ID of the algorithm `algorithm_id`, with all other fields unset.
+fn key_id(algorithm_id: u32) -> Vec<u8> {
+    const ALGORITHM_OFFSET: usize = SGX_KEY_ID_ALGORITHM_OFFSET;
+
+    let mut key_id = vec![0; SGX_KEY_ID_SIZE];
+    key_id[ALGORITHM_OFFSET..ALGORITHM_OFFSET + 4].copy_from_slice(&algorithm_id.to_le_bytes());
+    key_id
+}
+
+/// A provider of SGX quotes for the reports of Keeps
+pub trait QuoteProvider: Send + Sync {
+    /// Describes the provider, e.g. the socket of the AESM daemon
+    fn description(&self) -> String;
+
+    /// The kind of the provider
+    fn kind(&self) -> Provider;
+
+    /// Checks that the provider can quote, e.g. the socket of the AESM daemon exists
+    fn check(&self) -> Result<(), Error>;
+
+    /// Gets Att Key ID
+    fn get_attestation_key_id(&self) -> Result<Vec<u8>, Error>;
+
+    /// Fills the Target Info of the QE into the output buffer specified and
+    /// returns the number of bytes written.
+    fn get_target_info(
+        &self,
+        akid: Vec<u8>,
+        size: usize,
+        out_buf: &mut [u8],
+    ) -> Result<usize, Error>;
+
+    /// Gets key size
+    fn get_key_size(&self, akid: Vec<u8>) -> Result<usize, Error>;
+
+    /// Gets quote size
+    fn get_quote_size(&self, akid: Vec<u8>) -> Result<usize, Error>;
+
+    /// Fills the Quote obtained for the Report specified into the output buffer
+    /// specified and returns the number of bytes written.
+    fn get_quote(&self, report: &[u8], akid: Vec<u8>, out_buf: &mut [u8]) -> Result<usize, Error>;
+}
+
+/// The kinds of quote providers, which `ENARX_SGX_QUOTE_PROVIDER` selects
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Provider {
+    /// The AESM daemon, if its socket exists, otherwise the DCAP quote library, if it is installed
+    Auto,
+    /// The AESM daemon
+    Aesm,
+    /// The DCAP quote library, which runs the quoting enclave in-process
+    Dcap,
+}
+
+impl FromStr for Provider {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "aesm" => Ok(Self::Aesm),
+            "dcap" => Ok(Self::Dcap),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unknown SGX quote provider {s:?}, expected `auto`, `aesm` or `dcap`"),
+            )),
+        }
+    }
+}
+
+impl Provider {
+    /// Returns the provider selected by `ENARX_SGX_QUOTE_PROVIDER`.
+    pub fn from_env() -> Result<Self, Error> {
+        match env::var(QUOTE_PROVIDER_VAR) {
+            Ok(provider) if !provider.is_empty() => provider.parse(),
+            _ => Ok(Self::Auto),
+        }
+    }
+
+    /// Opens the provider.
+    pub fn open(self) -> Result<Box<dyn QuoteProvider>, Error> {
+        match self {
+            Self::Aesm => Ok(Box::new(Aesm::default())),
+            Self::Dcap => Ok(Box::new(Dcap::open()?)),
+            Self::Auto => {
+                let aesm = Aesm::default();
+                if !aesm.socket().exists() {
+                    if let Ok(dcap) = Dcap::open() {
+                        return Ok(Box::new(dcap));
+                    }
+                }
+                Ok(Box::new(aesm))
+            }
+        }
+    }
+}
+
+/// Returns the quote provider of this host, which `ENARX_SGX_QUOTE_PROVIDER` selects.
+pub fn quote_provider() -> Result<Box<dyn QuoteProvider>, Error> {
+    Provider::from_env()?.open()
+}
+
+/// The Intel collateral, which is embedded in the evidence
+struct Collateral {
+    crl: Document,
+    tcb: Document,
+}
+
+impl Collateral {
+    /// Reads the cached Intel collateral.
+    fn cached() -> Result<Self, Error> {
+        Ok(Self {
+            crl: Self::read(CRLS)?,
+            tcb: Self::read(TCB_FILE)?,
+        })
+    }
+
+    /// Reads the cached Intel collateral `file`.
+    fn read(file: &str) -> Result<Document, Error> {
+        Cache::INTEL
+            .find(file)
+            .and_then(|path| {
+                Document::read_der_file(&path)
+                    .with_context(|| format!("error reading Intel collateral file `{path:?}`"))
+            })
+            .map_err(|e| Error::new(ErrorKind::Other, format!("{e:#}")))
+    }
+
+    /// Encodes the evidence of `quote` with the collateral.
+    fn evidence(&self, quote: &[u8]) -> Result<Vec<u8>, Error> {
+        let evidence = SgxEvidence {
+            quote,
+            crl: self.crl.clone(),
+            tcb: self.tcb.clone(),
+        };
+
+        evidence.to_der().map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("SGX evidence to DER error: {e}"),
+            )
+        })
+    }
+
+    fn quote_size(&self, provider: &dyn QuoteProvider, akid: Vec<u8>) -> Result<usize, Error> {
+        let q_size = provider.get_quote_size(akid)?;
+        Ok(self.evidence(&vec![0; q_size])?.len())
+    }
+
+    fn quote(
+        &self,
+        provider: &dyn QuoteProvider,
+        report: &[u8],
+        akid: Vec<u8>,
+        out_buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        // Get the report in a separate buffer
+        let q_size = provider.get_quote_size(akid.clone())?;
+        let mut quote = vec![0; q_size];
+        provider.get_quote(report, akid, &mut quote)?;
+
+        let evidence = self.evidence(&quote)?;
+
+        if evidence.len() > out_buf.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "SGX CRL & report error: buffer length is {}, but need {}",
+                    out_buf.len(),
+                    evidence.len()
+                ),
+            ));
+        }
+
+        out_buf[..evidence.len()].copy_from_slice(&evidence);
+
+        Ok(evidence.len())
+    }
+}
+
+/// Gets quote size with CRL added
+pub fn get_quote_size_with_collateral(
+    provider: &dyn QuoteProvider,
+    akid: Vec<u8>,
+) -> Result<usize, Error> {
+    Collateral::cached()?.quote_size(provider, akid)
+}
+
+pub fn get_quote_and_collateral(
+    provider: &dyn QuoteProvider,
+    report: &[u8],
+    akid: Vec<u8>,
+    out_buf: &mut [u8],
+) -> Result<usize, Error> {
+    Collateral::cached()?.quote(provider, report, akid, out_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{self, MockAesm};
+    use super::*;
+
+    use der::Decode;
+
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/attestation");
+
+    fn collateral() -> Collateral {
+        Collateral {
+            crl: Document::read_der_file(format!("{DATA}/sgx.crls")).unwrap(),
+            tcb: Document::read_der_file(format!("{DATA}/sgx-tcb.der")).unwrap(),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(
+        not(all(host_can_test_sgx, host_can_test_attestation)),
+        ignore = "CPU does not support SGX2 or attestation not possible"
+    )]
+    fn request_target_info() {
+        let aesm = Aesm::default();
+        assert!(aesm.socket().exists());
+
+        let mut output = [1u8; SGX_TI_SIZE];
+
+        let akid = aesm.get_attestation_key_id().expect(
+            "Error obtaining attestation key id. Check your aesmd / pccs service installation.",
+        );
+        let pkeysize = aesm
+            .get_key_size(akid.clone())
+            .expect("error obtaining key size");
+        assert_eq!(
+            aesm.get_target_info(akid, pkeysize, &mut output).unwrap(),
+            SGX_TI_SIZE
+        );
+    }
+
+    #[test]
+    fn provider() {
+        assert_eq!("auto".parse::<Provider>().unwrap(), Provider::Auto);
+        assert_eq!("aesm".parse::<Provider>().unwrap(), Provider::Aesm);
+        assert_eq!("dcap".parse::<Provider>().unwrap(), Provider::Dcap);
+        assert!("qgs".parse::<Provider>().is_err());
+
+        let aesm = Provider::Aesm.open().unwrap();
+        assert!(aesm.description().starts_with("AESM ("));
+        assert_eq!(aesm.kind(), Provider::Aesm);
+    }
+
+    #[test]
+    fn key_id_layout() {
+        let akid = key_id(SGX_QL_ALG_ECDSA_P256);
+        assert_eq!(akid.len(), SGX_KEY_ID_SIZE);
+        assert_eq!(get_algorithm_id(&akid), SGX_QL_ALG_ECDSA_P256);
+        assert_eq!(get_algorithm_id(&key_id(0)), 0);
+
+        // The daemon reports key IDs of this layout.
+        let akid = mock::key_id(SGX_QL_ALG_ECDSA_P256);
+        assert_eq!(akid, key_id(SGX_QL_ALG_ECDSA_P256));
+        assert_eq!(
+            get_algorithm_id(&akid[..SGX_KEY_ID_ALGORITHM_OFFSET + 3]),
+            u32::MAX
+        );
+    }
+
+    #[test]
+    fn mock_target_info() {
+        let server = MockAesm::start(mock::daemon(vec![7; 64]));
+        let aesm = server.client();
+
+        let akid = aesm.get_attestation_key_id().unwrap();
+        assert_eq!(get_algorithm_id(&akid), 2);
+        let pkeysize = aesm.get_key_size(akid.clone()).unwrap();
+        assert_eq!(pkeysize, mock::PUB_KEY_ID_SIZE);
+
+        let mut output = [0u8; SGX_TI_SIZE];
+        assert_eq!(
+            aesm.get_target_info(akid.clone(), pkeysize, &mut output)
+                .unwrap(),
+            SGX_TI_SIZE
+        );
+        assert_eq!(output, [mock::TARGET_INFO; SGX_TI_SIZE]);
+
+        let mut output = [0u8; SGX_TI_SIZE - 1];
+        assert!(aesm.get_target_info(akid, pkeysize, &mut output).is_err());
+    }
+
+    #[test]
+    fn mock_quote() {
+        let server = MockAesm::start(mock::daemon(vec![7; 64]));
+        let aesm = server.client();
+        let akid = aesm.get_attestation_key_id().unwrap();
+        let report = [1u8; 432];
+
+        assert_eq!(aesm.get_quote_size(akid.clone()).unwrap(), 64);
+        let mut quote = [0u8; 64];
+        assert_eq!(
+            aesm.get_quote(&report, akid.clone(), &mut quote).unwrap(),
+            64
+        );
+        assert_eq!(quote, [7; 64]);
+
+        // The daemon returns a quote, which does not fill the buffer.
+        let mut quote = [0u8; 128];
+        let err = aesm
+            .get_quote(&report, akid.clone(), &mut quote)
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid QUOTE size: 64 != 128"));
+
+        assert!(aesm.get_quote(&report[..431], akid, &mut quote).is_err());
+    }
+
+    #[test]
+    fn mock_quote_with_collateral() {
+        let server = MockAesm::start(mock::daemon(vec![7; 4578]));
+        let aesm = server.client();
+        let akid = aesm.get_attestation_key_id().unwrap();
+        let collateral = collateral();
+
+        // The size of the evidence matches the evidence written, even into larger buffers.
+        let size = collateral.quote_size(&aesm, akid.clone()).unwrap();
+        let mut evidence = vec![0xff; size + 16];
+        assert_eq!(
+            collateral
+                .quote(&aesm, &[1u8; 432], akid.clone(), &mut evidence)
+                .unwrap(),
+            size
+        );
+        let evidence = SgxEvidence::from_der(&evidence[..size]).unwrap();
+        assert_eq!(evidence.quote, [7; 4578]);
+
+        let mut evidence = vec![0; size - 1];
+        assert!(collateral
+            .quote(&aesm, &[1u8; 432], akid, &mut evidence)
+            .is_err());
+    }
+
+    #[test]
+    fn mock_errors() {
+        let server = MockAesm::start(mock::failing(42));
+        let aesm = server.client();
+
+        let err = aesm.get_attestation_key_id().unwrap_err();
+        assert_eq!(AesmError::from(42), AesmError::AttKeyNotInitialized);
+        assert!(err.to_string().contains("AttKeyNotInitialized"));
+        assert!(aesm.get_key_size(vec![0; 256]).is_err());
+        assert!(aesm.get_quote_size(vec![0; 256]).is_err());
+        let err = aesm
+            .get_quote(&[0; 432], vec![0; 256], &mut [0; 64])
+            .unwrap_err();
+        assert!(err.to_string().contains("GetQuoteEx"));
+
+        let server = MockAesm::start(mock::failing(1234));
+        let err = server.client().get_quote_size(vec![0; 256]).unwrap_err();
+        assert!(err.to_string().contains("Unknown(1234)"));
+
+        // The daemon has no ECDSA attestation key.
+        let server = MockAesm::start(mock::daemon_with_key_ids(vec![vec![0; 256]], vec![]));
+        let err = server.client().get_attestation_key_id().unwrap_err();
+        assert_eq!(err.to_string(), "ECDSA attestation key not available");
+
+        // No daemon is listening on the socket.
+        let aesm = Aesm::new(server.client().socket().with_file_name("missing.socket"));
+        assert_eq!(
+            aesm.get_attestation_key_id().unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+}
@@ -211,6 +211,8 @@ impl TryFrom<Builder> for Arc<dyn super::super::Keep> {
             mem: builder.mmap,
             tcs: RwLock::new(builder.tcsp),
             enclave: Mutex::new(builder.file.try_clone().unwrap()),
+            quote_provider: super::attestation::quote_provider()
+                .context("Failed to open the SGX quote provider")?,
         }))
     }
 }
@@ -3,7 +3,7 @@
 use std::num::NonZeroU32;
 
 use crate::backend::Signatures;
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use goblin::elf::program_header::{PF_R, PF_W, PF_X};
 use sallyport::elf;
 use sgx::page::{Class, Flags, SecInfo};
@@ -103,9 +103,17 @@ impl super::super::Config for Config {
                 .note(elf::note::NAME, elf::note::BLOCK_SIZE)
                 .ok_or_else(|| anyhow!("SGX shim is missing BLOCK_SIZE"))?;
 
+            // The shim limits its heap to the maximum memory, which must fit in the enclave.
+            let size = 1 << bits;
+            if let Some(max_memory) = shim.resource(elf::note::MAX_MEMORY) {
+                if max_memory > size as u64 {
+                    bail!("SGX Keeps support at most {size} bytes of memory");
+                }
+            }
+
             Ok(Self {
                 parameters: params,
-                size: 1 << bits,
+                size,
                 ssap,
                 sallyport_block_size,
                 signatures,
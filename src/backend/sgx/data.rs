@@ -1,15 +1,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use super::attestation::Provider;
 use crate::backend::probe::x86_64::{CpuId, Vendor};
-use crate::backend::sgx::{sgx_cache_dir, TcbPackage, AESM_SOCKET, FMSPC_PATH, TCB_PATH};
+use crate::backend::sgx::{TcbPackage, FMSPC_FILE, TCB_FILE};
 use crate::backend::Datum;
-use crate::caching::CrlList;
+use crate::caching::{Cache, CrlList, CRLS};
 
 use sgx::parameters::{Features, MiscSelect, Xfrm};
 
 use std::arch::x86_64::__cpuid_count;
 use std::fs::File;
-use std::path::Path;
 use std::time::SystemTime;
 
 use chrono::{DateTime, Local};
@@ -170,12 +170,91 @@ pub fn dev_sgx_enclave() -> Datum {
     }
 }
 
-pub fn aesm_socket() -> Datum {
-    Datum {
-        name: "AESM Daemon Socket".into(),
-        pass: cfg!(feature = "disable-sgx-attestation") || Path::new(AESM_SOCKET).exists(),
-        info: Some(AESM_SOCKET.into()),
-        mesg: None,
+pub fn quote_provider() -> Datum {
+    const NAME: &str = "Quote Provider";
+
+    if cfg!(feature = "disable-sgx-attestation") {
+        return Datum {
+            name: NAME.into(),
+            pass: true,
+            info: Some("disabled".into()),
+            mesg: None,
+        };
+    }
+
+    match Provider::from_env().and_then(Provider::open) {
+        Ok(provider) => match provider.check() {
+            Ok(()) => Datum {
+                name: NAME.into(),
+                pass: true,
+                info: Some(provider.description()),
+                mesg: None,
+            },
+            Err(e) => Datum {
+                name: NAME.into(),
+                pass: false,
+                info: Some(format!("{}: {e}", provider.description())),
+                mesg: Some(provider_help(provider.kind()).into()),
+            },
+        },
+        Err(e) => Datum {
+            name: NAME.into(),
+            pass: false,
+            info: Some(e.to_string()),
+            mesg: Some(provider_help(Provider::Auto).into()),
+        },
+    }
+}
+
+/// Returns how to set up the quote provider of kind `provider`.
+fn provider_help(provider: Provider) -> &'static str {
+    match provider {
+        Provider::Aesm => {
+            "Check that the AESM daemon `aesmd` is running and the Intel DCAP quote provider \
+            library is installed."
+        }
+        Provider::Dcap => {
+            "Check that the Intel DCAP quote library `libsgx_dcap_ql.so.1` with its quoting \
+            enclaves and the Intel DCAP quote provider library are installed."
+        }
+        Provider::Auto => {
+            "Start the AESM daemon `aesmd` or install the Intel DCAP quote library \
+            `libsgx_dcap_ql.so.1`, and select it with `ENARX_SGX_QUOTE_PROVIDER=aesm` or `=dcap`."
+        }
+    }
+}
+
+pub fn attestation_key() -> Datum {
+    const NAME: &str = "Attestation Key";
+
+    let provider = match Provider::from_env().and_then(Provider::open) {
+        Ok(provider) => provider,
+        Err(e) => {
+            return Datum {
+                name: NAME.into(),
+                pass: false,
+                info: Some(e.to_string()),
+                mesg: Some(provider_help(Provider::Auto).into()),
+            }
+        }
+    };
+
+    match provider.get_attestation_key_id() {
+        Ok(_) => Datum {
+            name: NAME.into(),
+            pass: true,
+            info: Some("ECDSA P-256".into()),
+            mesg: None,
+        },
+        Err(e) => Datum {
+            name: NAME.into(),
+            pass: false,
+            info: Some(e.to_string()),
+            mesg: Some(format!(
+                "The quote provider did not provide an ECDSA attestation key. {}",
+                provider_help(provider.kind())
+            )),
+        },
     }
 }
 
@@ -184,28 +263,17 @@ pub fn intel_crl() -> Datum {
     const UPDATE_MSG: &str =
         "Run `enarx platform sgx cache-crl` to generate the Intel CRL cache file";
 
-    let crl_file =
-        match sgx_cache_dir() {
-            Ok(p) => p.join("crls.der"),
-            Err(e) => return Datum {
+    let crl_file = match Cache::INTEL.find(CRLS) {
+        Ok(path) => path,
+        Err(e) => {
+            return Datum {
                 name: NAME.to_string(),
                 pass: false,
-                info: Some(e.to_string()),
-                mesg: Some(
-                    "enarx expects the directory `/var/cache/intel-sgx` to exist and be readable"
-                        .into(),
-                ),
-            },
-        };
-
-    if !crl_file.exists() {
-        return Datum {
-            name: NAME.to_string(),
-            pass: false,
-            info: None,
-            mesg: Some(UPDATE_MSG.to_string()),
-        };
-    }
+                info: Some(format!("{e:#}")),
+                mesg: Some(UPDATE_MSG.to_string()),
+            }
+        }
+    };
 
     let crls = match std::fs::read(crl_file.clone()) {
         Ok(c) => c,
@@ -269,7 +337,7 @@ pub fn tcb_fmspc_cached() -> Datum {
     const NAME: &str = "TCB & FMSPC cache";
     const TCB_INSTRUCTION: &str = "Run `enarx platform sgx cache-tcb`";
 
-    if !Path::new(FMSPC_PATH).exists() {
+    if Cache::INTEL.find(FMSPC_FILE).is_err() {
         return Datum {
             name: NAME.to_string(),
             pass: false,
@@ -278,16 +346,19 @@ pub fn tcb_fmspc_cached() -> Datum {
         };
     }
 
-    if !Path::new(TCB_PATH).exists() {
-        return Datum {
-            name: NAME.to_string(),
-            pass: false,
-            info: Some("Missing TCB report".into()),
-            mesg: Some(TCB_INSTRUCTION.into()),
-        };
-    }
+    let tcb_file = match Cache::INTEL.find(TCB_FILE) {
+        Ok(path) => path,
+        Err(_) => {
+            return Datum {
+                name: NAME.to_string(),
+                pass: false,
+                info: Some("Missing TCB report".into()),
+                mesg: Some(TCB_INSTRUCTION.into()),
+            }
+        }
+    };
 
-    let tcb = match std::fs::read(TCB_PATH) {
+    let tcb = match std::fs::read(tcb_file) {
         Ok(bytes) => bytes,
         Err(e) => {
             return Datum {
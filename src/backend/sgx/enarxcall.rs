@@ -1,10 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::backend::parking::THREAD_PARK;
-use crate::backend::sgx::attestation::{
-    get_attestation_key_id, get_key_size, get_quote_and_collateral, get_quote_size_with_collateral,
-    get_target_info,
-};
+use crate::backend::sgx::attestation::{get_quote_and_collateral, get_quote_size_with_collateral};
 use crate::backend::sgx::ioctls::{ModifyTypes, RemovePages, RestrictPermissions};
 use crate::backend::{Command, Keep};
 
@@ -163,15 +160,18 @@ pub(crate) fn sgx_enarxcall<'a>(
                     .map_err(io::Error::from_raw_os_error)
                     .context("sgx_enarxcall deref")?
             };
-            let akid = get_attestation_key_id().context(
+            let akid = keep.quote_provider.get_attestation_key_id().context(
                 "Error obtaining attestation key id. Check your aesmd / pccs service installation.",
             )?;
-            let pkeysize = get_key_size(akid.clone()).context(
+            let pkeysize = keep.quote_provider.get_key_size(akid.clone()).context(
                 "Error obtaining key size. Check your aesmd / pccs service installation.",
             )?;
-            *ret = get_target_info(akid, pkeysize, out_buf).context(
-                "Error getting target info. Check your aesmd / pccs service installation.",
-            )?;
+            *ret = keep
+                .quote_provider
+                .get_target_info(akid, pkeysize, out_buf)
+                .context(
+                    "Error getting target info. Check your aesmd / pccs service installation.",
+                )?;
 
             Ok(None)
         }
@@ -199,11 +199,14 @@ pub(crate) fn sgx_enarxcall<'a>(
                     .context("sgx_enarxcall deref")?
             };
 
-            let akid = get_attestation_key_id().context(
+            let akid = keep.quote_provider.get_attestation_key_id().context(
                 "Error obtaining attestation key id. Check your aesmd / pccs service installation.",
             )?;
-            *ret = get_quote_and_collateral(report_buf, akid, quote_buf)
-                .context("Error getting quote. Check your aesmd / pccs service installation.")?;
+            *ret =
+                get_quote_and_collateral(keep.quote_provider.as_ref(), report_buf, akid, quote_buf)
+                    .context(
+                        "Error getting quote. Check your aesmd / pccs service installation.",
+                    )?;
 
             Ok(None)
         }
@@ -213,10 +216,10 @@ pub(crate) fn sgx_enarxcall<'a>(
             ret,
             ..
         } => {
-            let akid = get_attestation_key_id().context(
+            let akid = keep.quote_provider.get_attestation_key_id().context(
                 "Error obtaining attestation key id. Check your aesmd / pccs service installation.",
             )?;
-            *ret = get_quote_size_with_collateral(akid).context(
+            *ret = get_quote_size_with_collateral(keep.quote_provider.as_ref(), akid).context(
                 "Error getting quote size. Check your aesmd / pccs service installation.",
             )?;
 
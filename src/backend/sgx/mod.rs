@@ -11,22 +11,23 @@ mod thread;
 
 use super::Loader;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use mmarinus::{perms, Map};
 
 use crate::backend::Signatures;
 use std::arch::x86_64::__cpuid_count;
 use std::fs::File;
-use std::io::{self, ErrorKind};
-use std::path::PathBuf;
 use std::sync::{Arc, Mutex, RwLock};
 
 use der::Sequence;
 use x509_cert::Certificate;
 
+/// Default path of the AESM daemon socket, which `ENARX_AESM_SOCKET` overrides
 pub const AESM_SOCKET: &str = "/var/run/aesmd/aesm.socket";
-pub const FMSPC_PATH: &str = "/var/cache/intel-sgx/fmspc.txt";
-pub const TCB_PATH: &str = "/var/cache/intel-sgx/tcb.der";
+/// Name of the cached FMSPC file of the platform in [`crate::caching::Cache::INTEL`]
+pub const FMSPC_FILE: &str = "fmspc.txt";
+/// Name of the cached TCB report file of the platform in [`crate::caching::Cache::INTEL`]
+pub const TCB_FILE: &str = "tcb.der";
 
 pub type Tcs = usize;
 
@@ -42,6 +43,7 @@ pub(crate) struct Keep {
     mem: Map<perms::Unknown>,
     tcs: RwLock<Vec<Tcs>>,
     enclave: Mutex<File>,
+    quote_provider: Box<dyn attestation::QuoteProvider>,
 }
 
 impl Keep {
@@ -82,7 +84,15 @@ impl crate::backend::Backend for Backend {
     }
 
     fn config(&self) -> Vec<super::Datum> {
-        vec![data::aesm_socket()]
+        vec![data::quote_provider()]
+    }
+
+    fn checks(&self) -> Vec<super::Datum> {
+        if cfg!(feature = "disable-sgx-attestation") {
+            vec![]
+        } else {
+            vec![data::attestation_key()]
+        }
     }
 
     #[inline]
@@ -101,21 +111,6 @@ impl crate::backend::Backend for Backend {
     }
 }
 
-/// Returns the "system-level" search path for the SGX
-/// CRLs (`/var/cache/intel-sgx`).
-pub fn sgx_cache_dir() -> anyhow::Result<PathBuf> {
-    const CACHE_DIR: &str = "/var/cache";
-
-    let mut sys = PathBuf::from(CACHE_DIR);
-    if sys.exists() && sys.is_dir() {
-        sys.push("intel-sgx");
-        Ok(sys)
-    } else {
-        Err(io::Error::from(ErrorKind::NotFound))
-            .with_context(|| format!("Directory `{CACHE_DIR}` does not exist!"))
-    }
-}
-
 #[cfg(test)]
 mod tests {
     #[test]
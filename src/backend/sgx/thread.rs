@@ -3,10 +3,11 @@
 use super::enarxcall::sgx_enarxcall;
 #[cfg(feature = "gdb")]
 use crate::backend::execute_gdb;
+use crate::backend::policy::SYSCALL_POLICY;
+use crate::backend::trace;
 use crate::backend::Command;
 
 use std::arch::asm;
-use std::iter;
 use std::mem::{size_of, MaybeUninit};
 #[cfg(feature = "gdb")]
 use std::net::TcpStream;
@@ -185,80 +186,84 @@ impl super::super::Thread for Thread {
             if let (EENTER, ERESUME) = (how, self.how) {
                 let block: Block = self.block[self.cssa - 1].as_mut_slice().into();
                 for item in block {
-                    match item {
-                        Item::Gdbcall(_gdbcall, _data) => {
-                            #[cfg(feature = "gdb")]
-                            unsafe {
-                                execute_gdb(
-                                    _gdbcall,
-                                    _data,
-                                    &mut self.gdb_fd,
-                                    _gdblisten.as_ref().unwrap(),
+                    trace::traced(item, |item| {
+                        match item {
+                            Item::Gdbcall(_gdbcall, _data) => {
+                                #[cfg(feature = "gdb")]
+                                unsafe {
+                                    execute_gdb(
+                                        _gdbcall,
+                                        _data,
+                                        &mut self.gdb_fd,
+                                        _gdblisten.as_ref().unwrap(),
+                                    )
+                                    .map_err(io::Error::from_raw_os_error)
+                                    .context("execute_gdb")?;
+                                }
+                            }
+
+                            Item::Enarxcall(enarxcall, data) => {
+                                sallyport::host::execute(
+                                    sgx_enarxcall(enarxcall, data, self.keep.clone())?.into_iter(),
                                 )
                                 .map_err(io::Error::from_raw_os_error)
-                                .context("execute_gdb")?;
+                                .context("sallyport::host::execute")?;
                             }
-                        }
-
-                        Item::Enarxcall(enarxcall, data) => {
-                            sallyport::host::execute(
-                                sgx_enarxcall(enarxcall, data, self.keep.clone())?.into_iter(),
-                            )
-                            .map_err(io::Error::from_raw_os_error)
-                            .context("sallyport::host::execute")?;
-                        }
 
-                        // Catch exit for a clean shutdown
-                        Item::Syscall(
-                            item::Syscall {
-                                num,
-                                argv: [code, ..],
-                                ..
-                            },
-                            ..,
-                        ) if (*num == libc::SYS_exit as usize) => {
-                            error!(
+                            // Catch exit for a clean shutdown
+                            Item::Syscall(
+                                item::Syscall {
+                                    num,
+                                    argv: [code, ..],
+                                    ..
+                                },
+                                ..,
+                            ) if (*num == libc::SYS_exit as usize) => {
+                                error!(
                                 "exit({code}) syscall used over sallyport, when it should not be"
                             );
-                            bail!(
+                                bail!(
                                 "exit({code}) syscall used over sallyport, when it should not be"
                             );
-                        }
+                            }
 
-                        // Catch exit_group for a clean shutdown
-                        Item::Syscall(
-                            item::Syscall {
-                                num,
-                                argv: [code, ..],
-                                ..
-                            },
-                            ..,
-                        ) if (*num == libc::SYS_exit_group as usize) => {
-                            trace!("exit_group({code})");
-                            std::process::exit(*code as _);
-                        }
+                            // Catch exit_group for a clean shutdown
+                            Item::Syscall(
+                                item::Syscall {
+                                    num,
+                                    argv: [code, ..],
+                                    ..
+                                },
+                                ..,
+                            ) if (*num == libc::SYS_exit_group as usize) => {
+                                trace!("exit_group({code})");
+                                std::process::exit(*code as _);
+                            }
 
-                        Item::Syscall(ref _syscall, ..) => {
-                            #[cfg(feature = "dbg")]
-                            match (
-                                _syscall.num as libc::c_long,
-                                _syscall.argv[1] as libc::c_int,
-                            ) {
-                                (
-                                    libc::SYS_write | libc::SYS_read,
-                                    libc::STDIN_FILENO | libc::STDOUT_FILENO | libc::STDERR_FILENO,
-                                ) => {}
-                                (libc::SYS_clock_gettime, _) => {}
-                                _ => {
-                                    dbg!(&_syscall);
+                            Item::Syscall(syscall, data) => {
+                                #[cfg(feature = "dbg")]
+                                match (syscall.num as libc::c_long, syscall.argv[1] as libc::c_int)
+                                {
+                                    (
+                                        libc::SYS_write | libc::SYS_read,
+                                        libc::STDIN_FILENO
+                                        | libc::STDOUT_FILENO
+                                        | libc::STDERR_FILENO,
+                                    ) => {}
+                                    (libc::SYS_clock_gettime, _) => {}
+                                    _ => {
+                                        dbg!(&syscall);
+                                    }
                                 }
-                            }
 
-                            sallyport::host::execute(iter::once(item))
-                                .map_err(io::Error::from_raw_os_error)
-                                .context("sallyport::host::execute")?;
+                                SYSCALL_POLICY
+                                    .execute(syscall, data)
+                                    .map_err(io::Error::from_raw_os_error)
+                                    .context("sallyport::host::execute")?;
+                            }
                         }
-                    }
+                        Ok::<_, anyhow::Error>(())
+                    })?;
                 }
             }
         }
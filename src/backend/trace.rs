@@ -0,0 +1,396 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured trace of the sallyport items executed on behalf of a Keep
+//!
+//! If enabled with `--trace-syscalls`, every item is written as one JSON object
+//! per line, see `docs/Output.md` for the format.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use libc::c_long;
+use once_cell::sync::OnceCell;
+use sallyport::item::{self, Item};
+use serde::Serialize;
+use tracing::error;
+
+static TRACER: OnceCell<Tracer> = OnceCell::new();
+
+struct Tracer {
+    path: Utf8PathBuf,
+    file: Mutex<Option<LineWriter<File>>>,
+}
+
+/// Maximum number of bytes of a buffer argument included in a record
+const MAX_CONTENT: usize = 64;
+
+/// Generic names of the arguments of unknown syscalls
+const ARGS: &[&str] = &["arg0", "arg1", "arg2", "arg3", "arg4", "arg5"];
+
+/// Returns the name of the syscall `num` and the names of its sallyport arguments.
+///
+/// Arguments ending in `_offset` are offsets into the sallyport block.
+fn syscall(num: c_long) -> Option<(&'static str, &'static [&'static str])> {
+    Some(match num {
+        libc::SYS_accept => ("accept", &["sockfd", "addr_offset", "addrlen_offset"]),
+        libc::SYS_accept4 => (
+            "accept4",
+            &["sockfd", "addr_offset", "addrlen_offset", "flags"],
+        ),
+        libc::SYS_bind => ("bind", &["sockfd", "addr_offset", "addrlen"]),
+        libc::SYS_clock_getres => ("clock_getres", &["clockid", "res_offset"]),
+        libc::SYS_clock_gettime => ("clock_gettime", &["clockid", "tp_offset"]),
+        libc::SYS_close => ("close", &["fd"]),
+        libc::SYS_connect => ("connect", &["sockfd", "addr_offset", "addrlen"]),
+        libc::SYS_dup => ("dup", &["oldfd"]),
+        libc::SYS_dup2 => ("dup2", &["oldfd", "newfd"]),
+        libc::SYS_dup3 => ("dup3", &["oldfd", "newfd", "flags"]),
+        libc::SYS_epoll_create1 => ("epoll_create1", &["flags"]),
+        libc::SYS_epoll_ctl => ("epoll_ctl", &["epfd", "op", "fd", "event_offset"]),
+        libc::SYS_epoll_pwait => (
+            "epoll_pwait",
+            &[
+                "epfd",
+                "events_offset",
+                "maxevents",
+                "timeout",
+                "sigmask_offset",
+            ],
+        ),
+        libc::SYS_epoll_wait => (
+            "epoll_wait",
+            &["epfd", "events_offset", "maxevents", "timeout"],
+        ),
+        libc::SYS_eventfd2 => ("eventfd2", &["initval", "flags"]),
+        libc::SYS_exit => ("exit", &["status"]),
+        libc::SYS_exit_group => ("exit_group", &["status"]),
+        libc::SYS_fcntl => ("fcntl", &["fd", "cmd", "arg"]),
+        libc::SYS_getsockname => ("getsockname", &["sockfd", "addr_offset", "addrlen_offset"]),
+        libc::SYS_ioctl => ("ioctl", &["fd", "request", "argp_offset", "argp_len"]),
+        libc::SYS_listen => ("listen", &["sockfd", "backlog"]),
+        libc::SYS_nanosleep => ("nanosleep", &["req_offset", "rem_offset"]),
+        libc::SYS_open => (
+            "open",
+            &["pathname_offset", "pathname_len", "flags", "mode"],
+        ),
+        libc::SYS_poll => ("poll", &["fds_offset", "nfds", "timeout"]),
+        libc::SYS_read => ("read", &["fd", "buf_offset", "count"]),
+        libc::SYS_recvfrom => (
+            "recvfrom",
+            &[
+                "sockfd",
+                "buf_offset",
+                "len",
+                "flags",
+                "src_addr_offset",
+                "addrlen_offset",
+            ],
+        ),
+        libc::SYS_sendto => (
+            "sendto",
+            &[
+                "sockfd",
+                "buf_offset",
+                "len",
+                "flags",
+                "dest_addr_offset",
+                "addrlen",
+            ],
+        ),
+        libc::SYS_setsockopt => (
+            "setsockopt",
+            &["sockfd", "level", "optname", "optval_offset", "optlen"],
+        ),
+        libc::SYS_socket => ("socket", &["domain", "type", "protocol"]),
+        libc::SYS_sync => ("sync", &[]),
+        libc::SYS_write => ("write", &["fd", "buf_offset", "count"]),
+        _ => return None,
+    })
+}
+
+/// A traced sallyport item
+#[derive(Debug, Serialize)]
+struct Record {
+    /// Microseconds since the Unix epoch at which the item was recorded
+    timestamp_us: u128,
+    /// Host thread, which executed the item
+    tid: c_long,
+    /// `syscall`, `enarxcall` or `gdbcall`
+    kind: &'static str,
+    name: String,
+    num: usize,
+    args: BTreeMap<&'static str, usize>,
+    /// The path argument of `open`, decoded from the sallyport block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// The data read or written, decoded from the sallyport block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    buf: Option<Buf>,
+    ret: Vec<usize>,
+    /// The first return value as a signed integer
+    result: isize,
+    /// The error number, if `result` is a negated error number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errno: Option<isize>,
+    /// `None` if the item was recorded before its execution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ns: Option<u128>,
+}
+
+impl Record {
+    fn new(
+        kind: &'static str,
+        name: String,
+        num: usize,
+        args: BTreeMap<&'static str, usize>,
+        ret: Vec<usize>,
+    ) -> Self {
+        let result = ret[0] as isize;
+        Self {
+            timestamp_us: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_micros(),
+            tid: unsafe { libc::syscall(libc::SYS_gettid) },
+            kind,
+            name,
+            num,
+            args,
+            path: None,
+            buf: None,
+            ret,
+            result,
+            // Linux reserves the last 4095 values for negated error numbers.
+            errno: (-4095..0).contains(&result).then_some(-result),
+            duration_ns: None,
+        }
+    }
+
+    fn syscall(call: &item::Syscall, data: &[u8]) -> Self {
+        let (name, names) = match syscall(call.num as _) {
+            Some((name, names)) => (name.to_string(), names),
+            None => (format!("syscall_{}", call.num), ARGS),
+        };
+        let args = names.iter().copied().zip(call.argv).collect();
+        let mut record = Self::new("syscall", name, call.num, args, call.ret.to_vec());
+        match call.num as c_long {
+            libc::SYS_open => {
+                let [offset, len, ..] = call.argv;
+                record.path = block(data, offset, len).map(|path| {
+                    let path = path.strip_suffix(b"\0").unwrap_or(path);
+                    String::from_utf8_lossy(path).into_owned()
+                });
+            }
+            // Only the bytes actually transferred are recorded.
+            libc::SYS_read | libc::SYS_recvfrom | libc::SYS_sendto | libc::SYS_write
+                if record.result >= 0 =>
+            {
+                record.buf = Buf::new(data, call.argv[1], record.result as _);
+            }
+            _ => {}
+        }
+        record
+    }
+
+    fn enarxcall(call: &item::Enarxcall) -> Self {
+        let args = ARGS.iter().copied().zip(call.argv).collect();
+        let name = format!("{:?}", call.num);
+        Self::new("enarxcall", name, call.num as _, args, vec![call.ret])
+    }
+
+    fn gdbcall(call: &item::Gdbcall) -> Self {
+        let args = ARGS.iter().copied().zip(call.argv).collect();
+        let name = format!("{:?}", call.num);
+        Self::new("gdbcall", name, call.num as _, args, vec![call.ret])
+    }
+
+    fn finish(mut self, duration: Duration) -> Self {
+        self.duration_ns = Some(duration.as_nanos());
+        self
+    }
+}
+
+/// Returns the `len` bytes at `offset` in the sallyport block `data`, if in bounds.
+fn block(data: &[u8], offset: usize, len: usize) -> Option<&[u8]> {
+    data.get(offset..offset.checked_add(len)?)
+}
+
+/// A buffer argument of a syscall
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Buf {
+    /// Number of bytes read or written
+    len: usize,
+    /// The first `MAX_CONTENT` bytes, with non-printable bytes escaped
+    content: String,
+}
+
+impl Buf {
+    fn new(data: &[u8], offset: usize, len: usize) -> Option<Self> {
+        let bytes = block(data, offset, len)?;
+        let content = bytes[..len.min(MAX_CONTENT)].escape_ascii().to_string();
+        Some(Self { len, content })
+    }
+}
+
+/// Starts tracing all sallyport items to the file at `path`.
+pub fn init(path: &Utf8Path) -> Result<()> {
+    // The file is only opened once the Keep runs, as `run_package` expects the
+    // next free file descriptor to be 3.
+    File::create(path).with_context(|| format!("Failed to create {path}"))?;
+    TRACER
+        .set(Tracer {
+            path: path.into(),
+            file: Mutex::new(None),
+        })
+        .map_err(|_| anyhow!("sallyport tracing was already started"))
+}
+
+fn write(record: &Record) {
+    let Some(tracer) = TRACER.get() else { return };
+    let mut file = tracer.file.lock().unwrap();
+    let res = match *file {
+        Some(ref mut file) => Ok(file),
+        None => OpenOptions::new()
+            .append(true)
+            .open(&tracer.path)
+            .map(|f| file.insert(LineWriter::new(f))),
+    }
+    .and_then(|file| {
+        serde_json::to_writer(&mut *file, record)?;
+        file.write_all(b"\n")
+    });
+    if let Err(e) = res {
+        error!("failed to write sallyport trace to {}: {e}", tracer.path);
+    }
+}
+
+/// Executes `f` on `item`, tracing the item if enabled.
+pub fn traced<T>(item: Item<'_>, f: impl FnOnce(Item<'_>) -> T) -> T {
+    if TRACER.get().is_none() {
+        return f(item);
+    }
+
+    match item {
+        Item::Syscall(call, data) => {
+            // These do not return to be traced afterwards.
+            if matches!(call.num as c_long, libc::SYS_exit | libc::SYS_exit_group) {
+                write(&Record::syscall(call, data));
+                return f(Item::Syscall(call, data));
+            }
+            let start = Instant::now();
+            let ret = f(Item::Syscall(&mut *call, &mut *data));
+            write(&Record::syscall(call, data).finish(start.elapsed()));
+            ret
+        }
+        Item::Enarxcall(call, data) => {
+            let start = Instant::now();
+            let ret = f(Item::Enarxcall(&mut *call, data));
+            write(&Record::enarxcall(call).finish(start.elapsed()));
+            ret
+        }
+        Item::Gdbcall(call, data) => {
+            let start = Instant::now();
+            let ret = f(Item::Gdbcall(&mut *call, data));
+            write(&Record::gdbcall(call).finish(start.elapsed()));
+            ret
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let call = item::Syscall {
+            num: libc::SYS_write as _,
+            argv: [1, 64, 12, 0, 0, 0],
+            ret: [-libc::EBADF as usize, 0],
+        };
+        let record = Record::syscall(&call, &[]).finish(Duration::from_nanos(42));
+        assert_eq!(record.name, "write");
+        assert_eq!(
+            record.args,
+            BTreeMap::from([("buf_offset", 64), ("count", 12), ("fd", 1)])
+        );
+        assert_eq!(record.result, -libc::EBADF as isize);
+        assert_eq!(record.errno, Some(libc::EBADF as isize));
+        assert_eq!(record.buf, None);
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["kind"], "syscall");
+        assert_eq!(json["duration_ns"], 42);
+
+        let call = item::Syscall {
+            num: 0xEA01,
+            argv: [1, 2, 3, 4, 5, 6],
+            ret: [3, 0],
+        };
+        let record = Record::syscall(&call, &[]);
+        assert_eq!(record.name, "syscall_59905");
+        assert_eq!(record.args.len(), 6);
+        assert_eq!(record.errno, None);
+        let json = serde_json::to_value(&record).unwrap();
+        assert!(json.get("duration_ns").is_none());
+        assert!(json.get("errno").is_none());
+
+        let call = item::Enarxcall {
+            num: item::enarxcall::Number::MemInfo,
+            argv: [0; 4],
+            ret: 7,
+        };
+        let record = Record::enarxcall(&call);
+        assert_eq!(
+            (record.kind, record.name.as_str()),
+            ("enarxcall", "MemInfo")
+        );
+        assert_eq!(record.result, 7);
+    }
+
+    #[test]
+    fn decode() {
+        let data = b"xx/etc/resolv.conf\0hello\n\x01";
+        let open = item::Syscall {
+            num: libc::SYS_open as _,
+            argv: [2, 17, 0, 0, 0, 0],
+            ret: [3, 0],
+        };
+        let record = Record::syscall(&open, data);
+        assert_eq!(record.path.as_deref(), Some("/etc/resolv.conf"));
+        assert_eq!(record.buf, None);
+
+        let write = item::Syscall {
+            num: libc::SYS_write as _,
+            argv: [1, 19, 8, 0, 0, 0],
+            ret: [7, 0],
+        };
+        let json = serde_json::to_value(Record::syscall(&write, data)).unwrap();
+        assert_eq!(
+            json["buf"],
+            serde_json::json!({ "len": 7, "content": "hello\\n\\x01" })
+        );
+        assert!(json.get("path").is_none());
+
+        // Out of bounds buffers are not decoded.
+        let read = item::Syscall {
+            num: libc::SYS_read as _,
+            argv: [0, 19, 64, 0, 0, 0],
+            ret: [64, 0],
+        };
+        assert_eq!(Record::syscall(&read, data).buf, None);
+
+        let read = item::Syscall {
+            num: libc::SYS_read as _,
+            argv: [0, 0, 512, 0, 0, 0],
+            ret: [512, 0],
+        };
+        let buf = Record::syscall(&read, &[b'a'; 512]).buf.unwrap();
+        assert_eq!((buf.len, buf.content.len()), (512, MAX_CONTENT));
+    }
+}
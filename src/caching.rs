@@ -1,13 +1,24 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::io::Read;
+use crate::attestation::pki;
+
+use std::ffi::{CString, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Write};
 use std::iter::zip;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use std::vec;
 
 use anyhow::Context;
-use der::{Decode, Encode, Sequence};
+use der::{Decode, Sequence};
+use percent_encoding::percent_decode;
 use x509_cert::crl::CertificateList;
 use x509_cert::time::Time;
+use x509_cert::Certificate;
 
 #[derive(Sequence)]
 pub struct CrlListEntry {
@@ -65,29 +76,301 @@ impl CrlList {
 /// Maximum length of the CRL in bytes
 const MAX_CRL_SIZE: u64 = 10000;
 
+/// Maximum length of fetched certificates in bytes
+const MAX_CERT_SIZE: u64 = 10000;
+
+/// Response header of Intel's PCS with the issuer chain of a PCK CRL
+const CRL_ISSUER_CHAIN: &str = "SGX-PCK-CRL-Issuer-Chain";
+
 /// Fetches CRLs from each url within `urls` and returns pairs of CRLs and URLs they originated
-/// from concatenated in a DER sequence.
-pub fn fetch_crl_list<const N: usize>(urls: [String; N]) -> anyhow::Result<Vec<u8>> {
-    let mut resps = Vec::with_capacity(N);
+/// from, along with the certificate chains of the CRL issuers sent in the response headers.
+///
+/// The CRLs are not verified, see [`crate::attestation::pki::verify_crls`].
+pub fn fetch_crl_list(
+    urls: impl IntoIterator<Item = String>,
+) -> anyhow::Result<(CrlList, Vec<Vec<Certificate>>)> {
+    let urls: Vec<_> = urls.into_iter().collect();
+    let mut resps = Vec::with_capacity(urls.len());
+    let mut chains = vec![];
     for url in &urls {
-        let mut crl = vec![];
-        ureq::get(url)
+        let resp = ureq::get(url)
             .call()
-            .with_context(|| format!("failed to connect to `{url}`"))?
-            .into_reader()
+            .with_context(|| format!("failed to connect to `{url}`"))?;
+        if let Some(chain) = resp.header(CRL_ISSUER_CHAIN) {
+            chains.push(
+                issuer_chain(chain)
+                    .with_context(|| format!("invalid CRL issuer chain from `{url}`"))?,
+            );
+        }
+        let mut crl = vec![];
+        resp.into_reader()
             .take(MAX_CRL_SIZE)
             .read_to_end(&mut crl)
             .with_context(|| format!("failed to read response from `{url}`"))?;
         resps.push(crl);
     }
-    let mut crls = Vec::with_capacity(N);
+    let mut crls = Vec::with_capacity(urls.len());
     for (i, resp) in resps.iter().enumerate() {
         let crl = CertificateList::from_der(resp)
             .with_context(|| format!("failed to decode CRL fetched from `{}`", urls[i]))?;
         crls.push(crl)
     }
-    zip(urls, crls)
-        .collect::<CrlList>()
-        .to_der()
-        .context("failed to encode CRL list")
+    Ok((zip(urls, crls).collect(), chains))
+}
+
+/// Decodes a percent-encoded PEM certificate chain, as sent by Intel's PCS in response headers.
+pub fn issuer_chain(header: &str) -> anyhow::Result<Vec<Certificate>> {
+    let chain = percent_decode(header.as_bytes())
+        .decode_utf8()
+        .context("failed to percent-decode certificate chain")?;
+    pki::read_certificates(chain.as_bytes())
+}
+
+/// Fetches DER or PEM encoded certificates from `url`.
+pub fn fetch_certificates(url: &str) -> anyhow::Result<Vec<Certificate>> {
+    let mut certs = vec![];
+    ureq::get(url)
+        .call()
+        .with_context(|| format!("failed to connect to `{url}`"))?
+        .into_reader()
+        .take(MAX_CERT_SIZE)
+        .read_to_end(&mut certs)
+        .with_context(|| format!("failed to read response from `{url}`"))?;
+    pki::read_certificates(&certs)
+        .with_context(|| format!("failed to decode certificates fetched from `{url}`"))
+}
+
+/// Environment variable overriding the root directory of the caches
+const CACHE_DIR_VAR: &str = "ENARX_CACHE_DIR";
+
+/// Root directory of the system-wide caches
+const SYSTEM_CACHE_DIR: &str = "/var/cache";
+
+/// File name of cached CRLs
+pub const CRLS: &str = "crls.der";
+
+/// A cache of platform collateral
+///
+/// If `ENARX_CACHE_DIR` is set, the cache is a directory below it. Otherwise files
+/// are looked up in the system-wide cache below `/var/cache` first, and then in the
+/// cache of the user below `$XDG_CACHE_HOME/enarx`, which defaults to `~/.cache/enarx`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cache(&'static str);
+
+impl Cache {
+    /// Cache of AMD SEV-SNP collateral
+    pub const AMD: Self = Self("amd-sev");
+    /// Cache of Intel SGX collateral
+    pub const INTEL: Self = Self("intel-sgx");
+
+    /// Returns the directories of the cache in lookup order.
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        roots(std::env::var_os(CACHE_DIR_VAR), dirs::cache_dir())
+            .into_iter()
+            .map(|root| root.join(self.0))
+            .collect()
+    }
+
+    /// Returns the path of the most recently modified cached `file`, or of `file` in the
+    /// first directory of the cache, if no directory contains it.
+    ///
+    /// A refresh by a user without access to the system cache is written to the cache
+    /// of the user, so it must take precedence over the outdated system cache.
+    pub fn path(&self, file: &str) -> PathBuf {
+        let dirs = self.dirs();
+        newest(dirs.iter().map(|dir| dir.join(file))).unwrap_or_else(|| dirs[0].join(file))
+    }
+
+    /// Returns the path of the most recently modified cached `file`.
+    pub fn find(&self, file: &str) -> anyhow::Result<PathBuf> {
+        let path = self.path(file);
+        if path.exists() {
+            Ok(path)
+        } else {
+            Err(io::Error::from(ErrorKind::NotFound))
+                .with_context(|| format!("`{file}` not found in {:?}", self.dirs()))
+        }
+    }
+
+    /// Returns the first directory of the cache which is writable by the current user,
+    /// and creates it if needed.
+    pub fn write_dir(&self) -> anyhow::Result<PathBuf> {
+        let dirs = self.dirs();
+        dirs.iter()
+            .find(|dir| fs::create_dir_all(dir).is_ok() && writable(dir))
+            .cloned()
+            .ok_or_else(|| io::Error::from(ErrorKind::PermissionDenied))
+            .with_context(|| format!("None of the cache directories {dirs:?} is writable"))
+    }
+
+    /// Atomically replaces the cached `file` with `contents` and returns its path.
+    pub fn store(&self, file: &str, contents: &[u8]) -> anyhow::Result<PathBuf> {
+        let path = self.write_dir()?.join(file);
+        store(&path, contents)?;
+        Ok(path)
+    }
+}
+
+/// Returns the most recently modified of the existing `paths`, the first one of equally old ones.
+fn newest(paths: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    let mut newest: Option<(SystemTime, PathBuf)> = None;
+    for path in paths {
+        let modified = match fs::metadata(&path).and_then(|meta| meta.modified()) {
+            Ok(modified) => modified,
+            Err(_) => continue,
+        };
+        if newest.as_ref().map_or(true, |(time, _)| modified > *time) {
+            newest = Some((modified, path));
+        }
+    }
+    newest.map(|(_, path)| path)
+}
+
+/// Returns the root directories of the caches in lookup order.
+fn roots(custom: Option<OsString>, user: Option<PathBuf>) -> Vec<PathBuf> {
+    match custom {
+        Some(custom) if !custom.is_empty() => vec![custom.into()],
+        _ => [
+            Some(SYSTEM_CACHE_DIR.into()),
+            user.map(|user| user.join("enarx")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+    }
+}
+
+fn writable(dir: &Path) -> bool {
+    CString::new(dir.as_os_str().as_bytes())
+        .map(|dir| unsafe { libc::access(dir.as_ptr(), libc::W_OK) } == 0)
+        .unwrap_or(false)
+}
+
+/// Takes an exclusive lock on the cached file at `path`, which is held until the
+/// returned lock file is dropped.
+pub fn lock(path: &Path) -> anyhow::Result<File> {
+    let mut lock = path.as_os_str().to_owned();
+    lock.push(".lck");
+    let lock = PathBuf::from(lock);
+
+    let file = OpenOptions::new()
+        .mode(0o644)
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&lock)
+        .with_context(|| format!("Error creating lockfile {}", lock.display()))?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("Error locking {}", lock.display()));
+    }
+    Ok(file)
+}
+
+/// Atomically replaces the cached file at `path` with `contents`.
+///
+/// The contents are written to a temporary file next to `path` first, so readers
+/// either see the previous or the new contents. Concurrent writers are serialized
+/// by the lock of the file.
+pub fn store(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let _lock = lock(path)?;
+    replace(path, contents)
+}
+
+/// Atomically writes the cached file at `path` with `contents`, unless it exists.
+pub fn store_once(
+    path: &Path,
+    contents: impl FnOnce() -> anyhow::Result<Vec<u8>>,
+) -> anyhow::Result<()> {
+    let _lock = lock(path)?;
+    if path.exists() {
+        return Ok(());
+    }
+    replace(path, &contents()?)
+}
+
+fn replace(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    OpenOptions::new()
+        .mode(0o644)
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&temp)
+        .with_context(|| format!("opening temporary file {temp:?}"))?
+        .write_all(contents)
+        .with_context(|| format!("writing to temporary file {temp:?}"))?;
+
+    fs::rename(&temp, path)
+        .with_context(|| format!("Failed to move temporary file {temp:?} to final path {path:?}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn dirs() {
+        assert_eq!(
+            roots(None, Some("/home/user/.cache".into())),
+            [
+                PathBuf::from("/var/cache"),
+                PathBuf::from("/home/user/.cache/enarx")
+            ]
+        );
+        assert_eq!(roots(None, None), [PathBuf::from("/var/cache")]);
+        assert_eq!(
+            roots(Some("/srv/enarx".into()), Some("/home/user/.cache".into())),
+            [PathBuf::from("/srv/enarx")]
+        );
+        assert_eq!(roots(Some("".into()), None), [PathBuf::from("/var/cache")]);
+    }
+
+    #[test]
+    fn newest_file() {
+        let system = tempfile::tempdir().unwrap();
+        let user = tempfile::tempdir().unwrap();
+        let paths = || [system.path().join(CRLS), user.path().join(CRLS)];
+
+        assert_eq!(newest(paths()), None);
+        store(&paths()[0], b"system").unwrap();
+        assert_eq!(newest(paths()), Some(paths()[0].clone()));
+
+        // A refresh in the user cache hides the outdated system cache, and vice versa.
+        thread::sleep(Duration::from_millis(20));
+        store(&paths()[1], b"user").unwrap();
+        assert_eq!(newest(paths()), Some(paths()[1].clone()));
+        thread::sleep(Duration::from_millis(20));
+        store(&paths()[0], b"system").unwrap();
+        assert_eq!(newest(paths()), Some(paths()[0].clone()));
+    }
+
+    #[test]
+    fn concurrent_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Arc::new(dir.path().join(CRLS));
+
+        let writers: Vec<_> = (0..8u8)
+            .map(|i| {
+                let path = path.clone();
+                thread::spawn(move || store(&path, &[i; 4096]).unwrap())
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let contents = fs::read(&*path).unwrap();
+        assert_eq!(contents.len(), 4096);
+        assert!(contents.iter().all(|b| *b == contents[0]));
+
+        store_once(&path, || panic!("the file exists")).unwrap();
+    }
 }
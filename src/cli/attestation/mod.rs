@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod verify;
+
+use crate::attestation::{pki, Collateral, Reference};
+use crate::caching::CrlList;
+
+use std::fs;
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Context};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, Subcommand};
+use der::Decode;
+
+/// Commands for working with the attestation evidence of Keeps.
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    Verify(verify::Options),
+}
+
+impl Subcommands {
+    pub fn dispatch(self) -> anyhow::Result<ExitCode> {
+        match self {
+            Self::Verify(cmd) => cmd.execute(),
+        }
+    }
+}
+
+/// Collateral to verify attestation evidence with
+#[derive(Args, Debug)]
+pub struct CollateralOptions {
+    /// CRLs to check the certificate chain against, as cached by `enarx platform {sgx,snp} cache-crl`
+    #[clap(long)]
+    crls: Option<Utf8PathBuf>,
+
+    /// SGX TCB info, as cached by `enarx platform sgx cache-tcb`
+    #[clap(long)]
+    tcb: Option<Utf8PathBuf>,
+
+    /// Trusted Intel SGX root CA certificate
+    #[clap(long)]
+    sgx_root: Option<Utf8PathBuf>,
+
+    /// AMD ASK and trusted ARK certificates, in this order.
+    /// If only the ARK is given, the ASK is taken from the evidence.
+    #[clap(long)]
+    snp_chain: Option<Utf8PathBuf>,
+}
+
+impl CollateralOptions {
+    pub fn load(&self) -> anyhow::Result<Collateral> {
+        let crls = match self.crls {
+            Some(ref path) => Some(
+                CrlList::from_der(&read(path)?)
+                    .with_context(|| format!("Failed to decode CRLs in {path}"))?,
+            ),
+            None => None,
+        };
+        let tcb = match self.tcb {
+            Some(ref path) => Some(read(path)?),
+            None => None,
+        };
+        let sgx_root = match self.sgx_root {
+            Some(ref path) => Some(
+                pki::read_certificates(&read(path)?)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow!("No certificate found in {path}"))?,
+            ),
+            None => None,
+        };
+        let snp_chain = match self.snp_chain {
+            Some(ref path) => pki::read_certificates(&read(path)?)?,
+            None => vec![],
+        };
+
+        Ok(Collateral {
+            crls,
+            tcb,
+            sgx_root,
+            snp_chain,
+        })
+    }
+}
+
+/// Reads the expected values of Keeps, as printed by `enarx measure`.
+pub fn read_reference(path: Option<&Utf8Path>) -> anyhow::Result<Reference> {
+    match path {
+        Some(path) => serde_json::from_slice(&read(path)?)
+            .with_context(|| format!("Failed to decode reference values in {path}")),
+        None => Ok(Reference::default()),
+    }
+}
+
+fn read(path: &Utf8Path) -> anyhow::Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read {path}"))
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{read_reference, CollateralOptions};
+use crate::attestation::{self, Evidence, Technology, Verdict};
+use crate::cli::output;
+
+use std::fmt::Write;
+use std::fs;
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use camino::Utf8PathBuf;
+use clap::Args;
+
+/// Verify the attestation evidence of a Keep offline.
+///
+/// The evidence is read from the CSR a Keep sends to its Steward, the signatures of the
+/// evidence are verified against the given collateral and the attested values are checked
+/// against the reference values, as printed by `enarx measure`.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// PEM or DER encoded CSR containing the evidence
+    path: Utf8PathBuf,
+
+    /// Treat the input as raw evidence of the given technology instead of a CSR
+    #[clap(long, value_enum)]
+    technology: Option<Technology>,
+
+    #[clap(flatten)]
+    collateral: CollateralOptions,
+
+    /// Expected measurements of the Keep, as printed by `enarx measure`
+    #[clap(long)]
+    reference: Option<Utf8PathBuf>,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<ExitCode> {
+        let input =
+            fs::read(&self.path).with_context(|| format!("Failed to read {}", self.path))?;
+        let evidence = match self.technology {
+            Some(technology) => Evidence {
+                technology,
+                public_key: None,
+                data: input,
+            },
+            None => Evidence::from_csr(&input)
+                .with_context(|| format!("Failed to read evidence from {}", self.path))?,
+        };
+
+        let collateral = self.collateral.load()?;
+        let reference = read_reference(self.reference.as_deref())?;
+        let verdict = attestation::verify(&evidence, &collateral, &reference, SystemTime::now())?;
+
+        output::print(&verdict, display)?;
+        Ok(if verdict.verified {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        })
+    }
+}
+
+fn display(verdict: &Verdict) -> anyhow::Result<String> {
+    use colorful::*;
+
+    let is_atty = atty::is(atty::Stream::Stdout);
+    let icon = |pass| match (is_atty, pass) {
+        (true, true) => "✔".green().to_string(),
+        (true, false) => "✗".red().to_string(),
+        (false, true) => "✔".into(),
+        (false, false) => "✗".into(),
+    };
+
+    let mut out = String::new();
+    writeln!(out, "Technology: {:?}", verdict.technology)?;
+    for (name, value) in &verdict.claims {
+        writeln!(out, "  {name}: {value}")?;
+    }
+    for check in &verdict.checks {
+        write!(out, " {} {}", icon(check.pass), check.name)?;
+        if let Some(ref info) = check.info {
+            write!(out, ": {info}")?;
+        }
+        writeln!(out)?;
+    }
+    write!(
+        out,
+        "{} Evidence {}",
+        icon(verdict.verified),
+        if verdict.verified {
+            "verified"
+        } else {
+            "NOT verified"
+        }
+    )?;
+    Ok(out)
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::cli::output;
+
+use std::fs::read_to_string;
+use std::process::ExitCode;
+
+use anyhow::Context;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use enarx_config::{Config, Key, Severity};
+use serde::Serialize;
+
+/// Problem found in a configuration file along with its location
+#[derive(Debug, Serialize)]
+pub struct Report {
+    severity: Severity,
+    key: Option<Key>,
+    line: Option<usize>,
+    column: Option<usize>,
+    message: String,
+}
+
+impl Report {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Formats the report in the `path:line:column: severity: message` format.
+    pub fn display(&self, path: &Utf8Path) -> String {
+        let location = match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{path}:{line}:{column}"),
+            _ => path.to_string(),
+        };
+        match self.key {
+            Some(key) => format!("{location}: {}: {key}: {}", self.severity, self.message),
+            None => format!("{location}: {}: {}", self.severity, self.message),
+        }
+    }
+}
+
+/// Returns the 1-based line and column of the definition of `key` in `src`.
+///
+/// Only the canonical TOML syntax emitted by `enarx config init` is recognized,
+/// e.g. the `[[files]]` headers of the file descriptor tables.
+fn locate(src: &str, key: Key) -> Option<(usize, usize)> {
+    fn defines(line: &str, key: &str) -> bool {
+        line.strip_prefix(key)
+            .map_or(false, |rest| rest.trim_start().starts_with('='))
+    }
+
+    let mut files = 0;
+    for (n, line) in src.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let found = match key {
+            Key::Steward => defines(trimmed, "steward"),
+            Key::Files => trimmed.starts_with("[[files]]") || defines(trimmed, "files"),
+            Key::Env => trimmed.starts_with("[env]") || defines(trimmed, "env"),
+            Key::Keep => trimmed.starts_with("[keep]") || defines(trimmed, "keep"),
+            Key::File(i) if trimmed.starts_with("[[files]]") => {
+                files += 1;
+                files == i + 1
+            }
+            Key::File(..) => false,
+        };
+        if found {
+            return Some((n + 1, line.len() - trimmed.len() + 1));
+        }
+    }
+    None
+}
+
+/// Parses and checks an `Enarx.toml` configuration.
+pub fn check_str(src: &str) -> Vec<Report> {
+    match toml::from_str::<Config>(src) {
+        Err(e) => {
+            let (line, column) = e
+                .line_col()
+                .map(|(line, column)| (Some(line + 1), Some(column + 1)))
+                .unwrap_or_default();
+            vec![Report {
+                severity: Severity::Error,
+                key: None,
+                line,
+                column,
+                message: e.to_string(),
+            }]
+        }
+        Ok(config) => config
+            .check()
+            .into_iter()
+            .map(|diag| {
+                let (line, column) = locate(src, diag.key)
+                    .map(|(line, column)| (Some(line), Some(column)))
+                    .unwrap_or_default();
+                Report {
+                    severity: diag.severity,
+                    key: Some(diag.key),
+                    line,
+                    column,
+                    message: diag.message,
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Parses and checks an `Enarx.toml` configuration file.
+pub fn check_file(path: &Utf8Path) -> anyhow::Result<Vec<Report>> {
+    let src = read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+    Ok(check_str(&src))
+}
+
+#[derive(Serialize)]
+struct CheckOutput<'a> {
+    path: &'a Utf8Path,
+    diagnostics: Vec<Report>,
+}
+
+/// Validate an `Enarx.toml` configuration file
+///
+/// Exits with a non-zero exit code if any errors are found.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Path of the configuration file to check
+    #[clap(default_value = "Enarx.toml")]
+    path: Utf8PathBuf,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<ExitCode> {
+        let diagnostics = check_file(&self.path)?;
+        let failed = diagnostics.iter().any(Report::is_error);
+
+        let out = CheckOutput {
+            path: &self.path,
+            diagnostics,
+        };
+        output::print(&out, |out| {
+            Ok(out
+                .diagnostics
+                .iter()
+                .map(|report| report.display(out.path))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        })?;
+
+        if failed {
+            Ok(ExitCode::FAILURE)
+        } else {
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"steward = "http://attest.example.com"
+
+[[files]]
+kind = "stdin"
+
+[[files]]
+kind = "stdout"
+
+  [[files]]
+  name = "stdout"
+  kind = "stderr"
+"#;
+
+    #[test]
+    fn locations() {
+        let reports = check_str(CONFIG)
+            .iter()
+            .map(|report| report.display("Enarx.toml".into()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reports,
+            vec![
+                "Enarx.toml:1:1: error: steward: Steward URL scheme must be `https`, found `http`, the Keep refuses unencrypted Steward URLs",
+                "Enarx.toml:9:3: error: files[2]: duplicate file descriptor name `stdout`, already used by `files[1]`",
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_location() {
+        let src = r#"[[files]]
+kind = "stdin"
+
+[[files]]
+kind = "stdout"
+
+[[files]]
+kind = "stderr"
+
+[keep]
+memory = "1G"
+max-memory = "512M"
+"#;
+        let reports = check_str(src)
+            .iter()
+            .map(|report| report.display("Enarx.toml".into()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reports,
+            vec!["Enarx.toml:10:1: error: keep: memory of 1073741824 bytes exceeds the max-memory of 536870912 bytes"]
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        let reports = check_str("[[files]]\nkind = \"unknown\"\n");
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_error());
+        assert_eq!(reports[0].key, None);
+        assert!(reports[0].line.is_some());
+    }
+}
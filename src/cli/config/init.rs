@@ -1,5 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::output;
+
+use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::io::prelude::*;
 use std::path::Path;
@@ -26,6 +29,7 @@ impl Options {
             .open(enarx_toml_path)?;
 
         enarx_toml.write_all(CONFIG_TEMPLATE.as_bytes())?;
+        output::print_json_only(&BTreeMap::from([("path", enarx_toml_path)]))?;
         Ok(ExitCode::SUCCESS)
     }
 }
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod check;
 mod init;
 
 use std::process::ExitCode;
@@ -10,12 +11,14 @@ use clap::Subcommand;
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     Init(init::Options),
+    Check(check::Options),
 }
 
 impl Subcommands {
     pub fn dispatch(self) -> anyhow::Result<ExitCode> {
         match self {
             Self::Init(cmd) => cmd.execute(),
+            Self::Check(cmd) => cmd.execute(),
         }
     }
 }
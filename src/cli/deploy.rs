@@ -1,8 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::cli::BackendOptions;
-use crate::drawbridge::parse_tag;
-use crate::exec::{open_package, run_package, EXECS};
+use crate::drawbridge::{anonymous_client, get_yank, parse_tag, TagSpec};
+use crate::exec::{open_package, run_package};
 
 use std::fmt::Debug;
 use std::fs;
@@ -10,11 +10,11 @@ use std::fs;
 use std::os::unix::io::IntoRawFd;
 use std::process::ExitCode;
 
-use crate::backend::Signatures;
+use crate::backend::SignatureBundle;
 use anyhow::{anyhow, bail, Context};
 use camino::Utf8PathBuf;
 use clap::Args;
-use enarx_exec_wasmtime::{Package, PACKAGE_CONFIG, PACKAGE_ENTRYPOINT};
+use enarx_exec_wasmtime::{Package, Yank, PACKAGE_CONFIG, PACKAGE_ENTRYPOINT};
 use url::Url;
 
 /// Deploy an Enarx package to an Enarx Keep.
@@ -31,10 +31,18 @@ pub struct Options {
     #[clap(long)]
     pub unsigned: bool,
 
-    /// Path of the signature file to use.
+    /// Path of the signature file or signature bundle to use.
     #[clap(long, value_name = "SIGNATURES")]
     pub signatures: Option<Utf8PathBuf>,
 
+    /// Deploy the package even if it was yanked
+    #[clap(long)]
+    pub allow_yanked: bool,
+
+    /// CA bundle to verify the package host with, when checking the yank status of a package
+    #[clap(long, env = "ENARX_CA_BUNDLE")]
+    pub ca_bundle: Option<Utf8PathBuf>,
+
     /// gdb options
     #[cfg(feature = "gdb")]
     #[clap(long, default_value = "localhost:23456")]
@@ -52,31 +60,12 @@ impl Options {
             package,
             unsigned,
             signatures,
+            allow_yanked,
+            ca_bundle,
             #[cfg(feature = "gdb")]
             gdblisten,
         } = self;
 
-        let backend = backend.pick()?;
-        // TODO: Only allow secure backends
-        // https://github.com/enarx/enarx/issues/1850
-        let exec = EXECS
-            .iter()
-            .find(|w| w.with_backend(backend))
-            .ok_or_else(|| anyhow!("no supported exec found"))
-            .map(|b| b.exec())?;
-
-        #[cfg(not(feature = "gdb"))]
-        let gdblisten = None;
-
-        #[cfg(feature = "gdb")]
-        let gdblisten = Some(gdblisten);
-
-        let signatures = if unsigned {
-            None
-        } else {
-            Signatures::load(signatures)?
-        };
-
         let package = match package
             .parse()
             .ok()
@@ -85,8 +74,25 @@ impl Options {
             None => {
                 use drawbridge_client::API_VERSION;
 
-                let (host, user, repo, tag) = parse_tag(&package)
+                let spec: TagSpec = package
+                    .parse()
                     .with_context(|| format!("failed to parse `{package}` as a Drawbridge slug"))?;
+
+                // Fail before starting a Keep, which checks the yank status again.
+                if !allow_yanked {
+                    let cl = anonymous_client(&spec.host, ca_bundle)?;
+                    if let Some(Yank {
+                        yanked: true,
+                        reason,
+                    }) =
+                        get_yank(&cl, &spec.ctx).context("failed to fetch package yank status")?
+                    {
+                        let reason = reason.map(|r| format!(": {r}")).unwrap_or_default();
+                        bail!("package `{package}` was yanked{reason}");
+                    }
+                }
+
+                let (host, user, repo, tag) = parse_tag(&package)?;
                 format!("https://{host}/api/v{API_VERSION}/{user}/{repo}/_tag/{tag}")
                     .parse()
                     .with_context(|| {
@@ -96,6 +102,26 @@ impl Options {
             Some(url) => url,
         };
 
+        // The package is fetched by the Keep, so only the options set the resources.
+        backend.configure(Default::default())?;
+        let keep_backend = backend.pick()?;
+        backend.check_collateral(keep_backend)?;
+        // TODO: Only allow secure backends
+        // https://github.com/enarx/enarx/issues/1850
+
+        #[cfg(not(feature = "gdb"))]
+        let gdblisten = None;
+
+        #[cfg(feature = "gdb")]
+        let gdblisten = Some(gdblisten);
+
+        let bundle = if unsigned {
+            None
+        } else {
+            SignatureBundle::load(signatures)?
+        };
+        let (shim, exec, signatures) = backend.binaries(keep_backend, bundle.as_ref())?;
+
         match package.scheme() {
             "file" => {
                 let path = package
@@ -134,7 +160,8 @@ impl Options {
                 };
 
                 run_package(
-                    backend,
+                    keep_backend,
+                    shim,
                     exec,
                     signatures,
                     gdblisten,
@@ -149,11 +176,17 @@ impl Options {
             // The WASM module and config will be downloaded from a remote by exec-wasmtime
             // TODO: Disallow `http` or guard by an `--insecure` flag
             "http" | "https" => run_package(
-                backend,
+                keep_backend,
+                shim,
                 exec,
                 signatures,
                 gdblisten,
-                || Ok(Package::Remote(package)),
+                || {
+                    Ok(Package::Remote {
+                        url: package,
+                        allow_yanked,
+                    })
+                },
                 #[cfg(unix)]
                 log_level,
                 #[cfg(all(unix, feature = "bench"))]
@@ -3,8 +3,14 @@
 pub mod sev;
 mod sgx;
 
+use crate::cli::output;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{stdout, Write};
 use std::process::ExitCode;
 
+use camino::Utf8PathBuf;
 use clap::Subcommand;
 
 /// Commands for utilizing keys to interact with Enarx.
@@ -25,3 +31,21 @@ impl Subcommands {
         }
     }
 }
+
+/// Write the result of a key command to `out`, if specified, or print it to stdout otherwise.
+///
+/// In JSON mode, `{"<name>": "<value>"}` or `{"out": "<out>"}` is printed, respectively.
+fn write_result(name: &str, value: &str, out: Option<Utf8PathBuf>) -> anyhow::Result<()> {
+    if let Some(path) = out {
+        let mut file = File::create(&path)?;
+        file.write_all(value.as_bytes())?;
+        if output::is_json() {
+            output::print_json(&BTreeMap::from([("out", path.as_str())]))?;
+        }
+    } else if output::is_json() {
+        output::print_json(&BTreeMap::from([(name, value)]))?;
+    } else {
+        stdout().write_all(value.as_bytes())?;
+    }
+    Ok(())
+}
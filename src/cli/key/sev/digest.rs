@@ -2,11 +2,11 @@
 
 use crate::backend::sev::snp::sign::PublicKey;
 use crate::backend::ByteSized;
+use crate::cli::key::write_result;
 
 use std::fmt::Debug;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::stdout;
 use std::process::ExitCode;
 
 use anyhow::{bail, Context};
@@ -62,12 +62,7 @@ impl Options {
         let res = sev_key_digest(&sev_key)?;
         let out = hex::encode(res);
 
-        if let Some(path) = self.out {
-            let mut file = File::create(path)?;
-            file.write_all(out.as_bytes())?;
-        } else {
-            stdout().write_all(out.as_bytes())?;
-        }
+        write_result("digest", &out, self.out)?;
 
         Ok(ExitCode::SUCCESS)
     }
@@ -1,9 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::key::write_result;
+
 use std::fmt::Debug;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::stdout;
 use std::process::ExitCode;
 
 use camino::Utf8PathBuf;
@@ -28,12 +27,7 @@ impl Options {
 
         let res_key = RsaPrivateKey::to_pkcs1_pem(&key, LineEnding::default()).unwrap();
 
-        if let Some(path) = self.out {
-            let mut file = File::create(path)?;
-            file.write_all(res_key.as_bytes())?;
-        } else {
-            stdout().write_all(res_key.as_bytes())?;
-        }
+        write_result("key", &res_key, self.out)?;
 
         Ok(ExitCode::SUCCESS)
     }
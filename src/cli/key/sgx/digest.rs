@@ -1,9 +1,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::key::write_result;
+
 use std::fmt::Debug;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::stdout;
 use std::process::ExitCode;
 
 use anyhow::Context;
@@ -50,12 +51,7 @@ impl Options {
         let res = sgx_key_digest(&sgx_key)?;
         let out = hex::encode(res);
 
-        if let Some(path) = self.out {
-            let mut file = File::create(path)?;
-            file.write_all(out.as_bytes())?;
-        } else {
-            stdout().write_all(out.as_bytes())?;
-        }
+        write_result("digest", &out, self.out)?;
 
         Ok(ExitCode::SUCCESS)
     }
@@ -0,0 +1,405 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::backend::sev::snp::launch::{IdAuth, IdBlock};
+use crate::backend::ByteSized;
+use crate::backend::{check_header, Backend, SevSignature, SignatureBundle, Signatures, BACKENDS};
+use crate::cli::sign::SevIdOptions;
+use crate::cli::{output, KeepOptions};
+use crate::exec::EXECS;
+
+use std::ops::{Deref, Range};
+use std::process::ExitCode;
+
+use anyhow::{anyhow, bail, Context, Result};
+use camino::Utf8PathBuf;
+use chrono::Utc;
+use clap::Args;
+use mmarinus::{perms, Map, Private};
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha384};
+
+/// Offset of MISCMASK in the SIGSTRUCT body as per the Intel SDM
+pub(super) const BODY_MISCMASK: Range<usize> = 4..8;
+/// Offset of the features of ATTRIBUTEMASK in the SIGSTRUCT body as per the Intel SDM
+pub(super) const BODY_FEATURESMASK: Range<usize> = 44..52;
+/// Offset of the XFRM of ATTRIBUTEMASK in the SIGSTRUCT body as per the Intel SDM
+pub(super) const BODY_XFRMMASK: Range<usize> = 52..60;
+/// Offset of MRENCLAVE in the SIGSTRUCT body as per the Intel SDM
+const BODY_MRENCLAVE: Range<usize> = 60..92;
+/// Offset of ISVPRODID in the SIGSTRUCT body as per the Intel SDM
+pub(super) const BODY_ISVPRODID: Range<usize> = 124..126;
+/// Offset of ISVSVN in the SIGSTRUCT body as per the Intel SDM
+pub(super) const BODY_ISVSVN: Range<usize> = 126..128;
+/// Offset of the RSA modulus in the SIGSTRUCT as per the Intel SDM
+const SIGSTRUCT_MODULUS: Range<usize> = 128..512;
+/// Offset of the body in the SIGSTRUCT as per the Intel SDM
+const SIGSTRUCT_BODY: Range<usize> = 900..1028;
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct SgxMeasurement {
+    mrenclave: String,
+    mrsigner: Option<String>,
+    isv_prod_id: u16,
+    isv_svn: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct SevMeasurement {
+    measurement: String,
+    family_id: String,
+    image_id: String,
+    guest_svn: u32,
+    policy: u64,
+    id_block_digest: String,
+    id_key_digest: Option<String>,
+    author_key_digest: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Measurements {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sgx: Option<SgxMeasurement>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sev: Option<SevMeasurement>,
+}
+
+fn measure_sgx(body: &[u8], sigstruct: Option<&[u8]>) -> Result<SgxMeasurement> {
+    if body.len() != SIGSTRUCT_BODY.len() {
+        bail!("Invalid length of SGX SIGSTRUCT body");
+    }
+    let mrenclave = &body[BODY_MRENCLAVE];
+
+    // The signed body sets the ISV product ID and SVN of the enclave.
+    let (body, mrsigner) = match sigstruct {
+        None => (body, None),
+        Some(sigstruct) => {
+            if sigstruct.len() < SIGSTRUCT_BODY.end {
+                bail!("Invalid length of SGX signature");
+            }
+            if sigstruct[SIGSTRUCT_BODY][BODY_MRENCLAVE] != *mrenclave {
+                bail!("SGX signature does not match the measured MRENCLAVE");
+            }
+            (
+                &sigstruct[SIGSTRUCT_BODY],
+                Some(hex::encode(Sha256::digest(&sigstruct[SIGSTRUCT_MODULUS]))),
+            )
+        }
+    };
+
+    Ok(SgxMeasurement {
+        mrenclave: hex::encode(mrenclave),
+        mrsigner,
+        isv_prod_id: u16::from_le_bytes(body[BODY_ISVPRODID].try_into()?),
+        isv_svn: u16::from_le_bytes(body[BODY_ISVSVN].try_into()?),
+    })
+}
+
+/// Measures the SEV ID block `id_block`.
+///
+/// A signed ID block replaces `id_block`, as it sets the ID and the policy of the Keep.
+fn measure_sev(id_block: &[u8], signature: Option<&SevSignature>) -> Result<SevMeasurement> {
+    let block =
+        IdBlock::from_bytes(id_block).ok_or_else(|| anyhow!("Invalid length of SEV ID block"))?;
+
+    let (block, id_key_digest, author_key_digest) = match signature {
+        None => (block, None, None),
+        Some(signature) => {
+            let signed = IdBlock::from_bytes(&signature.id_block)
+                .ok_or_else(|| anyhow!("Invalid length of signed SEV ID block"))?;
+            if signed.launch_digest != block.launch_digest {
+                bail!("SEV signature does not match the measured launch digest");
+            }
+            let id_auth = IdAuth::from_bytes(&signature.id_auth)
+                .ok_or_else(|| anyhow!("Invalid length of SEV ID authentication information"))?;
+            (
+                signed,
+                Some(hex::encode(Sha384::digest(id_auth.id_key.as_bytes()))),
+                Some(hex::encode(Sha384::digest(id_auth.author_key.as_bytes()))),
+            )
+        }
+    };
+
+    Ok(SevMeasurement {
+        measurement: hex::encode(block.launch_digest),
+        family_id: hex::encode(block.family_id),
+        image_id: hex::encode(block.image_id),
+        guest_svn: block.guest_svn,
+        policy: block.policy,
+        id_block_digest: hex::encode(Sha384::digest(block.as_bytes())),
+        id_key_digest,
+        author_key_digest,
+    })
+}
+
+/// Checks that `signatures` match `blob`, the measurement of a Keep of `backend`
+/// as returned by [`Backend::hash`].
+///
+/// Backends without a measurement or a signature in `signatures` are not checked.
+fn check_blob(backend: &str, blob: &[u8], signatures: &Signatures) -> Result<()> {
+    match backend {
+        "sgx" if !signatures.sgx.is_empty() => {
+            measure_sgx(blob, Some(&signatures.sgx))?;
+        }
+        "sev" if !signatures.sev.id_block.is_empty() => {
+            measure_sev(blob, Some(&signatures.sev))?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Returns the measurements `signatures` sign, the SGX MRENCLAVE and the SEV launch digest.
+pub(super) fn signed_measurements(signatures: &Signatures) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mrenclave = match signatures.sgx.len() {
+        0 => vec![],
+        len if len < SIGSTRUCT_BODY.end => bail!("Invalid length of SGX signature"),
+        _ => signatures.sgx[SIGSTRUCT_BODY][BODY_MRENCLAVE].to_vec(),
+    };
+    let launch_digest = match signatures.sev.id_block.len() {
+        0 => vec![],
+        _ => IdBlock::from_bytes(&signatures.sev.id_block)
+            .ok_or_else(|| anyhow!("Invalid length of signed SEV ID block"))?
+            .launch_digest
+            .to_vec(),
+    };
+    Ok((mrenclave, launch_digest))
+}
+
+/// Returns whether signatures match the measurement of a Keep of `backend` running
+/// `shim` and `exec`, as used by [`SignatureBundle::select`].
+///
+/// The Keep is measured once, when the first signatures of a measured backend are checked.
+pub(crate) fn signature_matcher<'a>(
+    backend: &'a dyn Backend,
+    shim: &'a [u8],
+    exec: &'a [u8],
+) -> impl FnMut(&Signatures) -> Result<bool> + 'a {
+    let mut blob = None;
+    move |signatures| {
+        if !["sgx", "sev"].contains(&backend.name()) {
+            return Ok(true);
+        }
+        let blob = match blob {
+            Some(ref blob) => blob,
+            None => blob.insert(backend.hash(shim, exec)?),
+        };
+        Ok(check_blob(backend.name(), blob, signatures).is_ok())
+    }
+}
+
+/// Print the expected launch measurements of a Keep as JSON.
+///
+/// The measurements are computed in software, so no SGX or SEV hardware is required.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Backend to measure ("sgx", "sev"), all backends by default
+    #[clap(long)]
+    backend: Option<String>,
+
+    /// External shim binary to measure instead of the builtin shim, requires `--backend`
+    #[clap(long)]
+    shim: Option<Utf8PathBuf>,
+
+    /// External exec binary to measure instead of the builtin exec
+    #[clap(long)]
+    exec: Option<Utf8PathBuf>,
+
+    /// Signatures file produced by `enarx sign`, used to compute the signer digests
+    #[clap(long)]
+    signatures: Option<Utf8PathBuf>,
+
+    #[clap(flatten)]
+    keep: KeepOptions,
+
+    /// SEV ID block fields of unsigned Keeps, as passed to `enarx sign`
+    #[clap(flatten)]
+    sev_id: SevIdOptions,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<ExitCode> {
+        if self.shim.is_some() && self.backend.is_none() {
+            bail!("`--shim` requires `--backend`");
+        }
+        if let Some(ref name) = self.backend {
+            if !["sgx", "sev"].contains(&name.as_str()) {
+                bail!("Keep backend {name:?} cannot be measured");
+            }
+        }
+
+        if self.signatures.is_some() && self.sev_id.is_set() {
+            bail!("The SEV ID block options conflict with the ID block of `--signatures`");
+        }
+        self.keep.configure(Default::default())?;
+
+        let external_shim = match self.shim {
+            Some(ref path) => Some(
+                Map::load(path, Private, perms::Read)
+                    .with_context(|| format!("Failed to load shim {path}"))?,
+            ),
+            None => None,
+        };
+        let external_exec = match self.exec {
+            Some(ref path) => Some(
+                Map::load(path, Private, perms::Read)
+                    .with_context(|| format!("Failed to load exec {path}"))?,
+            ),
+            None => None,
+        };
+        if let (Some(path), Some(shim)) = (&self.shim, &external_shim) {
+            check_header(shim).with_context(|| format!("Invalid shim binary {path}"))?;
+        }
+        if let (Some(path), Some(exec)) = (&self.exec, &external_exec) {
+            check_header(exec).with_context(|| format!("Invalid exec binary {path}"))?;
+        }
+        let bundle = match self.signatures {
+            Some(path) => SignatureBundle::load(Some(path))?,
+            None => None,
+        };
+        let now = Utc::now();
+
+        let mut measurements = Measurements::default();
+
+        for backend in BACKENDS.deref().iter() {
+            let backend: &dyn Backend = backend.deref();
+
+            if !["sgx", "sev"].contains(&backend.name()) {
+                continue;
+            }
+            if let Some(ref name) = self.backend {
+                if backend.name() != name {
+                    continue;
+                }
+            }
+
+            let shim = match external_shim {
+                Some(ref shim) => shim.as_ref(),
+                None => backend.shim(),
+            };
+            let exec = if let Some(ref e) = external_exec {
+                e.as_ref()
+            } else if let Some(e) = EXECS.iter().find(|w| w.with_backend(backend)) {
+                e.exec()
+            } else {
+                &[]
+            };
+
+            if shim.is_empty() || exec.is_empty() {
+                bail!(
+                    "Keep backend {:?} was not compiled in, use `--shim` and `--exec`",
+                    backend.name()
+                );
+            }
+
+            let blob = backend
+                .hash(shim, exec)
+                .with_context(|| format!("Failed to measure Keep backend {:?}", backend.name()))?;
+
+            // The signatures of the bundle, which a Keep of the measured binaries would launch with
+            let signatures = match bundle {
+                Some(ref bundle) => {
+                    Some(bundle.select(backend.name(), now, true, |signatures| {
+                        Ok(check_blob(backend.name(), &blob, signatures).is_ok())
+                    })?)
+                }
+                None => None,
+            };
+
+            match backend.name() {
+                "sgx" => {
+                    let sigstruct = signatures
+                        .map(|s| s.sgx.as_slice())
+                        .filter(|s| !s.is_empty());
+                    measurements.sgx = Some(measure_sgx(&blob, sigstruct)?);
+                }
+                "sev" => {
+                    let mut id_block = IdBlock::from_bytes(&blob)
+                        .ok_or_else(|| anyhow!("Invalid length of SEV ID block"))?;
+                    self.sev_id.apply(&mut id_block);
+
+                    let signature = signatures
+                        .map(|s| &s.sev)
+                        .filter(|s| !s.id_block.is_empty());
+                    measurements.sev = Some(measure_sev(id_block.as_bytes(), signature)?);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        output::print_json(&measurements)?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sgx() {
+        let mut body = [0u8; 128];
+        body[BODY_MRENCLAVE].copy_from_slice(&[0xaa; 32]);
+        body[BODY_ISVPRODID].copy_from_slice(&0x1234u16.to_le_bytes());
+        body[BODY_ISVSVN].copy_from_slice(&7u16.to_le_bytes());
+
+        let measurement = measure_sgx(&body, None).unwrap();
+        assert_eq!(
+            measurement,
+            SgxMeasurement {
+                mrenclave: hex::encode([0xaa; 32]),
+                mrsigner: None,
+                isv_prod_id: 0x1234,
+                isv_svn: 7,
+            }
+        );
+
+        let mut sigstruct = [0u8; 1808];
+        sigstruct[SIGSTRUCT_MODULUS].copy_from_slice(&[0x55; 384]);
+        assert!(measure_sgx(&body, Some(&sigstruct)).is_err());
+
+        sigstruct[SIGSTRUCT_BODY].copy_from_slice(&body);
+        let measurement = measure_sgx(&body, Some(&sigstruct)).unwrap();
+        assert_eq!(
+            measurement.mrsigner,
+            Some(hex::encode(Sha256::digest([0x55; 384])))
+        );
+    }
+
+    #[test]
+    fn sev() {
+        let block = IdBlock {
+            launch_digest: [0xaa; 48],
+            guest_svn: 3,
+            policy: 0x30000,
+            ..Default::default()
+        };
+
+        let measurement = measure_sev(block.as_bytes(), None).unwrap();
+        assert_eq!(measurement.measurement, hex::encode([0xaa; 48]));
+        assert_eq!(measurement.guest_svn, 3);
+        assert_eq!(measurement.policy, 0x30000);
+        assert_eq!(
+            measurement.id_block_digest,
+            hex::encode(Sha384::digest(block.as_bytes()))
+        );
+        assert_eq!(measurement.id_key_digest, None);
+
+        let id_auth = IdAuth::default();
+        let signature = SevSignature {
+            id_block: block.as_bytes().to_vec(),
+            id_auth: id_auth.as_bytes().to_vec(),
+        };
+        let measurement = measure_sev(block.as_bytes(), Some(&signature)).unwrap();
+        assert_eq!(
+            measurement.id_key_digest,
+            Some(hex::encode(Sha384::digest(id_auth.id_key.as_bytes())))
+        );
+
+        let other = IdBlock::default();
+        let signature = SevSignature {
+            id_block: other.as_bytes().to_vec(),
+            id_auth: id_auth.as_bytes().to_vec(),
+        };
+        assert!(measure_sev(block.as_bytes(), Some(&signature)).is_err());
+    }
+}
@@ -1,29 +1,40 @@
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(enarx_with_shim)]
+mod attestation;
 mod config;
 mod deploy;
 #[cfg(enarx_with_shim)]
 mod key;
+#[cfg(enarx_with_shim)]
+mod measure;
+pub(crate) mod output;
 mod package;
 mod platform;
 mod repo;
 mod run;
 #[cfg(enarx_with_shim)]
 mod sign;
+#[cfg(enarx_with_shim)]
+mod steward;
 mod tree;
 mod unstable;
 mod user;
 
 #[cfg(enarx_with_shim)]
 use crate::backend::probe::x86_64::Vendor;
-use crate::backend::{Backend, BACKENDS};
+use crate::backend::{Backend, SignatureBundle, Signatures, BACKENDS};
+use crate::cli::output::OutputFormat;
+use crate::exec::EXECS;
 
+use std::borrow::Cow;
 use std::io;
 use std::ops::Deref;
 use std::process::ExitCode;
 use std::str::FromStr;
 
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, Context};
+use chrono::Utc;
 use clap::{Args, Parser, Subcommand};
 use tracing::info;
 use tracing_subscriber::filter::{filter_fn, FilterExt};
@@ -46,6 +57,10 @@ pub struct Options {
     #[clap(flatten)]
     logger: LogOptions,
 
+    /// Output options
+    #[clap(flatten)]
+    output: OutputOptions,
+
     /// Subcommands (with their own options)
     #[clap(subcommand)]
     cmd: Subcommands,
@@ -53,6 +68,12 @@ pub struct Options {
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
+        let format = match self.cmd {
+            Subcommands::Platform(ref subcmd) => subcmd.output_format(),
+            _ => None,
+        };
+        output::set_format(format.unwrap_or(self.output.output));
+
         let env_filter = EnvFilter::builder()
             .parse_lossy(self.logger.log_filter.as_ref().unwrap_or(&"".to_owned()));
         #[cfg(unix)]
@@ -121,13 +142,15 @@ impl Options {
         info!("logging initialized!");
         info!("CLI opts: {:?}", self);
 
-        match self.cmd {
+        let res = match self.cmd {
             Subcommands::Run(cmd) => cmd.execute(
                 #[cfg(unix)]
                 log_level,
                 #[cfg(all(unix, feature = "bench"))]
                 profile,
             ),
+            #[cfg(enarx_with_shim)]
+            Subcommands::Attestation(cmd) => cmd.dispatch(),
             Subcommands::Config(cmd) => cmd.dispatch(),
             Subcommands::Deploy(cmd) => cmd.execute(
                 #[cfg(unix)]
@@ -137,31 +160,21 @@ impl Options {
             ),
             #[cfg(enarx_with_shim)]
             Subcommands::Key(cmd) => cmd.dispatch(),
+            #[cfg(enarx_with_shim)]
+            Subcommands::Measure(cmd) => cmd.execute(),
             Subcommands::Platform(cmd) => cmd.dispatch(),
             Subcommands::Package(cmd) => cmd.dispatch(),
             Subcommands::Repo(cmd) => cmd.dispatch(),
             #[cfg(enarx_with_shim)]
             Subcommands::Sign(cmd) => cmd.execute(),
+            #[cfg(enarx_with_shim)]
+            Subcommands::Steward(cmd) => cmd.dispatch(),
             Subcommands::Tree(cmd) => cmd.dispatch(),
             Subcommands::User(cmd) => cmd.dispatch(),
             Subcommands::Unstable(cmd) => cmd.dispatch(),
             #[cfg(enarx_with_shim)]
             Subcommands::UpdateCache => {
-                match Vendor::get()? {
-                    Vendor::Amd => {
-                        use crate::cli::platform::snp::crl::CrlCache;
-                        let crl_cache_cmd = CrlCache::default();
-                        crl_cache_cmd.execute()?;
-                    }
-                    Vendor::Intel => {
-                        use crate::cli::platform::sgx::crl::CrlCache;
-                        use crate::cli::platform::sgx::tcb::TcbCache;
-                        let crl_cache_cmd = CrlCache::default();
-                        crl_cache_cmd.execute()?;
-                        let tcb_cache_cmd = TcbCache::default();
-                        tcb_cache_cmd.execute()?;
-                    }
-                }
+                platform::cache::refresh(Vendor::get()?)?;
                 Ok(ExitCode::SUCCESS)
             }
             #[cfg(enarx_with_shim)]
@@ -180,6 +193,14 @@ impl Options {
                 }
                 Ok(ExitCode::SUCCESS)
             }
+        };
+
+        match res {
+            Err(e) if output::is_json() => {
+                output::print_error(&e);
+                Ok(ExitCode::FAILURE)
+            }
+            res => res,
         }
     }
 }
@@ -189,11 +210,16 @@ impl Options {
 enum Subcommands {
     Run(run::Options),
     Deploy(deploy::Options),
+    #[cfg(enarx_with_shim)]
+    #[clap(subcommand)]
+    Attestation(attestation::Subcommands),
     #[clap(subcommand)]
     Config(config::Subcommands),
     #[cfg(enarx_with_shim)]
     #[clap(subcommand)]
     Key(key::Subcommands),
+    #[cfg(enarx_with_shim)]
+    Measure(measure::Options),
     #[clap(subcommand)]
     Platform(platform::Subcommands),
     #[clap(subcommand)]
@@ -203,6 +229,9 @@ enum Subcommands {
     #[cfg(enarx_with_shim)]
     #[clap(hide = true)]
     Sign(sign::Options),
+    #[cfg(enarx_with_shim)]
+    #[clap(subcommand)]
+    Steward(steward::Subcommands),
     #[clap(subcommand, hide = true)]
     Tree(tree::Subcommands),
     #[clap(subcommand)]
@@ -223,8 +252,31 @@ pub struct BackendOptions {
     /// Set which backend to use
     #[clap(long, env = "ENARX_BACKEND")]
     backend: Option<String>,
-    // TODO: Path to an external shim binary?
-    //shim: Option<PathBuf>,
+
+    /// Record every syscall, enarxcall and gdbcall of the Keep to this file, as JSON Lines
+    #[cfg(enarx_with_shim)]
+    #[clap(long, value_name = "PATH")]
+    trace_syscalls: Option<camino::Utf8PathBuf>,
+
+    #[cfg(enarx_with_shim)]
+    #[clap(flatten)]
+    keep: KeepOptions,
+
+    /// Path of an external shim binary to use instead of the builtin shim of the backend
+    #[cfg(enarx_with_shim)]
+    #[clap(long, env = "ENARX_SHIM", value_name = "PATH")]
+    shim: Option<camino::Utf8PathBuf>,
+
+    /// Path of an external exec binary to use instead of the builtin exec
+    #[cfg(enarx_with_shim)]
+    #[clap(long, env = "ENARX_EXEC", value_name = "PATH")]
+    exec: Option<camino::Utf8PathBuf>,
+
+    /// How to handle stale or missing platform collateral, which is embedded in the
+    /// evidence of SGX and SEV Keeps, before launching the Keep
+    #[cfg(enarx_with_shim)]
+    #[clap(long, env = "ENARX_COLLATERAL", value_enum, default_value = "warn")]
+    collateral: platform::cache::status::Check,
 }
 
 impl BackendOptions {
@@ -253,9 +305,134 @@ impl BackendOptions {
         }
         .map(|b| &**b)
     }
+
+    /// Returns the shim binary for `backend`, which is the builtin shim unless `--shim` is given.
+    pub fn shim(&self, backend: &dyn Backend) -> anyhow::Result<Cow<'static, [u8]>> {
+        #[cfg(enarx_with_shim)]
+        if let Some(ref path) = self.shim {
+            let shim =
+                std::fs::read(path).with_context(|| format!("failed to read shim at `{path}`"))?;
+            crate::backend::check_header(&shim)
+                .with_context(|| format!("invalid shim binary at `{path}`"))?;
+            return Ok(Cow::Owned(shim));
+        }
+        Ok(Cow::Borrowed(backend.shim()))
+    }
+
+    /// Returns the exec binary for `backend`, which is the builtin exec unless `--exec` is given.
+    pub fn exec(&self, backend: &dyn Backend) -> anyhow::Result<Cow<'static, [u8]>> {
+        #[cfg(enarx_with_shim)]
+        if let Some(ref path) = self.exec {
+            let exec =
+                std::fs::read(path).with_context(|| format!("failed to read exec at `{path}`"))?;
+            crate::backend::check_header(&exec)
+                .with_context(|| format!("invalid exec binary at `{path}`"))?;
+            return Ok(Cow::Owned(exec));
+        }
+        EXECS
+            .iter()
+            .find(|w| w.with_backend(backend))
+            .ok_or_else(|| anyhow!("no supported exec found"))
+            .map(|b| Cow::Borrowed(b.exec()))
+    }
+
+    /// Returns the shim and exec binaries for `backend` and the signatures of `bundle`
+    /// to launch them with.
+    ///
+    /// If an external binary is used, the measurement of the Keep is checked
+    /// against the signatures before any Keep is created.
+    pub fn binaries(
+        &self,
+        backend: &dyn Backend,
+        bundle: Option<&SignatureBundle>,
+    ) -> anyhow::Result<(Cow<'static, [u8]>, Cow<'static, [u8]>, Option<Signatures>)> {
+        let shim = self.shim(backend)?;
+        let exec = self.exec(backend)?;
+
+        let signatures = match bundle {
+            Some(bundle) => {
+                #[cfg(enarx_with_shim)]
+                let (verify, matches) = (
+                    self.shim.is_some() || self.exec.is_some(),
+                    measure::signature_matcher(backend, &shim, &exec),
+                );
+                #[cfg(not(enarx_with_shim))]
+                let (verify, matches) = (false, |_: &Signatures| anyhow::Ok(true));
+
+                let signatures = bundle
+                    .select(backend.name(), Utc::now(), verify, matches)
+                    .context("failed to select the signatures of the Keep")?;
+                Some(signatures.clone())
+            }
+            None => None,
+        };
+
+        Ok((shim, exec, signatures))
+    }
+
+    /// Checks the cached platform collateral of `backend`, as selected by `--collateral`.
+    #[cfg_attr(not(enarx_with_shim), allow(unused_variables))]
+    pub fn check_collateral(&self, backend: &dyn Backend) -> anyhow::Result<()> {
+        #[cfg(enarx_with_shim)]
+        platform::cache::status::check(backend.name(), self.collateral)?;
+        Ok(())
+    }
+
+    /// Applies the options, which take effect on the creation of the Keep.
+    ///
+    /// `package` are the Keep resources requested by the `Enarx.toml` of the package, if known.
+    #[cfg_attr(not(enarx_with_shim), allow(unused_variables))]
+    pub fn configure(&self, package: enarx_config::Keep) -> anyhow::Result<()> {
+        #[cfg(enarx_with_shim)]
+        if let Some(ref path) = self.trace_syscalls {
+            crate::backend::trace::init(path)?;
+        }
+        #[cfg(enarx_with_shim)]
+        self.keep.configure(package)?;
+        Ok(())
+    }
 }
 
-/// Common logging / output options
+/// Resources of the Keep, overriding the `keep` table of `Enarx.toml`
+///
+/// The resources of SGX and SEV Keeps are part of their measurement, so signed
+/// Keeps must be signed with the same resources.
+#[cfg(enarx_with_shim)]
+#[derive(Args, Debug)]
+pub struct KeepOptions {
+    /// Maximum number of threads, i.e. vCPUs of KVM and SEV Keeps and TCSs of SGX Keeps
+    ///
+    /// KVM Keeps default to the number of host CPUs. SEV Keeps create all vCPUs on
+    /// launch and default to one.
+    #[clap(long, env = "ENARX_THREADS", value_name = "N")]
+    threads: Option<std::num::NonZeroU64>,
+
+    /// Memory of the Keep on startup, e.g. "256MiB"
+    #[clap(long, env = "ENARX_MEMORY", value_name = "SIZE")]
+    memory: Option<enarx_config::MemorySize>,
+
+    /// Maximum memory of the Keep, e.g. "4GiB"
+    #[clap(long, env = "ENARX_MAX_MEMORY", value_name = "SIZE")]
+    max_memory: Option<enarx_config::MemorySize>,
+}
+
+#[cfg(enarx_with_shim)]
+impl KeepOptions {
+    /// Sets the resources of the Keep from the options and the `keep` table of `package`.
+    pub fn configure(&self, package: enarx_config::Keep) -> anyhow::Result<()> {
+        let resources = enarx_config::Keep {
+            threads: self.threads.or(package.threads),
+            memory: self.memory.or(package.memory),
+            max_memory: self.max_memory.or(package.max_memory),
+        };
+        if resources != enarx_config::Keep::default() {
+            crate::backend::resources::init(resources)?;
+        }
+        Ok(())
+    }
+}
+
+/// Common logging options
 #[derive(Args, Debug)]
 pub struct LogOptions {
     /// Set fancier logging filters.
@@ -275,6 +452,14 @@ pub struct LogOptions {
     profile: Option<camino::Utf8PathBuf>,
 }
 
+/// Common output options
+#[derive(Args, Debug)]
+pub struct OutputOptions {
+    /// Set output format of command results and errors ("text", "json")
+    #[clap(long, global = true, env = "ENARX_OUTPUT", default_value = "text")]
+    output: OutputFormat,
+}
+
 /// Represents logging target.
 #[derive(Debug, Clone, Copy)]
 enum LogTarget {
@@ -293,3 +478,71 @@ impl FromStr for LogTarget {
         }
     }
 }
+
+#[cfg(all(test, enarx_with_shim))]
+mod test {
+    use super::*;
+    use crate::backend::{SevSignature, SIGNATURES_VERSION};
+
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    #[derive(Parser, Debug)]
+    struct Cli {
+        #[clap(flatten)]
+        backend: BackendOptions,
+    }
+
+    fn binary(bytes: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(bytes).unwrap();
+        file
+    }
+
+    #[test]
+    fn external_binaries() {
+        let sgx = BACKENDS.iter().find(|b| b.name() == "sgx").unwrap().deref();
+        let exec = EXECS.iter().find(|w| w.with_backend(sgx)).unwrap().exec();
+        let shim_file = binary(sgx.shim());
+        let exec_file = binary(exec);
+
+        std::env::set_var("ENARX_SHIM", shim_file.path());
+        std::env::set_var("ENARX_EXEC", exec_file.path());
+        let external = Cli::try_parse_from(["enarx"]).unwrap().backend;
+        std::env::remove_var("ENARX_SHIM");
+        std::env::remove_var("ENARX_EXEC");
+        let builtin = Cli::try_parse_from(["enarx"]).unwrap().backend;
+
+        assert!(external.shim.is_some());
+        assert!(external.exec.is_some());
+        assert_eq!(*external.shim(sgx).unwrap(), *sgx.shim());
+        assert_eq!(*external.exec(sgx).unwrap(), *exec);
+
+        // Signatures of a different Keep are refused for external binaries, but
+        // not checked for the builtin ones, which they are made for.
+        let signatures = Signatures {
+            version: SIGNATURES_VERSION.into(),
+            sev: SevSignature {
+                id_block: vec![],
+                id_auth: vec![],
+            },
+            sgx: vec![1; 1808],
+        };
+        let bundle =
+            SignatureBundle::from_json(&serde_json::to_string(&signatures).unwrap()).unwrap();
+        assert!(external.binaries(sgx, Some(&bundle)).is_err());
+        assert!(builtin.binaries(sgx, Some(&bundle)).is_ok());
+
+        // External binaries must be x86-64 executables.
+        let text = binary(b"#!/bin/sh\n");
+        let invalid = Cli::try_parse_from(["enarx", "--shim", text.path().to_str().unwrap()])
+            .unwrap()
+            .backend;
+        assert!(invalid.shim(sgx).is_err());
+        let invalid = Cli::try_parse_from(["enarx", "--exec", text.path().to_str().unwrap()])
+            .unwrap()
+            .backend;
+        assert!(invalid.exec(sgx).is_err());
+    }
+}
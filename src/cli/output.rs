@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Command result and error output.
+//!
+//! See `docs/Output.md` for the JSON schemas of all command results and errors.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+static FORMAT: OnceCell<OutputFormat> = OnceCell::new();
+
+/// Represents the output format of command results and errors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Convert a str to an OutputFormat. This is how Clap parses CLI args.
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!("unknown output format {:?}", s)),
+        }
+    }
+}
+
+/// Sets the output format for the rest of the process lifetime.
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+/// Returns the output format selected on the command line.
+pub fn format() -> OutputFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Returns `true` if JSON output was requested.
+pub fn is_json() -> bool {
+    format() == OutputFormat::Json
+}
+
+/// Prints a command result to stdout.
+///
+/// In JSON mode, `value` is serialized, otherwise `text` is called to produce
+/// the human-readable representation.
+pub fn print<T: Serialize + ?Sized>(
+    value: &T,
+    text: impl FnOnce(&T) -> anyhow::Result<String>,
+) -> anyhow::Result<()> {
+    let out = match format() {
+        OutputFormat::Json => serde_json::to_string_pretty(value)?,
+        OutputFormat::Text => text(value)?,
+    };
+    if !out.is_empty() {
+        println!("{out}");
+    }
+    Ok(())
+}
+
+/// Prints a command result to stdout, which is already JSON in text mode as well.
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<()> {
+    print(value, |value| Ok(serde_json::to_string_pretty(value)?))
+}
+
+/// Prints a command result, which has no human-readable representation.
+///
+/// Nothing is printed in text mode.
+pub fn print_json_only<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<()> {
+    print(value, |_| Ok(String::new()))
+}
+
+/// Prints a human-readable status message.
+///
+/// Status messages go to stdout in text mode and to stderr in JSON mode,
+/// so that stdout only ever contains the JSON-encoded command result.
+pub fn status(mesg: impl Display) {
+    match format() {
+        OutputFormat::Json => eprintln!("{mesg}"),
+        OutputFormat::Text => println!("{mesg}"),
+    }
+}
+
+/// Prints a warning to stderr.
+///
+/// Unlike status messages, warnings never go to stdout, where they would mix with
+/// the output of a Keep launched by the command.
+pub fn warning(mesg: impl Display) {
+    eprintln!("Warning: {mesg}");
+}
+
+#[derive(Serialize)]
+struct ErrorOutput {
+    error: ErrorBody,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    message: String,
+    causes: Vec<String>,
+}
+
+/// Prints a command error to stderr in JSON format.
+pub fn print_error(err: &anyhow::Error) {
+    let out = ErrorOutput {
+        error: ErrorBody {
+            message: err.to_string(),
+            causes: err.chain().skip(1).map(ToString::to_string).collect(),
+        },
+    };
+    match serde_json::to_string_pretty(&out) {
+        Ok(out) => eprintln!("{out}"),
+        Err(_) => eprintln!("Error: {err:?}"),
+    }
+}
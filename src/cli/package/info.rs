@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::drawbridge::{client, TagSpec};
+use crate::cli::output;
+use crate::drawbridge::{client, get_yank, TagSpec};
 
 use std::ffi::OsString;
 use std::process::ExitCode;
@@ -8,7 +9,18 @@ use std::process::ExitCode;
 use anyhow::Context;
 use camino::Utf8PathBuf;
 use clap::Args;
+use drawbridge_client::types::TagEntry;
+use enarx_exec_wasmtime::Yank;
 use oauth2::url::Url;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct PackageInfo {
+    #[serde(flatten)]
+    entry: TagEntry,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    yank: Option<Yank>,
+}
 
 /// Retrieve information about a published package.
 #[derive(Args, Debug)]
@@ -34,10 +46,12 @@ impl Options {
             self.credential_helper,
         )?;
         let tag = cl.tag(&self.spec.ctx);
-        let tag_entry = tag
+        let entry = tag
             .get()
             .context("Failed to retrieve package information")?;
-        println!("{}", serde_json::to_string_pretty(&tag_entry)?);
+        let yank = get_yank(&cl, &self.spec.ctx).context("Failed to retrieve yank status")?;
+        let info = PackageInfo { entry, yank };
+        output::print_json(&info)?;
 
         Ok(ExitCode::SUCCESS)
     }
@@ -16,7 +16,6 @@ pub enum Subcommands {
     #[clap(hide = true)]
     Fetch(fetch::Options),
     Publish(publish::Options),
-    #[clap(hide = true)]
     Yank(yank::Options),
 }
 
@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::config::check::{check_file, Report};
+use crate::cli::output;
 use crate::drawbridge::{client, TagSpec};
 
 use std::ffi::OsString;
@@ -10,6 +12,12 @@ use anyhow::{bail, Context};
 use camino::Utf8PathBuf;
 use clap::Args;
 use oauth2::url::Url;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Publish {
+    created: bool,
+}
 
 /// Publish a new package.
 #[derive(Args, Debug)]
@@ -53,12 +61,24 @@ impl Options {
                     bail!("Publishing nested directories is not supported")
                 }
             }
+
+            let config = self.path.join("Enarx.toml");
+            if config.is_file() {
+                let reports = check_file(&config)?;
+                for report in &reports {
+                    eprintln!("{}", report.display(&config));
+                }
+                if reports.iter().any(Report::is_error) {
+                    bail!("Invalid configuration: {config}");
+                }
+            }
         }
 
         let tag = cl.tag(&self.spec.ctx);
-        let (_tag_created, _tree_created) = tag
+        let (created, _tree_created) = tag
             .create_from_path_unsigned(self.path)
             .context("Failed to create tag and upload tree")?;
+        output::print_json_only(&Publish { created })?;
 
         Ok(ExitCode::SUCCESS)
     }
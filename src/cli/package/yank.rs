@@ -1,15 +1,53 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::output;
+use crate::drawbridge::{client, set_yank, TagSpec};
+
+use std::ffi::OsString;
 use std::process::ExitCode;
 
+use camino::Utf8PathBuf;
 use clap::Args;
+use enarx_exec_wasmtime::Yank;
+use oauth2::url::Url;
 
 /// Yank a published package.
+///
+/// Yanked packages can only be deployed with `--allow-yanked`.
 #[derive(Args, Debug)]
-pub struct Options {}
+pub struct Options {
+    #[clap(long, env = "ENARX_CA_BUNDLE")]
+    ca_bundle: Option<Utf8PathBuf>,
+    #[clap(long, default_value = "https://auth.profian.com/")]
+    oidc_domain: Url,
+    #[clap(long, env = "ENARX_INSECURE_AUTH_TOKEN")]
+    insecure_auth_token: Option<String>,
+    #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
+    credential_helper: Option<OsString>,
+    /// Reason for yanking the package.
+    #[clap(long, conflicts_with = "undo")]
+    reason: Option<String>,
+    /// Undo a previous yank.
+    #[clap(long)]
+    undo: bool,
+    spec: TagSpec,
+}
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        unimplemented!()
+        let cl = client(
+            self.spec.host,
+            self.oidc_domain,
+            self.insecure_auth_token,
+            self.ca_bundle,
+            self.credential_helper,
+        )?;
+        let yank = Yank {
+            yanked: !self.undo,
+            reason: self.reason,
+        };
+        set_yank(&cl, &self.spec.ctx, &yank)?;
+        output::print_json_only(&yank)?;
+        Ok(ExitCode::SUCCESS)
     }
 }
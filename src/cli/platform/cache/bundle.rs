@@ -0,0 +1,374 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Signed bundles of platform collateral for air-gapped hosts
+
+use crate::attestation::pki;
+use crate::attestation::sgx::verify_tcb_info;
+use crate::backend::sev::snp::vcek::CERT_CHAIN;
+use crate::backend::sgx::{TcbPackage, FMSPC_FILE, TCB_FILE};
+use crate::caching::CrlList;
+use crate::cli::platform::sgx;
+
+use std::slice;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use der::asn1::{GeneralizedTime, OctetString};
+use der::{Decode, Encode, Sequence};
+use p384::ecdsa::signature::{Signer as _, Verifier as _};
+use p384::ecdsa::{Signature, SigningKey, VerifyingKey};
+use x509_cert::Certificate;
+
+/// Cache directory of AMD collateral
+pub const AMD: &str = "amd-sev";
+/// Cache directory of Intel collateral
+pub const INTEL: &str = "intel-sgx";
+
+/// A cache file in a collateral bundle
+#[derive(Sequence)]
+pub struct File {
+    /// Cache directory to install the file into, [`AMD`] or [`INTEL`]
+    pub dir: String,
+    pub name: String,
+    pub contents: OctetString,
+}
+
+impl File {
+    pub fn new(dir: &str, name: impl Into<String>, contents: Vec<u8>) -> Result<Self> {
+        Ok(Self {
+            dir: dir.into(),
+            name: name.into(),
+            contents: OctetString::new(contents)?,
+        })
+    }
+
+    /// Checks that the file belongs into the cache and, for CRLs, that they are current at `now`.
+    fn check(&self, now: SystemTime) -> Result<()> {
+        let (dir, name) = (self.dir.as_str(), self.name.as_str());
+        let known = match (dir, name) {
+            (AMD | INTEL, "crls.der") | (AMD, CERT_CHAIN) | (INTEL, TCB_FILE | FMSPC_FILE) => true,
+            (AMD, name) => {
+                name.starts_with("vcek-")
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            }
+            _ => false,
+        };
+        ensure!(known, "unexpected file `{dir}/{name}` in bundle");
+
+        if name == "crls.der" {
+            let crls = CrlList::from_der(self.contents.as_bytes())
+                .with_context(|| format!("Failed to decode `{dir}/{name}` in bundle"))?;
+            if let Some(next_update) = crls.next_update() {
+                ensure!(
+                    next_update.to_system_time() >= now,
+                    "the CRLs in `{dir}/{name}` of the bundle expired on {next_update}"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The signed contents of a collateral bundle
+#[derive(Sequence)]
+pub struct Contents {
+    pub created: GeneralizedTime,
+    pub files: Vec<File>,
+    /// Issuer chains of the Intel CRLs, which are only used to verify them and not installed
+    pub intel_crl_chains: Vec<Vec<Certificate>>,
+}
+
+impl Contents {
+    /// Returns the file `name` of the cache directory `dir`, if the bundle contains it.
+    fn file(&self, dir: &str, name: &str) -> Option<&File> {
+        self.files.iter().find(|f| f.dir == dir && f.name == name)
+    }
+
+    /// Returns the CRLs of the cache directory `dir`, if the bundle contains them.
+    fn crls(&self, dir: &str) -> Result<Option<CrlList>> {
+        self.file(dir, "crls.der")
+            .map(|file| {
+                CrlList::from_der(file.contents.as_bytes())
+                    .with_context(|| format!("Failed to decode `{dir}/crls.der` in bundle"))
+            })
+            .transpose()
+    }
+
+    /// Verifies the collateral of the bundle at `now` against the trusted AMD `arks` and
+    /// Intel SGX `intel_roots`, like the cache commands verify fetched collateral.
+    ///
+    /// The CRLs must be issued by the trusted roots, the VCEKs by the bundled AMD certificate
+    /// chain rooted in one of `arks`, and the TCB info must be signed, current and apply to
+    /// the bundled FMSPC.
+    pub fn verify(
+        &self,
+        arks: &[Certificate],
+        intel_roots: &[Certificate],
+        now: SystemTime,
+    ) -> Result<()> {
+        // The AMD CRLs are issued by the ARKs themselves.
+        if let Some(crls) = self.crls(AMD)? {
+            pki::verify_crls(&crls, arks, now)
+                .with_context(|| format!("Invalid `{AMD}/crls.der` in bundle"))?;
+        }
+        if let Some(crls) = self.crls(INTEL)? {
+            let roots = sgx::anchors(intel_roots, &self.intel_crl_chains);
+            let issuers = sgx::crl::verified_issuers(&roots, &self.intel_crl_chains, now)?;
+            pki::verify_crls(&crls, &issuers, now)
+                .with_context(|| format!("Invalid `{INTEL}/crls.der` in bundle"))?;
+        }
+
+        let chain = match self.file(AMD, CERT_CHAIN) {
+            Some(chain) => {
+                let chain = Vec::<Certificate>::from_der(chain.contents.as_bytes())
+                    .context("Failed to decode the AMD certificate chain in bundle")?;
+                pki::verify_chain_to(&chain, arks, now)
+                    .with_context(|| format!("Invalid `{AMD}/{CERT_CHAIN}` in bundle"))?;
+                Some(chain)
+            }
+            None => None,
+        };
+        let vceks = self
+            .files
+            .iter()
+            .filter(|f| f.dir == AMD && f.name.starts_with("vcek-"));
+        for vcek in vceks {
+            let chain = chain
+                .as_ref()
+                .ok_or_else(|| anyhow!("the bundle has VCEKs, but no AMD certificate chain"))?;
+            let cert = Certificate::from_der(vcek.contents.as_bytes())
+                .with_context(|| format!("Failed to decode `{AMD}/{}` in bundle", vcek.name))?;
+            let vcek_chain: Vec<_> = [cert].into_iter().chain(chain.iter().cloned()).collect();
+            pki::verify_chain_to(&vcek_chain, arks, now).with_context(|| {
+                format!(
+                    "`{AMD}/{}` in bundle is not issued by the bundled AMD certificate chain",
+                    vcek.name
+                )
+            })?;
+        }
+
+        if let Some(tcb) = self.file(INTEL, TCB_FILE) {
+            let fmspc = self
+                .file(INTEL, FMSPC_FILE)
+                .ok_or_else(|| anyhow!("the bundle has TCB info, but no FMSPC"))?;
+            let fmspc = std::str::from_utf8(fmspc.contents.as_bytes())
+                .ok()
+                .and_then(|fmspc| hex::decode(fmspc.trim()).ok())
+                .ok_or_else(|| anyhow!("invalid FMSPC in bundle"))?;
+            let tcb = TcbPackage::from_der(tcb.contents.as_bytes())
+                .context("Failed to decode the TCB info in bundle")?;
+            let roots = sgx::anchors(intel_roots, slice::from_ref(&tcb.crts));
+            pki::verify_chain_to(&tcb.crts, &roots, now)
+                .with_context(|| format!("Invalid `{INTEL}/{TCB_FILE}` signing chain in bundle"))?;
+            verify_tcb_info(&tcb, tcb.crts.last(), &fmspc, now)
+                .with_context(|| format!("Invalid `{INTEL}/{TCB_FILE}` in bundle"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Collateral bundle, as written by `enarx platform cache export`
+#[derive(Sequence)]
+pub struct Bundle {
+    /// DER encoded [`Contents`]
+    pub contents: OctetString,
+    /// DER encoded ECDSA P-384 SHA-384 signature of `contents`
+    pub signature: OctetString,
+}
+
+impl Bundle {
+    /// Signs `contents` with `key`.
+    pub fn sign(contents: &Contents, key: &SigningKey) -> Result<Self> {
+        let contents = contents.to_der()?;
+        let signature: Signature = key.sign(&contents);
+        Ok(Self {
+            contents: OctetString::new(contents)?,
+            signature: OctetString::new(signature.to_der().as_bytes())?,
+        })
+    }
+
+    /// Verifies that the bundle is signed with `key` and its collateral is valid at `now`,
+    /// see [`Contents::verify`], and returns its contents.
+    pub fn open(
+        &self,
+        key: &VerifyingKey,
+        arks: &[Certificate],
+        intel_roots: &[Certificate],
+        now: SystemTime,
+    ) -> Result<Contents> {
+        let signature = Signature::from_der(self.signature.as_bytes())
+            .context("Failed to decode bundle signature")?;
+        key.verify(self.contents.as_bytes(), &signature)
+            .map_err(|_| anyhow!("bundle is not signed with the trusted key"))?;
+
+        let contents =
+            Contents::from_der(self.contents.as_bytes()).context("Failed to decode bundle")?;
+        for file in &contents.files {
+            file.check(now)?;
+        }
+        contents.verify(arks, intel_roots, now)?;
+        Ok(contents)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::attestation::roots;
+
+    use std::time::Duration;
+
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/attestation");
+
+    fn now() -> SystemTime {
+        // The fixtures were generated on 2026-10-18.
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_281_600)
+    }
+
+    fn read(name: &str) -> Vec<Certificate> {
+        pki::read_certificates(&std::fs::read(format!("{DATA}/{name}")).unwrap()).unwrap()
+    }
+
+    /// The ARK of the test fixtures
+    fn test_ark() -> Vec<Certificate> {
+        read("snp-chain.pem").split_off(1)
+    }
+
+    fn bundle(key: &SigningKey, name: &str) -> Bundle {
+        let crls = std::fs::read(format!("{DATA}/snp.crls")).unwrap();
+        let contents = Contents {
+            created: GeneralizedTime::from_system_time(now()).unwrap(),
+            files: vec![File::new(AMD, name, crls).unwrap()],
+            intel_crl_chains: vec![],
+        };
+        Bundle::sign(&contents, key).unwrap()
+    }
+
+    #[test]
+    fn open() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let arks = test_ark();
+        let bundle = bundle(&key, "crls.der");
+        let contents = bundle.open(key.verifying_key(), &arks, &[], now()).unwrap();
+        assert_eq!(contents.files.len(), 1);
+        assert_eq!(contents.files[0].name, "crls.der");
+
+        let encoded = bundle.to_der().unwrap();
+        assert!(Bundle::from_der(&encoded)
+            .unwrap()
+            .open(key.verifying_key(), &arks, &[], now())
+            .is_ok());
+
+        // A bundle signed with another key is rejected.
+        let other = SigningKey::random(&mut rand::thread_rng());
+        assert!(bundle
+            .open(other.verifying_key(), &arks, &[], now())
+            .is_err());
+
+        // A correctly signed bundle, whose CRLs are not issued by a trusted ARK, is rejected.
+        let pinned = roots::amd_arks().unwrap();
+        let err = bundle
+            .open(key.verifying_key(), &pinned, &[], now())
+            .map(|_| ())
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("untrusted"));
+
+        // A tampered bundle is rejected.
+        let mut tampered = bundle.contents.as_bytes().to_vec();
+        let n = tampered.len();
+        tampered[n - 10] ^= 1;
+        let tampered = Bundle {
+            contents: OctetString::new(tampered).unwrap(),
+            signature: bundle.signature.clone(),
+        };
+        assert!(tampered
+            .open(key.verifying_key(), &arks, &[], now())
+            .is_err());
+
+        // A bundle with expired CRLs is rejected.
+        let later = SystemTime::UNIX_EPOCH + Duration::from_secs(4_800_000_000);
+        assert!(bundle.open(key.verifying_key(), &arks, &[], later).is_err());
+
+        // Files outside of the cache are rejected.
+        let bundle = self::bundle(&key, "../crls.der");
+        assert!(bundle.open(key.verifying_key(), &arks, &[], now()).is_err());
+    }
+
+    #[test]
+    fn collateral() {
+        const SNP: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/backend/sev/snp/testdata");
+        let vcek = std::fs::read(format!("{SNP}/vcek.der")).unwrap();
+        let amd = std::fs::read(format!("{SNP}/chain.pem")).unwrap();
+        let amd = pki::read_certificates(&amd).unwrap();
+        let pinned = roots::amd_arks().unwrap();
+        let contents = |chain: Option<&Vec<Certificate>>| {
+            let mut files = vec![File::new(AMD, "vcek-test", vcek.clone()).unwrap()];
+            if let Some(chain) = chain {
+                files.push(File::new(AMD, CERT_CHAIN, chain.to_der().unwrap()).unwrap());
+            }
+            Contents {
+                created: GeneralizedTime::from_system_time(now()).unwrap(),
+                files,
+                intel_crl_chains: vec![],
+            }
+        };
+        contents(Some(&amd)).verify(&pinned, &[], now()).unwrap();
+
+        // The AMD certificate chain must be rooted in a trusted ARK.
+        assert!(contents(Some(&amd))
+            .verify(&test_ark(), &[], now())
+            .is_err());
+
+        // VCEKs must be issued by the bundled AMD certificate chain.
+        assert!(contents(None).verify(&pinned, &[], now()).is_err());
+        let other = read("snp-chain.pem");
+        assert!(contents(Some(&other))
+            .verify(&test_ark(), &[], now())
+            .is_err());
+
+        let tcb = std::fs::read(format!("{DATA}/sgx-tcb.der")).unwrap();
+        let intel = read("sgx-root.pem");
+        let contents = |fmspc: &str| Contents {
+            created: GeneralizedTime::from_system_time(now()).unwrap(),
+            files: vec![
+                File::new(INTEL, TCB_FILE, tcb.clone()).unwrap(),
+                File::new(INTEL, FMSPC_FILE, fmspc.into()).unwrap(),
+            ],
+            intel_crl_chains: vec![],
+        };
+        contents("00906ED50000\n")
+            .verify(&[], &intel, now())
+            .unwrap();
+
+        // The TCB info must be signed by a trusted root CA.
+        assert!(contents("00906ED50000")
+            .verify(&[], &test_ark(), now())
+            .is_err());
+
+        // The TCB info must apply to the bundled FMSPC and be current.
+        assert!(contents("000000000000").verify(&[], &intel, now()).is_err());
+        let later = SystemTime::UNIX_EPOCH + Duration::from_secs(4_800_000_000);
+        assert!(contents("00906ED50000").verify(&[], &intel, later).is_err());
+    }
+
+    #[test]
+    fn intel_crls() {
+        let crls = std::fs::read(format!("{DATA}/sgx.crls")).unwrap();
+        let intel = read("sgx-root.pem");
+        let contents = |chains: Vec<Vec<Certificate>>| Contents {
+            created: GeneralizedTime::from_system_time(now()).unwrap(),
+            files: vec![File::new(INTEL, "crls.der", crls.clone()).unwrap()],
+            intel_crl_chains: chains,
+        };
+        contents(vec![read("sgx-crl-chain.pem")])
+            .verify(&[], &intel, now())
+            .unwrap();
+
+        // The CRLs must be issued by the bundled issuer chains, rooted in a trusted root CA.
+        assert!(contents(vec![]).verify(&[], &intel, now()).is_err());
+        assert!(contents(vec![read("sgx-crl-chain.pem")])
+            .verify(&[], &test_ark(), now())
+            .is_err());
+    }
+}
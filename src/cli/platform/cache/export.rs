@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::bundle::{Bundle, Contents, File, AMD, INTEL};
+use crate::backend::sev::snp::firmware::{Identifier, TcbVersion};
+use crate::backend::sev::snp::vcek::{fetch_cert_chain, CERT_CHAIN};
+use crate::caching::fetch_certificates;
+use crate::cli::platform::{sgx, snp, TrustOptions};
+use crate::endpoints::Endpoints;
+
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context};
+use camino::Utf8PathBuf;
+use clap::Args;
+use der::asn1::GeneralizedTime;
+use der::Encode;
+use p384::ecdsa::SigningKey;
+use p384::pkcs8::DecodePrivateKey;
+
+/// A VCEK, identified by the chip ID and the reported TCB version of its platform
+#[derive(Clone, Debug)]
+pub struct Vcek {
+    id: Identifier,
+    tcb: TcbVersion,
+}
+
+impl Vcek {
+    pub fn new(id: Identifier, tcb: TcbVersion) -> Self {
+        Self { id, tcb }
+    }
+}
+
+impl std::fmt::Display for Vcek {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:x}:{}:{}:{}:{}",
+            self.id, self.tcb.bootloader, self.tcb.tee, self.tcb.snp, self.tcb.microcode
+        )
+    }
+}
+
+fn parse_vcek(s: &str) -> anyhow::Result<Vcek> {
+    let mut parts = s.split(':');
+    let id = hex::decode(parts.next().unwrap_or_default()).context("invalid chip ID")?;
+    let mut spl = || -> anyhow::Result<u8> {
+        parts
+            .next()
+            .ok_or_else(|| anyhow!("expected <CHIP_ID>:<BL>:<TEE>:<SNP>:<UCODE>"))?
+            .parse()
+            .context("invalid security patch level")
+    };
+    let tcb = TcbVersion {
+        bootloader: spl()?,
+        tee: spl()?,
+        snp: spl()?,
+        microcode: spl()?,
+        ..Default::default()
+    };
+    if parts.next().is_some() {
+        bail!("expected <CHIP_ID>:<BL>:<TEE>:<SNP>:<UCODE>");
+    }
+    Ok(Vcek::new(id.into(), tcb))
+}
+
+/// Export platform collateral into a signed bundle for hosts without internet access
+///
+/// The collateral is fetched and verified like by the cache commands of
+/// `enarx platform snp` and `enarx platform sgx`. Install the bundle with
+/// `enarx platform cache import`.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// P-384 private key in PEM form to sign the bundle with, e.g. created by `enarx key sev create`
+    #[clap(long)]
+    key: Utf8PathBuf,
+
+    /// File path to write the bundle to
+    #[clap(long)]
+    out: Utf8PathBuf,
+
+    /// Include the AMD CRLs. Implied by `--snp-vcek`.
+    #[clap(long)]
+    snp: bool,
+
+    /// Include the Intel CRLs. Implied by `--sgx-fmspc`.
+    #[clap(long)]
+    sgx: bool,
+
+    /// Include the VCEK of a platform, as printed by `enarx platform snp vcek --id` on it
+    #[clap(long, value_name = "CHIP_ID:BL:TEE:SNP:UCODE", value_parser = parse_vcek)]
+    snp_vcek: Vec<Vcek>,
+
+    /// Include the TCB info of the SGX platform model with this FMSPC
+    #[clap(long)]
+    sgx_fmspc: Option<String>,
+
+    #[clap(flatten)]
+    snp_chain: snp::crl::ChainOptions,
+
+    #[clap(flatten)]
+    trust: TrustOptions,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<ExitCode> {
+        let pem = std::fs::read_to_string(&self.key)
+            .with_context(|| format!("Failed to read {}", self.key))?;
+        let key = SigningKey::from_pkcs8_pem(&pem).context("Invalid P-384 private key")?;
+
+        let endpoints = Endpoints::get()?;
+        let now = SystemTime::now();
+        let (snp, sgx) = match (
+            self.snp || !self.snp_vcek.is_empty(),
+            self.sgx || self.sgx_fmspc.is_some(),
+        ) {
+            (false, false) => (true, true),
+            selected => selected,
+        };
+
+        let mut files = vec![];
+        let mut intel_crl_chains = vec![];
+        if snp {
+            let arks = self.trust.amd_arks()?;
+            let crls = self.snp_chain.fetch(&endpoints.amd, &arks, now)?;
+            files.push(File::new(AMD, "crls.der", crls.to_der()?)?);
+            for vcek in &self.snp_vcek {
+                let url = vcek.id.vcek_url(endpoints.amd.base(), &vcek.tcb);
+                let cert = fetch_certificates(&url)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow!("No VCEK found at `{url}`"))?;
+                files.push(File::new(
+                    AMD,
+                    vcek.id.vcek_cache_name(&vcek.tcb),
+                    cert.to_der()?,
+                )?);
+            }
+            if !self.snp_vcek.is_empty() {
+                let chain = fetch_cert_chain(&arks)?;
+                files.push(File::new(AMD, CERT_CHAIN, chain.to_der()?)?);
+            }
+        }
+        if sgx {
+            let roots = self.trust.intel_roots()?;
+            let (crls, chains) = sgx::crl::fetch(&endpoints.intel, &roots, now)?;
+            intel_crl_chains = chains;
+            files.push(File::new(INTEL, "crls.der", crls.to_der()?)?);
+            if let Some(ref fmspc) = self.sgx_fmspc {
+                let tcb = sgx::tcb::fetch_tcb(&endpoints.intel, fmspc, &roots, now)?;
+                files.push(File::new(INTEL, "tcb.der", tcb)?);
+                files.push(File::new(INTEL, "fmspc.txt", fmspc.trim().into())?);
+            }
+        }
+
+        let contents = Contents {
+            created: GeneralizedTime::from_system_time(now)?,
+            files,
+            intel_crl_chains,
+        };
+        contents.verify(&self.trust.amd_arks()?, &self.trust.intel_roots()?, now)?;
+        let bundle = Bundle::sign(&contents, &key)?.to_der()?;
+        std::fs::write(&self.out, bundle)
+            .with_context(|| format!("Failed to write bundle to {}", self.out))?;
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn vcek() {
+        let vcek = parse_vcek("8ba826b2:0:0:3:29").unwrap();
+        assert_eq!(vcek.id, Identifier::from(vec![0x8b, 0xa8, 0x26, 0xb2]));
+        assert_eq!(vcek.tcb.snp, 3);
+        assert_eq!(vcek.tcb.microcode, 29);
+        assert_eq!(vcek.to_string(), "8ba826b2:0:0:3:29");
+
+        assert!(parse_vcek("8ba826b2:0:0:3").is_err());
+        assert!(parse_vcek("8ba826b2:0:0:3:29:1").is_err());
+        assert!(parse_vcek("chip:0:0:3:29").is_err());
+        assert!(parse_vcek("8ba826b2:0:0:3:256").is_err());
+    }
+}
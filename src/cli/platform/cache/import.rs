@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::bundle::{Bundle, AMD};
+use crate::caching::Cache;
+use crate::cli::platform::TrustOptions;
+
+use std::fs;
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use camino::Utf8PathBuf;
+use clap::Args;
+use der::Decode;
+use p384::ecdsa::VerifyingKey;
+use p384::pkcs8::DecodePublicKey;
+
+/// Import a bundle of platform collateral, as exported by `enarx platform cache export`
+///
+/// The bundle is verified before any of its files are installed into the `amd-sev`
+/// and `intel-sgx` cache directories. Its collateral must be rooted in the pinned or
+/// trusted roots, like fetched collateral. Bundles with expired CRLs are rejected.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// P-384 public key in PEM form the bundle must be signed with
+    #[clap(long)]
+    trust: Utf8PathBuf,
+
+    /// Bundle to import
+    bundle: Utf8PathBuf,
+
+    #[clap(flatten)]
+    roots: TrustOptions,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<ExitCode> {
+        let pem = fs::read_to_string(&self.trust)
+            .with_context(|| format!("Failed to read {}", self.trust))?;
+        let key = VerifyingKey::from_public_key_pem(&pem).context("Invalid P-384 public key")?;
+
+        let bundle =
+            fs::read(&self.bundle).with_context(|| format!("Failed to read {}", self.bundle))?;
+        let contents = Bundle::from_der(&bundle)
+            .context("Failed to decode bundle")?
+            .open(
+                &key,
+                &self.roots.amd_arks()?,
+                &self.roots.intel_roots()?,
+                SystemTime::now(),
+            )
+            .with_context(|| format!("Refusing to import {}", self.bundle))?;
+
+        for file in contents.files {
+            let cache = match file.dir.as_str() {
+                AMD => Cache::AMD,
+                _ => Cache::INTEL,
+            };
+            cache
+                .store(&file.name, file.contents.as_bytes())
+                .with_context(|| format!("Failed to install `{}/{}`", file.dir, file.name))?;
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
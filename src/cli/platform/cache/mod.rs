@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod bundle;
+pub(crate) mod export;
+mod import;
+pub(crate) mod status;
+
+use crate::backend::probe::x86_64::Vendor;
+use crate::cli::platform::{sgx, snp};
+
+use std::process::ExitCode;
+
+use clap::Subcommand;
+
+/// Refreshes the cached collateral of the platforms of `vendor`.
+///
+/// The VCEK of an AMD SEV-SNP host is only downloaded if it is not cached yet.
+pub(crate) fn refresh(vendor: Vendor) -> anyhow::Result<()> {
+    match vendor {
+        Vendor::Amd => {
+            snp::crl::CrlCache::default().execute()?;
+            snp::update::Options::default().execute()?;
+        }
+        Vendor::Intel => {
+            sgx::crl::CrlCache::default().execute()?;
+            sgx::tcb::TcbCache::default().execute()?;
+        }
+    }
+    Ok(())
+}
+
+/// Commands for the cache of platform collateral
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    Export(export::Options),
+    Import(import::Options),
+    Status(status::Options),
+}
+
+impl Subcommands {
+    pub fn dispatch(self) -> anyhow::Result<ExitCode> {
+        match self {
+            Self::Export(cmd) => cmd.execute(),
+            Self::Import(cmd) => cmd.execute(),
+            Self::Status(cmd) => cmd.execute(),
+        }
+    }
+}
@@ -0,0 +1,354 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::backend::probe::x86_64::Vendor;
+use crate::backend::sev::snp::vcek::{platform_id, CERT_CHAIN};
+use crate::backend::sgx::{TcbPackage, FMSPC_FILE, TCB_FILE};
+use crate::caching::{Cache, CrlList, CRLS};
+use crate::cli::output;
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::{Args, ValueEnum};
+use der::Decode;
+use serde::{Deserialize, Serialize};
+use x509_cert::Certificate;
+
+/// State of a file in the collateral cache
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum State {
+    /// The file is present and current
+    Fresh,
+    /// The file is past its next update
+    Stale,
+    /// The file is not in the cache
+    Missing,
+    /// The file cannot be read or decoded
+    Invalid,
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Fresh => "fresh",
+            Self::Stale => "stale",
+            Self::Missing => "missing",
+            Self::Invalid => "invalid",
+        })
+    }
+}
+
+/// A file in the collateral cache
+#[derive(Debug, Serialize)]
+pub struct Item {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub state: State,
+    /// Seconds since the file was written
+    pub age: Option<u64>,
+    /// Time by which the file must be refreshed
+    pub next_update: Option<DateTime<Utc>>,
+    /// Why the file is missing or invalid
+    pub error: Option<String>,
+}
+
+impl Item {
+    /// Inspects the cache file at `path`, whose next update is decoded by `next_update`.
+    fn inspect(
+        name: &'static str,
+        path: PathBuf,
+        now: SystemTime,
+        next_update: impl FnOnce(&[u8]) -> Result<Option<SystemTime>>,
+    ) -> Self {
+        let mut item = Self {
+            name,
+            path,
+            state: State::Missing,
+            age: None,
+            next_update: None,
+            error: None,
+        };
+
+        let contents = match fs::read(&item.path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                if e.kind() != ErrorKind::NotFound {
+                    item.state = State::Invalid;
+                }
+                item.error = Some(e.to_string());
+                return item;
+            }
+        };
+        item.age = fs::metadata(&item.path)
+            .and_then(|md| md.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .map(|age| age.as_secs());
+
+        match next_update(&contents) {
+            Ok(next_update) => {
+                item.state = match next_update {
+                    Some(next_update) if next_update <= now => State::Stale,
+                    _ => State::Fresh,
+                };
+                item.next_update = next_update.map(Into::into);
+            }
+            Err(e) => {
+                item.state = State::Invalid;
+                item.error = Some(format!("{e:#}"));
+            }
+        }
+        item
+    }
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.name, self.path.display(), self.state)?;
+        if let Some(age) = self.age {
+            let (days, hours, minutes) = (age / 86400, age % 86400 / 3600, age % 3600 / 60);
+            match days {
+                0 => write!(f, ", written {hours}h {minutes}m ago")?,
+                _ => write!(f, ", written {days}d {hours}h ago")?,
+            }
+        }
+        if let Some(next_update) = self.next_update {
+            let next_update = next_update.to_rfc3339_opts(SecondsFormat::Secs, true);
+            write!(f, ", next update {next_update}")?;
+        }
+        if let Some(ref error) = self.error {
+            write!(f, " ({error})")?;
+        }
+        Ok(())
+    }
+}
+
+fn crls_next_update(crls: &[u8]) -> Result<Option<SystemTime>> {
+    let crls = CrlList::from_der(crls)?;
+    Ok(crls
+        .next_update()
+        .map(|next_update| next_update.to_system_time()))
+}
+
+fn chain_next_update(chain: &[u8]) -> Result<Option<SystemTime>> {
+    let chain = Vec::<Certificate>::from_der(chain)?;
+    Ok(chain
+        .iter()
+        .map(|cert| cert.tbs_certificate.validity.not_after.to_system_time())
+        .min())
+}
+
+fn tcb_next_update(tcb: &[u8]) -> Result<Option<SystemTime>> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Report {
+        tcb_info: Info,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Info {
+        next_update: DateTime<Utc>,
+    }
+
+    let tcb = TcbPackage::from_der(tcb)?;
+    let report: Report = serde_json::from_slice(tcb.report)?;
+    Ok(Some(report.tcb_info.next_update.into()))
+}
+
+/// Returns the state of the cached AMD SEV-SNP collateral of this host at `now`.
+pub fn snp(now: SystemTime) -> Result<Vec<Item>> {
+    let cache = Cache::AMD;
+    let vcek = match platform_id() {
+        Ok((id, tcb)) => Item::inspect("VCEK", cache.path(&id.vcek_cache_name(&tcb)), now, |_| {
+            Ok(None)
+        }),
+        Err(e) => Item {
+            name: "VCEK",
+            path: cache.dirs().swap_remove(0),
+            state: State::Missing,
+            age: None,
+            next_update: None,
+            error: Some(format!("{e:#}")),
+        },
+    };
+    let crls = Item::inspect("AMD CRLs", cache.path(CRLS), now, crls_next_update);
+    let chain = Item::inspect(
+        "AMD certificate chain",
+        cache.path(CERT_CHAIN),
+        now,
+        chain_next_update,
+    );
+    Ok(vec![crls, chain, vcek])
+}
+
+/// Returns the state of the cached Intel SGX collateral of this host at `now`.
+pub fn sgx(now: SystemTime) -> Result<Vec<Item>> {
+    let cache = Cache::INTEL;
+    Ok(vec![
+        Item::inspect("Intel CRLs", cache.path(CRLS), now, crls_next_update),
+        Item::inspect("Intel TCB info", cache.path(TCB_FILE), now, tcb_next_update),
+        Item::inspect("Intel FMSPC", cache.path(FMSPC_FILE), now, |_| Ok(None)),
+    ])
+}
+
+/// How to handle stale or missing collateral before launching a Keep
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Check {
+    /// Launch the Keep without checking the collateral
+    Ignore,
+    /// Print a warning
+    Warn,
+    /// Refresh the collateral, and print a warning if that fails
+    Refresh,
+}
+
+/// Checks the cached collateral, which Keeps of `backend` embed in their evidence,
+/// before a Keep is launched.
+pub fn check(backend: &str, check: Check) -> Result<()> {
+    let vendor = match backend {
+        _ if check == Check::Ignore => return Ok(()),
+        "sev" => Vendor::Amd,
+        "sgx" if !cfg!(feature = "disable-sgx-attestation") => Vendor::Intel,
+        _ => return Ok(()),
+    };
+    let inspect = |now| match vendor {
+        Vendor::Amd => snp(now),
+        Vendor::Intel => sgx(now),
+    };
+
+    let mut items = inspect(SystemTime::now())?;
+    if check == Check::Refresh && items.iter().any(|item| item.state != State::Fresh) {
+        match super::refresh(vendor) {
+            Ok(()) => items = inspect(SystemTime::now())?,
+            Err(e) => output::warning(format_args!(
+                "failed to refresh the platform collateral: {e:#}"
+            )),
+        }
+    }
+
+    let outdated: Vec<_> = items
+        .iter()
+        .filter(|item| item.state != State::Fresh)
+        .collect();
+    if !outdated.is_empty() {
+        for item in outdated {
+            output::warning(item);
+        }
+        output::warning(
+            "the attestation of the Keep may be rejected, run `enarx update-cache` to refresh the platform collateral",
+        );
+    }
+    Ok(())
+}
+
+/// Show the age and next update of the cached platform collateral
+///
+/// Exits with a failure if any collateral is stale, missing or invalid.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Show the AMD SEV-SNP collateral. Defaults to the collateral of the CPU vendor of this host.
+    #[clap(long)]
+    snp: bool,
+
+    /// Show the Intel SGX collateral. Defaults to the collateral of the CPU vendor of this host.
+    #[clap(long)]
+    sgx: bool,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<ExitCode> {
+        let (snp, sgx) = match (self.snp, self.sgx) {
+            (false, false) => match Vendor::get() {
+                Ok(Vendor::Amd) => (true, false),
+                Ok(Vendor::Intel) => (false, true),
+                Err(_) => (true, true),
+            },
+            selected => selected,
+        };
+
+        let now = SystemTime::now();
+        let mut items = vec![];
+        if snp {
+            items.extend(self::snp(now)?);
+        }
+        if sgx {
+            items.extend(self::sgx(now)?);
+        }
+
+        output::print(&items, |items| {
+            Ok(items
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n"))
+        })?;
+
+        if items.iter().all(|item| item.state == State::Fresh) {
+            Ok(ExitCode::SUCCESS)
+        } else {
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/attestation");
+
+    fn now() -> SystemTime {
+        // The fixtures were generated on 2026-10-18.
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_281_600)
+    }
+
+    #[test]
+    fn inspect() {
+        let later = SystemTime::UNIX_EPOCH + Duration::from_secs(4_800_000_000);
+
+        let crls = PathBuf::from(format!("{DATA}/snp.crls"));
+        let item = Item::inspect("AMD CRLs", crls.clone(), now(), crls_next_update);
+        assert_eq!(item.state, State::Fresh);
+        assert!(item.next_update.is_some());
+        let item = Item::inspect("AMD CRLs", crls, later, crls_next_update);
+        assert_eq!(item.state, State::Stale);
+
+        let tcb = PathBuf::from(format!("{DATA}/sgx-tcb.der"));
+        let item = Item::inspect("Intel TCB info", tcb.clone(), now(), tcb_next_update);
+        assert_eq!(item.state, State::Fresh);
+        assert_eq!(
+            item.next_update.unwrap().to_rfc3339(),
+            "2119-12-31T00:00:00+00:00"
+        );
+        let item = Item::inspect("Intel TCB info", tcb, later, tcb_next_update);
+        assert_eq!(item.state, State::Stale);
+
+        // The CRLs are not a TCB package.
+        let item = Item::inspect(
+            "Intel TCB info",
+            format!("{DATA}/snp.crls").into(),
+            now(),
+            tcb_next_update,
+        );
+        assert_eq!(item.state, State::Invalid);
+
+        let item = Item::inspect(
+            "Intel CRLs",
+            format!("{DATA}/missing.der").into(),
+            now(),
+            crls_next_update,
+        );
+        assert_eq!(item.state, State::Missing);
+        assert!(item.age.is_none());
+    }
+}
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::info::get_icon;
+use crate::backend::{Backend, Datum, BACKENDS};
+use crate::cli::output;
+
+use std::ops::Deref;
+use std::process::ExitCode;
+
+use anyhow::anyhow;
+use clap::Args;
+use serde::Serialize;
+
+/// Check that this system can run Keeps and explain how to fix any problems
+///
+/// In addition to the tests of `enarx platform info`, live checks exercise the
+/// platform like the launch of a Keep, e.g. by creating a virtual machine, querying
+/// the SEV firmware or requesting an attestation key from the SGX quote provider.
+/// Exits with a failure if any check fails.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Only check this backend. Defaults to all backends supported by this system.
+    #[clap(long, env = "ENARX_BACKEND")]
+    backend: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    backend: &'static str,
+    pass: bool,
+    checks: Vec<Datum>,
+}
+
+impl Report {
+    fn new(backend: &dyn Backend) -> Self {
+        let mut checks = backend.data();
+        checks.extend(backend.config());
+        checks.extend(backend.checks());
+        Self {
+            backend: backend.name(),
+            pass: checks.iter().all(|datum| datum.pass),
+            checks,
+        }
+    }
+}
+
+/// Returns whether the system passed the checks of `reports`, which requires at least one backend.
+fn passed(reports: &[Report]) -> bool {
+    !reports.is_empty() && reports.iter().all(|report| report.pass)
+}
+
+impl Options {
+    /// Returns the backends to check.
+    fn backends(&self) -> anyhow::Result<Vec<&'static dyn Backend>> {
+        Ok(match self.backend {
+            Some(ref name) => vec![BACKENDS
+                .deref()
+                .iter()
+                .find(|b| b.name() == name)
+                .map(|b| &**b)
+                .ok_or_else(|| anyhow!("Keep backend identifier {name:?} is unknown."))?],
+            None => BACKENDS
+                .deref()
+                .iter()
+                .filter(|b| b.name() != "nil" && b.have())
+                .map(|b| &**b)
+                .collect(),
+        })
+    }
+
+    pub fn execute(self) -> anyhow::Result<ExitCode> {
+        let reports: Vec<_> = self.backends()?.into_iter().map(Report::new).collect();
+        let pass = passed(&reports);
+
+        if output::is_json() {
+            let reports: Vec<_> = reports
+                .into_iter()
+                .map(|mut report| {
+                    for datum in &mut report.checks {
+                        datum.name = datum.name.trim().into();
+                    }
+                    report
+                })
+                .collect();
+            output::print_json(&reports)?;
+        } else {
+            let is_atty = atty::is(atty::Stream::Stdout);
+            for report in &reports {
+                println!(
+                    "{} Backend: {}",
+                    get_icon(is_atty, report.pass),
+                    report.backend
+                );
+                for datum in &report.checks {
+                    print!("  {} {}", get_icon(is_atty, datum.pass), datum.name);
+                    if let Some(ref info) = datum.info {
+                        print!(": {info}");
+                    }
+                    println!();
+                    if let (false, Some(mesg)) = (datum.pass, &datum.mesg) {
+                        println!("\n    {mesg}\n");
+                    }
+                }
+            }
+            if reports.is_empty() {
+                println!(
+                    "No supported backend found. Please check your machine with `$ enarx platform info`."
+                );
+            }
+        }
+
+        if pass {
+            Ok(ExitCode::SUCCESS)
+        } else {
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::{nil, Keep, Signatures};
+
+    use std::sync::Arc;
+
+    struct Failing;
+
+    impl Backend for Failing {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn shim(&self) -> &'static [u8] {
+            &[]
+        }
+
+        fn data(&self) -> Vec<Datum> {
+            vec![Datum {
+                name: "Driver".into(),
+                pass: true,
+                info: Some("/dev/failing".into()),
+                mesg: None,
+            }]
+        }
+
+        fn config(&self) -> Vec<Datum> {
+            vec![]
+        }
+
+        fn checks(&self) -> Vec<Datum> {
+            vec![Datum {
+                name: "Launch".into(),
+                pass: false,
+                info: None,
+                mesg: Some("Fix the launch".into()),
+            }]
+        }
+
+        fn keep(&self, _: &[u8], _: &[u8], _: Option<Signatures>) -> anyhow::Result<Arc<dyn Keep>> {
+            anyhow::bail!("This is a failing backend")
+        }
+
+        fn hash(&self, _: &[u8], _: &[u8]) -> anyhow::Result<Vec<u8>> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn test_doctor() {
+        let options = Options {
+            backend: Some("nil".into()),
+        };
+        let backends = options.backends().unwrap();
+        assert_eq!(backends.len(), 1);
+        let report = Report::new(backends[0]);
+        assert_eq!(report.backend, "nil");
+        assert!(report.pass);
+        assert!(report.checks.is_empty());
+
+        // The nil backend has no checks to fail.
+        let code = options.execute().unwrap();
+        assert_eq!(format!("{code:?}"), format!("{:?}", ExitCode::SUCCESS));
+
+        assert!(Options {
+            backend: Some("unknown".into()),
+        }
+        .execute()
+        .is_err());
+    }
+
+    #[test]
+    fn failing_check() {
+        let report = Report::new(&Failing);
+        let checks: Vec<_> = report
+            .checks
+            .iter()
+            .map(|datum| (datum.name.as_str(), datum.pass))
+            .collect();
+        assert_eq!(checks, [("Driver", true), ("Launch", false)]);
+        assert!(!report.pass);
+        let nil = Report::new(&nil::Backend::default());
+        assert!(nil.pass);
+        assert!(!passed(&[report, nil]));
+
+        // Without any backend to run Keeps, the system fails the checks.
+        assert!(!passed(&[]));
+    }
+}
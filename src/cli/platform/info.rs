@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::backend::{Backend, BACKENDS};
+use crate::cli::output::{self, OutputFormat};
 use clap::Args;
 #[cfg(unix)]
 use libc::{uname, utsname};
@@ -11,12 +12,17 @@ use std::process::ExitCode;
 
 #[derive(Args, Debug)]
 pub struct Options {
-    #[clap(short, long)]
-    /// Emit JSON rather than human-readable output
+    /// Deprecated alias of `--output json`
+    #[clap(short, long, hide = true)]
     json: bool,
 }
 
 impl Options {
+    /// Returns the output format selected with the deprecated `--json` flag, if any.
+    pub(super) fn output_format(&self) -> Option<OutputFormat> {
+        self.json.then_some(OutputFormat::Json)
+    }
+
     pub fn execute(self) -> anyhow::Result<ExitCode> {
         let backends = BACKENDS.deref();
 
@@ -63,10 +69,9 @@ impl Options {
             backends,
         };
         if self.json {
-            println!("{}", serde_json::to_string_pretty(&info)?);
-        } else {
-            println!("{info}");
+            output::warning("`platform info --json` is deprecated, use `--output json` instead");
         }
+        output::print(&info, |info| Ok(info.to_string()))?;
 
         Ok(ExitCode::SUCCESS)
     }
@@ -79,23 +84,24 @@ struct Info<'a> {
     backends: &'a Vec<Box<dyn Backend>>,
 }
 
+/// Returns the icon for a passed or failed datum, colored on a terminal.
+pub(super) fn get_icon(is_atty: bool, pass: bool) -> String {
+    use colorful::*;
+
+    match is_atty {
+        true => match pass {
+            true => "✔".green().to_string(),
+            false => "✗".red().to_string(),
+        },
+        false => match pass {
+            true => "✔".into(),
+            false => "✗".into(),
+        },
+    }
+}
+
 impl fmt::Display for Info<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        use colorful::*;
-
-        fn get_icon(is_atty: bool, pass: bool) -> String {
-            match is_atty {
-                true => match pass {
-                    true => "✔".green().to_string(),
-                    false => "✗".red().to_string(),
-                },
-                false => match pass {
-                    true => "✔".into(),
-                    false => "✗".into(),
-                },
-            }
-        }
-
         let is_atty = atty::is(atty::Stream::Stdout);
         let backends = self.backends;
 
@@ -1,18 +1,35 @@
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(enarx_with_shim)]
+pub(crate) mod cache;
+mod doctor;
 mod info;
 #[cfg(enarx_with_shim)]
 pub(crate) mod sgx;
 #[cfg(enarx_with_shim)]
 pub(crate) mod snp;
 
+#[cfg(enarx_with_shim)]
+use crate::attestation::{pki, roots};
+use crate::cli::output::OutputFormat;
+
 use std::process::ExitCode;
 
+#[cfg(enarx_with_shim)]
+use anyhow::Context;
+#[cfg(enarx_with_shim)]
+use camino::Utf8PathBuf;
 use clap::Subcommand;
+#[cfg(enarx_with_shim)]
+use x509_cert::Certificate;
 
 /// Commands for configuration of trusted execution environments.
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
+    #[cfg(enarx_with_shim)]
+    #[clap(subcommand)]
+    Cache(cache::Subcommands),
+    Doctor(doctor::Options),
     Info(info::Options),
     #[cfg(enarx_with_shim)]
     #[clap(subcommand)]
@@ -25,6 +42,9 @@ pub enum Subcommands {
 impl Subcommands {
     pub fn dispatch(self) -> anyhow::Result<ExitCode> {
         match self {
+            #[cfg(enarx_with_shim)]
+            Self::Cache(subcmd) => subcmd.dispatch(),
+            Self::Doctor(cmd) => cmd.execute(),
             Self::Info(cmd) => cmd.execute(),
             #[cfg(enarx_with_shim)]
             Self::Sgx(subcmd) => subcmd.dispatch(),
@@ -32,4 +52,53 @@ impl Subcommands {
             Self::Snp(subcmd) => subcmd.dispatch(),
         }
     }
+
+    /// Returns the output format selected by deprecated subcommand options, which
+    /// overrides `--output`.
+    pub fn output_format(&self) -> Option<OutputFormat> {
+        match self {
+            Self::Info(cmd) => cmd.output_format(),
+            _ => None,
+        }
+    }
+}
+
+/// Trust anchors of the platform collateral
+///
+/// Collateral must be rooted in the AMD ARKs pinned in Enarx, other roots are only
+/// trusted if given explicitly. No Intel SGX root CA is pinned yet, see
+/// [`sgx::anchors`] for how Intel collateral is verified without one.
+#[cfg(enarx_with_shim)]
+#[derive(clap::Args, Debug, Default)]
+pub struct TrustOptions {
+    /// Also trust this root certificate, an AMD ARK or an Intel SGX root CA,
+    /// in addition to the pinned ones. Can be given several times.
+    #[clap(long, value_name = "PATH")]
+    trust_root: Vec<Utf8PathBuf>,
+}
+
+#[cfg(enarx_with_shim)]
+impl TrustOptions {
+    fn trusted(&self) -> anyhow::Result<Vec<Certificate>> {
+        let mut certs = vec![];
+        for path in &self.trust_root {
+            let bytes = std::fs::read(path).with_context(|| format!("Failed to read {path}"))?;
+            certs.extend(pki::read_certificates(&bytes)?);
+        }
+        Ok(certs)
+    }
+
+    /// Returns the trusted AMD ARKs.
+    pub fn amd_arks(&self) -> anyhow::Result<Vec<Certificate>> {
+        let mut arks = roots::amd_arks()?;
+        arks.extend(self.trusted()?);
+        Ok(arks)
+    }
+
+    /// Returns the trusted Intel SGX root CAs.
+    pub fn intel_roots(&self) -> anyhow::Result<Vec<Certificate>> {
+        let mut roots = roots::intel_roots()?;
+        roots.extend(self.trusted()?);
+        Ok(roots)
+    }
 }
@@ -1,11 +1,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::sgx::sgx_cache_dir;
-use crate::caching::fetch_crl_list;
+use crate::attestation::pki;
+use crate::caching::{fetch_crl_list, Cache, CrlList, CRLS};
+use crate::cli::platform::TrustOptions;
+use crate::endpoints::{Endpoints, Intel};
 
-use std::fs::OpenOptions;
-use std::io::Write;
 use std::process::ExitCode;
+use std::time::SystemTime;
 
 use anyhow::Context;
 use clap::Args;
@@ -13,41 +14,118 @@ use clap::Args;
 use x509_cert::der::Decode as _; // required for Musl target
 #[allow(unused_imports)]
 use x509_cert::der::Encode as _; // required for Musl target
-
-const CERT_CRL: &str = "https://certificates.trustedservices.intel.com/IntelSGXRootCA.der";
-const PROCESSOR_CRL: &str =
-    "https://api.trustedservices.intel.com/sgx/certification/v4/pckcrl?ca=processor&encoding=der";
-const PLATFORM_CRL: &str =
-    "https://api.trustedservices.intel.com/sgx/certification/v4/pckcrl?ca=platform&encoding=der";
+use x509_cert::Certificate;
 
 /// Fetch Intel's Certificate Revocation Lists (CRLs),
-/// saving as cached files in `/var/cache/intel-sgx/` directory
+/// saving as cached files in the `intel-sgx` cache directory
+///
+/// The CRLs are verified against the Intel SGX root CA before they are cached.
 #[derive(Args, Debug, Default)]
-pub struct CrlCache {}
+pub struct CrlCache {
+    #[clap(flatten)]
+    trust: TrustOptions,
+}
 
 impl CrlCache {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        let mut dest_file = sgx_cache_dir()?;
-        let mut crl_temp = dest_file.clone();
-        dest_file.push("crls.der");
-        crl_temp.push("crls.der.tmp");
-
-        let crls = fetch_crl_list([CERT_CRL.into(), PROCESSOR_CRL.into(), PLATFORM_CRL.into()])?;
-        OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&crl_temp)
-            .context(format!(
-                "opening destination file {crl_temp:?} for saving Intel CRLs"
-            ))?
-            .write_all(&crls)
-            .context(format!("writing Intel CRLs to file {crl_temp:?}"))?;
-
-        std::fs::rename(&crl_temp, &dest_file).context(format!(
-            "Failed to move temporary CRL file {crl_temp:?} to final path {dest_file:?}"
-        ))?;
+        let roots = self.trust.intel_roots()?;
+        let (crls, _) = fetch(&Endpoints::get()?.intel, &roots, SystemTime::now())?;
+        let crls = crls.to_der().context("failed to encode CRL list")?;
+        Cache::INTEL
+            .store(CRLS, &crls)
+            .context("Failed to cache Intel CRLs")?;
 
         Ok(ExitCode::SUCCESS)
     }
 }
+
+/// Fetches the CRLs from `intel` and verifies them against the trusted Intel SGX `roots`,
+/// see [`super::anchors`] if there are none. Returns them with the chains of their issuers.
+pub(crate) fn fetch(
+    intel: &Intel,
+    roots: &[Certificate],
+    now: SystemTime,
+) -> anyhow::Result<(CrlList, Vec<Vec<Certificate>>)> {
+    let (crls, chains) = fetch_crl_list([
+        intel.root_ca_crl(),
+        intel.pck_crl("processor"),
+        intel.pck_crl("platform"),
+    ])?;
+    let roots = super::anchors(roots, &chains);
+    let issuers = verified_issuers(&roots, &chains, now)?;
+    pki::verify_crls(&crls, &issuers, now).context("Failed to verify Intel CRLs")?;
+    Ok((crls, chains))
+}
+
+/// Verifies that the CRL issuer `chains` are rooted in one of `roots` and returns their
+/// certificates together with the roots.
+pub(crate) fn verified_issuers(
+    roots: &[Certificate],
+    chains: &[Vec<Certificate>],
+    now: SystemTime,
+) -> anyhow::Result<Vec<Certificate>> {
+    let mut issuers = vec![];
+    for chain in chains {
+        pki::verify_chain_to(chain, roots, now).context("Invalid Intel CRL issuer chain")?;
+        issuers.extend(chain.iter().cloned());
+    }
+    issuers.extend(roots.iter().cloned());
+    Ok(issuers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use der::Decode;
+
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/attestation");
+
+    fn read(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{DATA}/{name}")).unwrap()
+    }
+
+    fn now() -> SystemTime {
+        // The fixtures were generated on 2026-10-18.
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_281_600)
+    }
+
+    #[test]
+    fn verify() {
+        let roots = pki::read_certificates(&read("sgx-root.pem")).unwrap();
+        let chain = pki::read_certificates(&read("sgx-crl-chain.pem")).unwrap();
+        let crls = CrlList::from_der(&read("sgx.crls")).unwrap();
+
+        let issuers = verified_issuers(&roots, std::slice::from_ref(&chain), now()).unwrap();
+        pki::verify_crls(&crls, &issuers, now()).unwrap();
+
+        // Without the issuer chain only the CRL of the root CA can be verified.
+        let issuers = verified_issuers(&roots, &[], now()).unwrap();
+        assert!(pki::verify_crls(&crls, &issuers, now()).is_err());
+
+        // An issuer chain with another root is rejected.
+        let snp = pki::read_certificates(&read("snp-chain.pem")).unwrap();
+        assert!(verified_issuers(&roots, &[snp], now()).is_err());
+
+        // A chain is only trusted if its root is, not because it is self-signed.
+        assert!(verified_issuers(&[], std::slice::from_ref(&chain), now()).is_err());
+
+        // Without a trusted root, the CRLs are verified against the root CA they were
+        // served with, but the chains must still be consistent.
+        let anchors = super::super::anchors(&[], std::slice::from_ref(&chain));
+        assert_eq!(anchors, roots);
+        let issuers = verified_issuers(&anchors, std::slice::from_ref(&chain), now()).unwrap();
+        pki::verify_crls(&crls, &issuers, now()).unwrap();
+        assert_eq!(super::super::anchors(&roots, &[]), roots);
+
+        // A tampered CRL is rejected.
+        let mut tampered = read("sgx.crls");
+        let n = tampered.len();
+        tampered[n - 10] ^= 1;
+        let tampered = CrlList::from_der(&tampered).unwrap();
+        let issuers = verified_issuers(&roots, &[chain], now()).unwrap();
+        assert!(pki::verify_crls(&tampered, &issuers, now()).is_err());
+    }
+}
@@ -4,9 +4,12 @@ pub(crate) mod crl;
 pub(crate) mod register;
 pub(crate) mod tcb;
 
+use crate::cli::output;
+
 use std::process::ExitCode;
 
 use clap::Subcommand;
+use x509_cert::Certificate;
 
 /// SGX-specific functionality
 #[derive(Subcommand, Debug)]
@@ -27,3 +30,21 @@ impl Subcommands {
         }
     }
 }
+
+/// Returns the root CAs to verify Intel collateral against, given the trusted `roots` and the
+/// certificate `chains` served with the collateral.
+///
+/// No Intel SGX root CA is pinned yet. Unless one is trusted with `--trust-root`, the
+/// collateral is only verified against the root CAs it was served with, after a warning.
+pub(crate) fn anchors(roots: &[Certificate], chains: &[Vec<Certificate>]) -> Vec<Certificate> {
+    if !roots.is_empty() {
+        return roots.to_vec();
+    }
+    output::warning(
+        "no Intel SGX root CA is pinned or trusted with `--trust-root`, the collateral is only verified against the root CA it was served with",
+    );
+    chains
+        .iter()
+        .filter_map(|chain| chain.last().cloned())
+        .collect()
+}
@@ -1,51 +1,62 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::sgx::{TcbPackage, FMSPC_PATH, TCB_PATH};
-
-use std::fs::OpenOptions;
-use std::io::{Read, Write};
-use std::path::Path;
+use crate::attestation::pki;
+use crate::attestation::sgx::verify_tcb_info;
+use crate::backend::sgx::{TcbPackage, FMSPC_FILE, TCB_FILE};
+use crate::caching::{issuer_chain, Cache};
+use crate::cli::platform::TrustOptions;
+use crate::endpoints::{Endpoints, Intel};
+
+use std::io::Read;
 use std::process::Command;
 use std::process::ExitCode;
+use std::time::SystemTime;
 
 use anyhow::{bail, Context};
 use clap::Args;
 use der::oid::ObjectIdentifier;
 use der::{Decode, Encode};
-use percent_encoding::percent_decode;
 use x509_cert::Certificate;
 
-const FMSPC_URL: &str = "https://api.trustedservices.intel.com/sgx/certification/v4/pckcert";
-const TCB_URL: &str = "https://api.trustedservices.intel.com/sgx/certification/v4/tcb";
-const PCKID_CSV_PATH: &str = "/var/cache/intel-sgx/pckid_retrieval.csv";
+const PCKID_CSV_FILE: &str = "pckid_retrieval.csv";
 
 /// Fetch the prerequisites for fetching the TCB report from Intel
 // * Run Intel's `PCKIDRetrievalTool`
 // * Parse the resulting pckid_retrieval.csv for EncryptedPPID, PCE_ID, CPUSVNPCE, ISVSVN, QE_ID
-// * Use these fields to fetch FMSPC from https://api.trustedservices.intel.com/sgx/certification/v4/pckcert
-// * Use the FMSPC to fetch the actual TCB report from https://api.trustedservices.intel.com/sgx/certification/v4/tcb
+// * Use these fields to fetch FMSPC from the `pckcert` endpoint of the Intel PCS or PCCS
+// * Use the FMSPC to fetch the actual TCB report from the `tcb` endpoint of the Intel PCS or PCCS
+// See `crate::endpoints` for configuring the endpoints.
 // SGX API documentation: https://api.portal.trustedservices.intel.com/documentation
 // SGX cert extension documentation: https://download.01.org/intel-sgx/dcap-1.1/linux/docs/Intel_SGX_PCK_Certificate_CRL_Spec-1.1.pdf
-// Save the resulting files in the `/var/cache/intel-sgx/` directory
+// Save the resulting files in the `intel-sgx` cache directory, see `crate::caching::Cache`
 #[derive(Args, Debug, Default)]
 pub struct PckCache {}
 
 impl PckCache {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        if !Path::new(PCKID_CSV_PATH).exists() {
-            Command::new("PCKIDRetrievalTool")
-                .arg("-f")
-                .arg(PCKID_CSV_PATH)
-                .spawn()
-                .expect("Could not run PCKIDRetrievalTool, is it in the $PATH? Are you root?")
-                .wait()?;
-        }
+        let pckid_csv = match Cache::INTEL.find(PCKID_CSV_FILE) {
+            Ok(path) => path,
+            Err(_) => {
+                let path = Cache::INTEL.write_dir()?.join(PCKID_CSV_FILE);
+                Command::new("PCKIDRetrievalTool")
+                    .arg("-f")
+                    .arg(&path)
+                    .spawn()
+                    .expect("Could not run PCKIDRetrievalTool, is it in the $PATH? Are you root?")
+                    .wait()?;
+                path
+            }
+        };
 
-        let report = std::fs::read_to_string(PCKID_CSV_PATH)?;
+        let report = std::fs::read_to_string(&pckid_csv)
+            .with_context(|| format!("Failed to read {pckid_csv:?}"))?;
         let report_parts: Vec<&str> = report.split(',').collect();
-        let url = format!(
-            "{FMSPC_URL}/?encrypted_ppid={}&cpusvn={}&pcesvn={}&pceid={}",
-            report_parts[0], report_parts[2], report_parts[3], report_parts[1]
+        let url = Endpoints::get()?.intel.pck_cert(
+            report_parts[0],
+            report_parts[1],
+            report_parts[2],
+            report_parts[3],
+            report_parts.get(4).map_or("", |qe_id| qe_id.trim()),
         );
 
         let (pck_cert, _) = fetch_file(&url).context("Failed to fetch PCK certificate")?;
@@ -58,16 +69,9 @@ impl PckCache {
             Certificate::from_der(&pck_cert[0]).context("Failed to DER-decode PCK certificate")?;
 
         let fmspc = decode_extension(&pck_cert)?;
-        OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(FMSPC_PATH)
-            .context(format!(
-                "opening destination file {FMSPC_PATH} for saving Intel FMSPC"
-            ))?
-            .write_all(fmspc.as_bytes())
-            .context(format!("writing Intel FMSPC to file {FMSPC_PATH}"))?;
+        Cache::INTEL
+            .store(FMSPC_FILE, fmspc.as_bytes())
+            .context("Failed to cache Intel FMSPC")?;
 
         Ok(ExitCode::SUCCESS)
     }
@@ -116,69 +120,67 @@ fn fetch_file(url: &str) -> anyhow::Result<(Vec<u8>, Option<String>)> {
 }
 
 /// Fetch the TCB report from Intel
+///
+/// The TCB report is verified against the Intel SGX root CA before it is cached.
 #[derive(Args, Debug, Default)]
-pub struct TcbCache {}
+pub struct TcbCache {
+    #[clap(flatten)]
+    trust: TrustOptions,
+}
 
 impl TcbCache {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        if !Path::new(FMSPC_PATH).exists() {
-            bail!("Cannot read {FMSPC_PATH}, please run `enarx platform sgx cache-pck`")
-        }
-
-        let fmspc =
-            std::fs::read_to_string(FMSPC_PATH).context(format!("Failed to read {FMSPC_PATH}"))?;
-
-        let url = format!("{TCB_URL}?fmspc={fmspc}");
-        let (tcb_report, tcb_cert_chain) =
-            fetch_file(&url).context("Failed to fetch Intel TCB report")?;
-
-        if tcb_cert_chain.is_none() {
-            bail!("Did not receive Intel TCB signing certificates in HTTP response header");
-        }
-
-        let tcb_cert_chain = tcb_cert_chain.unwrap();
-        // Decode certs from HTTP-friendly characters
-        let tcb_cert_chain = String::from(percent_decode(tcb_cert_chain.as_bytes()).decode_utf8()?);
-
-        let mut tcb_cert_vec = vec![];
-        let mut certs_cursor = std::io::Cursor::new(tcb_cert_chain);
-        let tcb_certs = rustls_pemfile::certs(&mut certs_cursor)
-            .context("Failed to PEM-decode TCB certificate chain")?;
-
-        for cert in tcb_certs.iter() {
-            tcb_cert_vec.push(
-                Certificate::from_der(cert)
-                    .context("Failed to DER-encode TCB certificate chain")?,
-            );
-        }
-
-        let tcb_package = TcbPackage {
-            crts: tcb_cert_vec,
-            report: &tcb_report,
+        let fmspc_file = match Cache::INTEL.find(FMSPC_FILE) {
+            Ok(path) => path,
+            Err(_) => {
+                bail!("Cannot find the Intel FMSPC, please run `enarx platform sgx cache-pck`")
+            }
         };
 
-        let tcb_package = tcb_package
-            .to_der()
-            .context("Failed to encode TCB certs and report to DER")?;
-
-        let mut tcb_temp = String::from(TCB_PATH);
-        tcb_temp.push_str(".tmp");
-
-        OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&tcb_temp)
-            .context(format!(
-                "opening destination file {tcb_temp} for saving Intel TCB report"
-            ))?
-            .write_all(&tcb_package)
-            .context(format!("writing Intel TCB report to file {tcb_temp}"))?;
+        let fmspc = std::fs::read_to_string(&fmspc_file)
+            .with_context(|| format!("Failed to read {fmspc_file:?}"))?;
 
-        std::fs::rename(&tcb_temp, TCB_PATH).context(format!(
-            "Failed to move temporary TCB {tcb_temp} to final path {TCB_PATH}"
-        ))?;
+        let roots = self.trust.intel_roots()?;
+        let tcb_package = fetch_tcb(&Endpoints::get()?.intel, &fmspc, &roots, SystemTime::now())?;
+        Cache::INTEL
+            .store(TCB_FILE, &tcb_package)
+            .context("Failed to cache Intel TCB report")?;
 
         Ok(ExitCode::SUCCESS)
     }
 }
+
+/// Fetches the TCB report of the platform model `fmspc` from `intel`, verifies it against the
+/// trusted Intel SGX `roots`, see [`super::anchors`] if there are none, and returns it DER
+/// encoded together with its signing certificates.
+pub(crate) fn fetch_tcb(
+    intel: &Intel,
+    fmspc: &str,
+    roots: &[Certificate],
+    now: SystemTime,
+) -> anyhow::Result<Vec<u8>> {
+    let fmspc = fmspc.trim();
+    let (tcb_report, tcb_cert_chain) =
+        fetch_file(&intel.tcb(fmspc)).context("Failed to fetch Intel TCB report")?;
+
+    let tcb_cert_chain = match tcb_cert_chain {
+        Some(chain) => chain,
+        None => bail!("Did not receive Intel TCB signing certificates in HTTP response header"),
+    };
+
+    let tcb_package = TcbPackage {
+        crts: issuer_chain(&tcb_cert_chain).context("Failed to decode TCB certificate chain")?,
+        report: &tcb_report,
+    };
+
+    let fmspc = hex::decode(fmspc).context(format!("Invalid FMSPC {fmspc}"))?;
+    let roots = super::anchors(roots, std::slice::from_ref(&tcb_package.crts));
+    pki::verify_chain_to(&tcb_package.crts, &roots, now)
+        .context("Invalid Intel TCB signing certificate chain")?;
+    verify_tcb_info(&tcb_package, tcb_package.crts.last(), &fmspc, now)
+        .context("Failed to verify Intel TCB report")?;
+
+    tcb_package
+        .to_der()
+        .context("Failed to encode TCB certs and report to DER")
+}
@@ -1,50 +1,270 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::sev::snp::vcek::sev_cache_dir;
-use crate::caching::fetch_crl_list;
+use crate::attestation::{pki, roots};
+use crate::caching::{fetch_certificates, fetch_crl_list, Cache, CrlList, CRLS};
+use crate::cli::output;
+use crate::cli::platform::TrustOptions;
+use crate::endpoints::{Amd, Endpoints};
 
-use std::fs::OpenOptions;
-use std::io::Write;
 use std::process::ExitCode;
+use std::time::SystemTime;
 
 use anyhow::Context;
+use camino::Utf8PathBuf;
 use clap::Args;
 #[allow(unused_imports)]
 use x509_cert::der::Decode as _; // required for Musl target
 #[allow(unused_imports)]
 use x509_cert::der::Encode as _; // required for Musl target
+use x509_cert::Certificate;
 
-const GENOA: &str = "https://kdsintf.amd.com/vcek/v1/Genoa/crl";
-const MILAN: &str = "https://kdsintf.amd.com/vcek/v1/Milan/crl";
+const PRODUCTS: [&str; 2] = ["Genoa", "Milan"];
 
 /// Fetch AMD's Certificate Revocation Lists (CRLs),
-/// saving as cached files in `/var/cache/amd-sev/` directory
+/// saving as cached files in the `amd-sev` cache directory
+///
+/// The CRLs are verified against the AMD ASK and ARK certificates before they are cached.
+/// Processor models whose ARK is neither pinned nor trusted with `--trust-root` are skipped.
 #[derive(Args, Debug, Default)]
-pub struct CrlCache {}
+pub struct CrlCache {
+    #[clap(flatten)]
+    chain: ChainOptions,
+
+    #[clap(flatten)]
+    trust: TrustOptions,
+}
 
 impl CrlCache {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        let mut dest_file = sev_cache_dir()?;
-        let mut crl_temp = dest_file.clone();
-        dest_file.push("crls.der");
-        crl_temp.push("crls.der.tmp");
-
-        let crls = fetch_crl_list([GENOA.into(), MILAN.into()])?;
-        OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&crl_temp)
-            .context(format!(
-                "opening destination file {crl_temp:?} for saving AMD CRLs"
-            ))?
-            .write_all(&crls)
-            .context(format!("writing AMD CRLs to file {crl_temp:?}"))?;
-
-        std::fs::rename(&crl_temp, &dest_file).context(format!(
-            "Failed to move temporary CRL file {crl_temp:?} to final path {dest_file:?}"
-        ))?;
+        let arks = self.trust.amd_arks()?;
+        let crls = self
+            .chain
+            .fetch(&Endpoints::get()?.amd, &arks, SystemTime::now())?;
+        let crls = crls.to_der().context("failed to encode CRL list")?;
+        Cache::AMD
+            .store(CRLS, &crls)
+            .context("Failed to cache AMD CRLs")?;
 
         Ok(ExitCode::SUCCESS)
     }
 }
+
+/// Issuers of the AMD CRLs
+#[derive(Args, Debug, Default)]
+pub struct ChainOptions {
+    /// AMD ASK and ARK certificates, in this order, once per processor model.
+    /// Fetched from AMD if not given. The ARK must be trusted.
+    #[clap(long)]
+    snp_chain: Vec<Utf8PathBuf>,
+}
+
+impl ChainOptions {
+    /// Fetches the CRLs from the KDS at `kds` and verifies them against the trusted `arks`.
+    ///
+    /// If the chains are fetched from the KDS, processor models without a pinned ARK are
+    /// skipped, unless their ARK is one of `arks`.
+    pub(crate) fn fetch(
+        &self,
+        kds: &Amd,
+        arks: &[Certificate],
+        now: SystemTime,
+    ) -> anyhow::Result<CrlList> {
+        let mut products = vec![];
+        let mut chains = vec![];
+        if self.snp_chain.is_empty() {
+            for product in PRODUCTS {
+                let chain = fetch_certificates(&kds.cert_chain(product))?;
+                if !roots::is_amd_pinned(product) && !is_rooted_in(&chain, arks)? {
+                    output::warning(format!(
+                        "skipping the AMD CRL of {product}, whose ARK is not pinned, trust it with `--trust-root` to include it"
+                    ));
+                    continue;
+                }
+                products.push(product);
+                chains.push(chain);
+            }
+        } else {
+            for path in &self.snp_chain {
+                let chain =
+                    std::fs::read(path).with_context(|| format!("Failed to read {path}"))?;
+                chains.push(pki::read_certificates(&chain)?);
+            }
+            products.extend(PRODUCTS);
+        }
+
+        let (crls, _) = fetch_crl_list(products.iter().map(|product| kds.crl(product)))?;
+        let issuers = verified_issuers(&chains, arks, now)?;
+        pki::verify_crls(&crls, &issuers, now).context("Failed to verify AMD CRLs")?;
+        Ok(crls)
+    }
+}
+
+/// Returns whether the root certificate of `chain` is one of `arks`.
+fn is_rooted_in(chain: &[Certificate], arks: &[Certificate]) -> anyhow::Result<bool> {
+    let Some(root) = chain.last() else { return Ok(false) };
+    let root = root.to_der()?;
+    for ark in arks {
+        if ark.to_der()? == root {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Verifies that the AMD certificate `chains` are rooted in one of `arks` and returns their
+/// certificates.
+fn verified_issuers(
+    chains: &[Vec<Certificate>],
+    arks: &[Certificate],
+    now: SystemTime,
+) -> anyhow::Result<Vec<Certificate>> {
+    let mut issuers = vec![];
+    for chain in chains {
+        pki::verify_chain_to(chain, arks, now).context("Invalid AMD certificate chain")?;
+        issuers.extend(chain.iter().cloned());
+    }
+    Ok(issuers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use der::{Decode, Encode};
+
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/attestation");
+
+    fn read(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{DATA}/{name}")).unwrap()
+    }
+
+    fn now() -> SystemTime {
+        // The fixtures were generated on 2026-10-18.
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_281_600)
+    }
+
+    #[test]
+    fn verify() {
+        let chain = pki::read_certificates(&read("snp-chain.pem")).unwrap();
+        let arks = [chain[chain.len() - 1].clone()];
+        let issuers = verified_issuers(std::slice::from_ref(&chain), &arks, now()).unwrap();
+        let crls = CrlList::from_der(&read("snp.crls")).unwrap();
+        pki::verify_crls(&crls, &issuers, now()).unwrap();
+
+        // CRLs are outdated after their next update.
+        let later = SystemTime::UNIX_EPOCH + Duration::from_secs(4_800_000_000);
+        assert!(pki::verify_crls(&crls, &issuers, later).is_err());
+
+        // CRLs of other issuers are rejected.
+        let crls = CrlList::from_der(&read("sgx.crls")).unwrap();
+        assert!(pki::verify_crls(&crls, &issuers, now()).is_err());
+
+        // A tampered CRL is rejected.
+        let mut crls = read("snp.crls");
+        let n = crls.len();
+        crls[n - 10] ^= 1;
+        let crls = CrlList::from_der(&crls).unwrap();
+        assert!(pki::verify_crls(&crls, &issuers, now()).is_err());
+
+        // A chain whose ASK is not signed by the ARK is rejected.
+        let mut tampered = chain.clone();
+        tampered.swap(0, 1);
+        assert!(verified_issuers(&[tampered], &arks, now()).is_err());
+
+        // A self-signed ARK is not trusted unless it is pinned or given explicitly.
+        let pinned = TrustOptions::default().amd_arks().unwrap();
+        assert!(verified_issuers(std::slice::from_ref(&chain), &pinned, now()).is_err());
+    }
+
+    /// Serves `files` by path over HTTP until `requests` requests were answered.
+    fn serve(files: Vec<(String, Vec<u8>)>, requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(&stream).lines().map(Result::unwrap);
+                let request = lines.next().unwrap();
+                // Read the remaining request headers before responding.
+                lines.find(String::is_empty);
+                let path = request.split(' ').nth(1).unwrap();
+                let response = match files.iter().find(|(p, _)| p == path) {
+                    Some((_, body)) => {
+                        let header = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        );
+                        [header.as_bytes(), body].concat()
+                    }
+                    None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec(),
+                };
+                stream.write_all(&response).unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn mirror() {
+        let crls = CrlList::from_der(&read("snp.crls")).unwrap();
+        let (_, crl) = crls.entries().next().unwrap();
+        let crl = crl.to_der().unwrap();
+        let mut files = vec![];
+        for product in PRODUCTS {
+            files.push((format!("/vcek/v1/{product}/crl"), crl.clone()));
+            files.push((
+                format!("/vcek/v1/{product}/cert_chain"),
+                read("snp-chain.pem"),
+            ));
+        }
+        let kds = Amd {
+            kds: serve(files, 8),
+        };
+
+        // The ARK served by the mirror is rejected, only the pinned ARKs are trusted.
+        let pinned = TrustOptions::default().amd_arks().unwrap();
+        let err = ChainOptions::default()
+            .fetch(&kds, &pinned, now())
+            .map(|_| ())
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("is not trusted"));
+
+        // The mirror only has to serve collateral rooted in an ARK trusted explicitly.
+        let chain = pki::read_certificates(&read("snp-chain.pem")).unwrap();
+        let arks = [chain[chain.len() - 1].clone()];
+        let crls = ChainOptions::default().fetch(&kds, &arks, now()).unwrap();
+        let urls: Vec<_> = crls.entries().map(|(url, _)| url.to_string()).collect();
+        assert_eq!(urls, PRODUCTS.map(|product| kds.crl(product)));
+    }
+
+    #[test]
+    fn unpinned_product() {
+        let crls = CrlList::from_der(&read("snp.crls")).unwrap();
+        let (_, crl) = crls.entries().next().unwrap();
+        let kds = Amd {
+            kds: serve(
+                vec![
+                    // The Genoa ARK is not pinned and not trusted explicitly.
+                    (
+                        "/vcek/v1/Genoa/cert_chain".into(),
+                        read("sgx-crl-chain.pem"),
+                    ),
+                    ("/vcek/v1/Milan/cert_chain".into(), read("snp-chain.pem")),
+                    ("/vcek/v1/Milan/crl".into(), crl.to_der().unwrap()),
+                ],
+                3,
+            ),
+        };
+
+        let chain = pki::read_certificates(&read("snp-chain.pem")).unwrap();
+        let arks = [chain[chain.len() - 1].clone()];
+        let crls = ChainOptions::default().fetch(&kds, &arks, now()).unwrap();
+        let urls: Vec<_> = crls.entries().map(|(url, _)| url.to_string()).collect();
+        assert_eq!(urls, [kds.crl("Milan")]);
+    }
+}
@@ -1,19 +1,26 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::sev::snp::vcek::vcek_write;
+use crate::backend::sev::snp::vcek::{cert_chain_write, vcek_write};
+use crate::cli::output;
 
 use std::process::ExitCode;
 
 use clap::Args;
 
 /// Download the current VCEK certificate for this platform
-/// to a cache file in the `/var/cache/amd-sev/` directory
+/// to a cache file in the `amd-sev` cache directory
+///
+/// The AMD certificate chain of the VCEK is cached as well, if it can be fetched.
 #[derive(Args, Debug, Default)]
 pub struct Options {}
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
         // try to write to the system cache
-        vcek_write().map(|()| ExitCode::SUCCESS)
+        vcek_write()?;
+        if let Err(e) = cert_chain_write() {
+            output::warning(format_args!("not caching the AMD certificate chain: {e:#}"));
+        }
+        Ok(ExitCode::SUCCESS)
     }
 }
@@ -1,11 +1,30 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::sev::snp::vcek::{get_vcek_reader, get_vcek_reader_with_path, sev_cache_dir};
+use crate::backend::sev::snp::vcek::{get_vcek_reader, get_vcek_reader_with_path, platform_id};
+use crate::cli::output;
+use crate::cli::platform::cache::export::Vcek as VcekId;
 
-use std::io::{self, ErrorKind};
+use std::io::{self, ErrorKind, Read};
+use std::path::PathBuf;
 use std::process::ExitCode;
 
 use clap::Args;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct VcekFile {
+    path: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Vcek {
+    pem: String,
+}
+
+#[derive(Serialize)]
+struct Id {
+    id: String,
+}
 
 /// Print the VCEK certificate for this platform to stdout in PEM format
 #[derive(Args, Debug)]
@@ -13,31 +32,51 @@ pub struct Options {
     /// Print the location of the VCEK certificate file
     #[clap(long)]
     file: bool,
+
+    /// Print the chip ID and reported TCB of this platform, which identify its VCEK,
+    /// for `enarx platform cache export --snp-vcek`
+    #[clap(long, conflicts_with = "file")]
+    id: bool,
 }
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        if self.file {
-            match get_vcek_reader_with_path(sev_cache_dir()?) {
-                Ok((path, _)) => {
-                    println!("{path:?}");
-                    Ok(ExitCode::SUCCESS)
-                }
+        if self.id {
+            let (id, tcb) = platform_id()?;
+            let id = VcekId::new(id, tcb).to_string();
+            output::print(&Id { id }, |Id { id }| Ok(id.clone()))?;
+            Ok(ExitCode::SUCCESS)
+        } else if self.file {
+            let path = match get_vcek_reader_with_path() {
+                Ok((path, _)) => Some(path),
                 Err(e) => {
                     if matches!(
                         e.downcast_ref::<io::Error>().map(io::Error::kind),
                         Some(ErrorKind::NotFound)
                     ) {
-                        eprintln!("No cache file found.");
-                        Ok(ExitCode::SUCCESS)
+                        None
                     } else {
-                        Err(e)
+                        return Err(e);
                     }
                 }
-            }
+            };
+            output::print(&VcekFile { path }, |VcekFile { path }| match path {
+                Some(path) => Ok(format!("{path:?}")),
+                None => {
+                    eprintln!("No cache file found.");
+                    Ok(String::new())
+                }
+            })?;
+            Ok(ExitCode::SUCCESS)
         } else {
             let mut reader = get_vcek_reader()?;
-            io::copy(&mut reader, &mut io::stdout())?;
+            if output::is_json() {
+                let mut pem = String::new();
+                reader.read_to_string(&mut pem)?;
+                output::print_json(&Vcek { pem })?;
+            } else {
+                io::copy(&mut reader, &mut io::stdout())?;
+            }
             Ok(ExitCode::SUCCESS)
         }
     }
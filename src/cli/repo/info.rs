@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::output;
 use crate::drawbridge::{client, RepoSpec};
 
 use std::ffi::OsString;
@@ -9,6 +10,7 @@ use anyhow::Context;
 use camino::Utf8PathBuf;
 use clap::Args;
 use drawbridge_client::types::{RepositoryConfig, TagName};
+use enarx_exec_wasmtime::without_yank_markers;
 use oauth2::url::Url;
 use serde::Serialize;
 
@@ -45,9 +47,9 @@ impl Options {
         let config = repo
             .get()
             .context("Failed to retrieve repository information")?;
-        let tags = repo.tags().context("Failed to retrieve repository tags")?;
+        let tags = without_yank_markers(repo.tags().context("Failed to retrieve repository tags")?);
         let info = RepoInfo { config, tags };
-        println!("{}", serde_json::to_string_pretty(&info)?);
+        output::print_json(&info)?;
         Ok(ExitCode::SUCCESS)
     }
 }
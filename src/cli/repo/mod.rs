@@ -15,9 +15,7 @@ use clap::Subcommand;
 pub enum Subcommands {
     Info(info::Options),
     Register(register::Options),
-    #[clap(hide = true)]
     Search(search::Options),
-    #[clap(hide = true)]
     Yank(yank::Options),
     #[clap(subcommand, hide = true)]
     Token(token::Subcommands),
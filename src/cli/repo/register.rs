@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::output;
 use crate::drawbridge::{client, RepoSpec};
 
 use std::ffi::OsString;
@@ -41,6 +42,7 @@ impl Options {
         };
         repo.create(&repo_config)
             .context("Failed to register repository")?;
+        output::print_json_only(&repo_config)?;
         Ok(ExitCode::SUCCESS)
     }
 }
@@ -1,15 +1,297 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::output;
+use crate::drawbridge::{agent, get_token, UserSpec};
+
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::io::Read;
 use std::process::ExitCode;
 
+use anyhow::{bail, Context};
+use camino::Utf8PathBuf;
 use clap::Args;
+use drawbridge_client::types::{RepositoryConfig, RepositoryContext, TagName};
+use enarx_exec_wasmtime::without_yank_markers;
+use oauth2::url::Url;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Maximum size of a repository configuration or tag list in bytes
+const MAX_LIST_SIZE: u64 = 1_000_000;
 
-/// Search for repositories that match a given query.
+#[derive(Debug, Serialize)]
+struct RepoMatch {
+    name: String,
+    config: RepositoryConfig,
+    tags: Vec<TagName>,
+}
+
+/// Search repositories of a user for a name and tag.
+///
+/// Drawbridge cannot list the repositories of a user, so the repositories to
+/// search are given by name. Repositories that do not exist do not match.
 #[derive(Args, Debug)]
-pub struct Options {}
+pub struct Options {
+    #[clap(long, env = "ENARX_CA_BUNDLE")]
+    ca_bundle: Option<Utf8PathBuf>,
+    #[clap(long, default_value = "https://auth.profian.com/")]
+    oidc_domain: Url,
+    #[clap(long, env = "ENARX_INSECURE_AUTH_TOKEN")]
+    insecure_auth_token: Option<String>,
+    #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
+    credential_helper: Option<OsString>,
+    /// Only match repositories whose name contains this substring.
+    #[clap(long)]
+    name: Option<String>,
+    /// Only match repositories containing this tag.
+    #[clap(long)]
+    tag: Option<String>,
+    /// User whose repositories to search.
+    spec: UserSpec,
+    /// Names of the repositories to search.
+    #[clap(required = true)]
+    repos: Vec<String>,
+}
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        unimplemented!()
+        let token = get_token(
+            &self.spec.host,
+            &self.oidc_domain,
+            self.insecure_auth_token.as_ref(),
+            self.credential_helper.as_ref(),
+        )?;
+        let base = format!("https://{}/", self.spec.host);
+        let host = Host {
+            agent: agent(self.ca_bundle.as_ref())?,
+            base: base
+                .parse()
+                .with_context(|| format!("Failed to parse URL: {base}"))?,
+            token: token.trim().to_string(),
+        };
+
+        let matches = self.search(&host)?;
+        output::print(&matches[..], format_table)?;
+        Ok(ExitCode::SUCCESS)
+    }
+
+    /// Fetches the configuration and tags of the repositories on `host` and filters them.
+    fn search(&self, host: &Host) -> anyhow::Result<Vec<RepoMatch>> {
+        let user = self.spec.ctx.name.to_string();
+        let mut names = self.repos.clone();
+        names.sort();
+        names.dedup();
+
+        let mut matches = vec![];
+        for name in names {
+            if let Some(ref filter) = self.name {
+                if !name.contains(filter.as_str()) {
+                    continue;
+                }
+            }
+            RepositoryContext::try_from((user.as_str(), name.as_str()))
+                .with_context(|| format!("Invalid repository name: {name}"))?;
+            let path = format!("{user}/{name}");
+
+            let config = match host.get(&path)? {
+                Some(config) => config,
+                None => continue,
+            };
+            let tags: Vec<TagName> = host
+                .get(&format!("{path}/_tag"))?
+                .with_context(|| format!("Failed to retrieve repository tags: {name}"))?;
+            let tags = without_yank_markers(tags);
+            if let Some(ref tag) = self.tag {
+                if !tags.iter().any(|t| t.to_string() == *tag) {
+                    continue;
+                }
+            }
+            matches.push(RepoMatch { name, config, tags });
+        }
+        Ok(matches)
+    }
+}
+
+/// A package host serving the Drawbridge API
+struct Host {
+    agent: ureq::Agent,
+    base: Url,
+    token: String,
+}
+
+impl Host {
+    /// Fetches the JSON document at `path`, or `None` if it does not exist.
+    fn get<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<Option<T>> {
+        let url = self
+            .base
+            .join(path)
+            .with_context(|| format!("Failed to parse URL: {path}"))?;
+        let response = match self
+            .agent
+            .get(url.as_str())
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(ureq::Error::Status(code @ (401 | 403), _)) => {
+                bail!("Not authorized to read `{url}` (status code {code})")
+            }
+            Err(e) => return Err(e).context("GET request failed"),
+        };
+        serde_json::from_reader(response.into_reader().take(MAX_LIST_SIZE))
+            .map(Some)
+            .with_context(|| format!("Failed to decode the response of `{url}`"))
+    }
+}
+
+fn format_table(matches: &[RepoMatch]) -> anyhow::Result<String> {
+    let width = matches
+        .iter()
+        .map(|m| m.name.len())
+        .chain(["REPOSITORY".len()])
+        .max()
+        .unwrap_or_default();
+    let mut out = format!("{:width$}  {:6}  TAGS", "REPOSITORY", "PUBLIC");
+    for m in matches {
+        let tags = m
+            .tags
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(out, "\n{:width$}  {:6}  {tags}", m.name, m.config.public)?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves `files` by path over HTTP, answering other paths with `404`, until
+    /// `requests` requests were answered.
+    fn serve(files: Vec<(&'static str, u16, &'static str)>, requests: usize) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(&stream).lines().map(Result::unwrap);
+                let request = lines.next().unwrap();
+                let authorized = lines
+                    .by_ref()
+                    .take_while(|line| !line.is_empty())
+                    .any(|line| line.eq_ignore_ascii_case("authorization: Bearer test-token"));
+                let path = request.split(' ').nth(1).unwrap();
+                let (status, body) = match files.iter().find(|(p, _, _)| *p == path) {
+                    _ if !authorized => (401, ""),
+                    Some((_, status, body)) => (*status, *body),
+                    None => (404, ""),
+                };
+                let response = format!(
+                    "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        url.parse().unwrap()
+    }
+
+    fn options(name: Option<&str>, tag: Option<&str>, repos: &[&str]) -> Options {
+        Options {
+            ca_bundle: None,
+            oidc_domain: "https://auth.profian.com/".parse().unwrap(),
+            insecure_auth_token: None,
+            credential_helper: None,
+            name: name.map(Into::into),
+            tag: tag.map(Into::into),
+            spec: "localhost/testuser".parse().unwrap(),
+            repos: repos.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn host(base: Url, token: &str) -> Host {
+        Host {
+            agent: ureq::Agent::new(),
+            base,
+            token: token.into(),
+        }
+    }
+
+    fn repos() -> Vec<(&'static str, u16, &'static str)> {
+        vec![
+            ("/testuser/pubrepo", 200, r#"{"public":true}"#),
+            (
+                "/testuser/pubrepo/_tag",
+                200,
+                r#"["0.1.0","0.1.0+yank.0","0.2.0"]"#,
+            ),
+            ("/testuser/privrepo", 200, r#"{"public":false}"#),
+            ("/testuser/privrepo/_tag", 200, r#"["0.1.0"]"#),
+            ("/testuser/broken", 500, "internal error"),
+        ]
+    }
+
+    #[test]
+    fn search() {
+        let base = serve(repos(), 5);
+        let matches = options(None, None, &["privrepo", "pubrepo", "missing"])
+            .search(&host(base, "test-token"))
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&matches).unwrap(),
+            serde_json::json!([
+                { "name": "privrepo", "config": { "public": false }, "tags": ["0.1.0"] },
+                { "name": "pubrepo", "config": { "public": true }, "tags": ["0.1.0", "0.2.0"] },
+            ])
+        );
+        assert_eq!(
+            format_table(&matches).unwrap(),
+            "REPOSITORY  PUBLIC  TAGS\n\
+             privrepo    false   0.1.0\n\
+             pubrepo     true    0.1.0, 0.2.0"
+        );
+    }
+
+    #[test]
+    fn filters() {
+        let base = serve(repos(), 6);
+        let host = host(base, "test-token");
+
+        // The name filter skips repositories without fetching them.
+        let matches = options(Some("pub"), None, &["privrepo", "pubrepo"])
+            .search(&host)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].name, "pubrepo");
+
+        let matches = options(None, Some("0.2.0"), &["privrepo", "pubrepo"])
+            .search(&host)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].name, "pubrepo");
+    }
+
+    #[test]
+    fn errors() {
+        let base = serve(repos(), 2);
+
+        // Server errors fail the search, rather than not matching.
+        let err = options(None, None, &["broken"])
+            .search(&host(base.clone(), "test-token"))
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("status code 500"));
+
+        let err = options(None, None, &["pubrepo"])
+            .search(&host(base, "bad-token"))
+            .unwrap_err();
+        assert!(err.to_string().contains("Not authorized"));
     }
 }
@@ -1,15 +1,74 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::output;
+use crate::drawbridge::{client, set_yank, RepoSpec};
+
+use std::ffi::OsString;
 use std::process::ExitCode;
 
+use anyhow::Context;
+use camino::Utf8PathBuf;
 use clap::Args;
+use drawbridge_client::types::{TagContext, TagName};
+use enarx_exec_wasmtime::{without_yank_markers, Yank};
+use oauth2::url::Url;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct RepoYank {
+    #[serde(flatten)]
+    yank: Yank,
+    tags: Vec<TagName>,
+}
 
 /// Yank all packages published to a repository.
+///
+/// Yanked packages can only be deployed with `--allow-yanked`.
 #[derive(Args, Debug)]
-pub struct Options {}
+pub struct Options {
+    #[clap(long, env = "ENARX_CA_BUNDLE")]
+    ca_bundle: Option<Utf8PathBuf>,
+    #[clap(long, default_value = "https://auth.profian.com/")]
+    oidc_domain: Url,
+    #[clap(long, env = "ENARX_INSECURE_AUTH_TOKEN")]
+    insecure_auth_token: Option<String>,
+    #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
+    credential_helper: Option<OsString>,
+    /// Reason for yanking the packages.
+    #[clap(long, conflicts_with = "undo")]
+    reason: Option<String>,
+    /// Undo a previous yank of all packages.
+    #[clap(long)]
+    undo: bool,
+    spec: RepoSpec,
+}
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        unimplemented!()
+        let cl = client(
+            self.spec.host,
+            self.oidc_domain,
+            self.insecure_auth_token,
+            self.ca_bundle,
+            self.credential_helper,
+        )?;
+        let yank = Yank {
+            yanked: !self.undo,
+            reason: self.reason,
+        };
+        let tags = cl
+            .repository(&self.spec.ctx)
+            .tags()
+            .context("Failed to retrieve repository tags")?;
+        let tags = without_yank_markers(tags);
+        for name in &tags {
+            let tag = TagContext {
+                repository: self.spec.ctx.clone(),
+                name: name.clone(),
+            };
+            set_yank(&cl, &tag, &yank)?;
+        }
+        output::print_json_only(&RepoYank { yank, tags })?;
+        Ok(ExitCode::SUCCESS)
     }
 }
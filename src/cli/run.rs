@@ -1,17 +1,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::Signatures;
+use crate::backend::SignatureBundle;
 use crate::cli::BackendOptions;
-use crate::exec::{open_package, run_package, EXECS};
+use crate::exec::{open_package, run_package};
 
 use std::fmt::Debug;
+use std::fs::read_to_string;
 #[cfg(unix)]
 use std::os::unix::io::IntoRawFd;
 use std::process::ExitCode;
 
-use anyhow::anyhow;
+use anyhow::Context;
 use camino::Utf8PathBuf;
 use clap::Args;
+use enarx_config::Config;
 use enarx_exec_wasmtime::Package;
 
 /// Run a WebAssembly module inside an Enarx Keep.
@@ -31,7 +33,7 @@ pub struct Options {
     #[clap(long)]
     pub unsigned: bool,
 
-    /// Path of the signature file to use.
+    /// Path of the signature file or signature bundle to use.
     #[clap(long, value_name = "SIGNATURES")]
     pub signatures: Option<Utf8PathBuf>,
 
@@ -56,18 +58,28 @@ impl Options {
             #[cfg(feature = "gdb")]
             gdblisten,
         } = self;
-        let backend = backend.pick()?;
-        let exec = EXECS
-            .iter()
-            .find(|w| w.with_backend(backend))
-            .ok_or_else(|| anyhow!("no supported exec found"))
-            .map(|b| b.exec())?;
-
-        let signatures = if unsigned {
+
+        // The Keep resources of the package take effect on the creation of the Keep.
+        let keep = match wasmcfgfile {
+            Some(ref path) => {
+                let conf = read_to_string(path)
+                    .with_context(|| format!("failed to read package config at `{path}`"))?;
+                toml::from_str::<Config>(&conf)
+                    .with_context(|| format!("failed to parse package config at `{path}`"))?
+                    .keep
+            }
+            None => Default::default(),
+        };
+        backend.configure(keep)?;
+        let keep_backend = backend.pick()?;
+        backend.check_collateral(keep_backend)?;
+
+        let bundle = if unsigned {
             None
         } else {
-            Signatures::load(signatures)?
+            SignatureBundle::load(signatures)?
         };
+        let (shim, exec, signatures) = backend.binaries(keep_backend, bundle.as_ref())?;
 
         let get_pkg = || {
             let (wasm, conf) = open_package(module, wasmcfgfile)?;
@@ -85,7 +97,8 @@ impl Options {
         };
 
         run_package(
-            backend,
+            keep_backend,
+            shim,
             exec,
             signatures,
             #[cfg(not(feature = "gdb"))]
@@ -1,9 +1,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::sev::snp::launch::{IdAuth, IdBlock};
+use crate::backend::sev::snp::launch::{policy, IdAuth, IdBlock};
 use crate::backend::sev::snp::sign::Signature as IdSignature;
+use crate::backend::sev::snp::Version;
 use crate::backend::ByteSized;
-use crate::backend::{Backend, SevSignature, Signatures, BACKENDS};
+use crate::backend::{
+    Backend, BundleEntry, BundleKey, SevSignature, SignatureBundle, Signatures, BACKENDS,
+};
+use crate::cli::measure::{
+    signed_measurements, BODY_FEATURESMASK, BODY_ISVPRODID, BODY_ISVSVN, BODY_MISCMASK,
+    BODY_XFRMMASK,
+};
+use crate::cli::KeepOptions;
 use crate::exec::EXECS;
 
 use std::fmt::Debug;
@@ -17,6 +25,7 @@ use std::process::ExitCode;
 use crate::backend::sev::snp::sign::PublicKey;
 use anyhow::{anyhow, bail, Context, Result};
 use camino::Utf8PathBuf;
+use chrono::{DateTime, NaiveDate, Utc};
 use clap::Args;
 use p384::ecdsa::signature::Signer as _;
 use p384::ecdsa::SigningKey;
@@ -54,13 +63,294 @@ pub struct Options {
     /// File path to write the signature
     #[clap(long)]
     out: Option<Utf8PathBuf>,
+
+    #[clap(flatten)]
+    keep: KeepOptions,
+
+    #[clap(flatten)]
+    sgx: SgxSignOptions,
+
+    #[clap(flatten)]
+    sev_id: SevIdOptions,
+
+    #[clap(flatten)]
+    bundle: BundleOptions,
+}
+
+fn parse_hex_u32(s: &str) -> Result<u32> {
+    Ok(u32::from_str_radix(s.trim_start_matches("0x"), 16)?)
+}
+
+fn parse_hex_u64(s: &str) -> Result<u64> {
+    Ok(u64::from_str_radix(s.trim_start_matches("0x"), 16)?)
+}
+
+/// SGX SIGSTRUCT fields, overriding the defaults of the shim
+///
+/// The masks select the MISCSELECT and attribute bits of the enclave, which
+/// must match the signed values.
+#[derive(Args, Debug, Default)]
+pub struct SgxSignOptions {
+    /// ISV product ID of the SGX enclave, e.g. to tell products signed with the same key apart
+    #[clap(long, value_name = "ID")]
+    sgx_isv_prod_id: Option<u16>,
+
+    /// ISV security version number of the SGX enclave, used to prevent rollbacks
+    #[clap(long, value_name = "SVN")]
+    sgx_isv_svn: Option<u16>,
+
+    /// Date of the SGX signature as YYYY-MM-DD, unset (zero) by default
+    ///
+    /// The date is part of the signature, so signing the same Keep twice only
+    /// yields the same signature if the date is the same.
+    #[clap(long, value_name = "DATE")]
+    sgx_date: Option<NaiveDate>,
+
+    /// Mask of the MISCSELECT bits of the SGX enclave in hexadecimal
+    #[clap(long, value_name = "HEX", value_parser = parse_hex_u32)]
+    sgx_misc_mask: Option<u32>,
+
+    /// Mask of the attribute features of the SGX enclave in hexadecimal
+    #[clap(long, value_name = "HEX", value_parser = parse_hex_u64)]
+    sgx_features_mask: Option<u64>,
+
+    /// Mask of the XFRM attributes of the SGX enclave in hexadecimal
+    #[clap(long, value_name = "HEX", value_parser = parse_hex_u64)]
+    sgx_xfrm_mask: Option<u64>,
+}
+
+impl SgxSignOptions {
+    /// Returns the author of the SIGSTRUCT, which holds the date in BCD as `0xYYYYMMDD`.
+    ///
+    /// The date is zero unless `--sgx-date` is given, so that signatures are reproducible.
+    fn author(&self) -> Author {
+        let date = match self.sgx_date {
+            Some(date) => date.format("%Y%m%d").to_string(),
+            None => return Author::new(0, 0),
+        };
+        // The formatted date only consists of decimal digits.
+        Author::new(u32::from_str_radix(&date, 16).unwrap(), 0)
+    }
+
+    /// Applies the options to the SIGSTRUCT body `body`.
+    fn apply(&self, body: &mut [u8]) -> Result<()> {
+        if body.len() != size_of::<Body>() {
+            bail!("Invalid length of SGX input data");
+        }
+        if let Some(pid) = self.sgx_isv_prod_id {
+            body[BODY_ISVPRODID].copy_from_slice(&pid.to_le_bytes());
+        }
+        if let Some(svn) = self.sgx_isv_svn {
+            body[BODY_ISVSVN].copy_from_slice(&svn.to_le_bytes());
+        }
+        if let Some(mask) = self.sgx_misc_mask {
+            body[BODY_MISCMASK].copy_from_slice(&mask.to_le_bytes());
+        }
+        if let Some(mask) = self.sgx_features_mask {
+            body[BODY_FEATURESMASK].copy_from_slice(&mask.to_le_bytes());
+        }
+        if let Some(mask) = self.sgx_xfrm_mask {
+            body[BODY_XFRMMASK].copy_from_slice(&mask.to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+fn parse_id(s: &str) -> Result<[u8; 16]> {
+    let mut id = [0u8; 16];
+    hex::decode_to_slice(s, &mut id).context("expected 32 hexadecimal digits")?;
+    Ok(id)
+}
+
+fn parse_version(s: &str) -> Result<Version> {
+    let (major, minor) = s
+        .split_once('.')
+        .ok_or_else(|| anyhow!("expected a version like `1.51`"))?;
+    Ok(Version {
+        major: major.parse()?,
+        minor: minor.parse()?,
+    })
+}
+
+/// SEV ID block fields and guest policy, overriding the defaults of the shim
+///
+/// The guest policy of the signed ID block is enforced on the launch of the Keep.
+#[derive(Args, Debug, Default)]
+pub struct SevIdOptions {
+    /// Family ID of the SEV ID block as 32 hexadecimal digits, e.g. to identify a product line
+    #[clap(long, value_name = "HEX", value_parser = parse_id)]
+    sev_family_id: Option<[u8; 16]>,
+
+    /// Image ID of the SEV ID block as 32 hexadecimal digits
+    #[clap(long, value_name = "HEX", value_parser = parse_id)]
+    sev_image_id: Option<[u8; 16]>,
+
+    /// Guest SVN of the SEV ID block, which verifiers can use to prevent rollbacks
+    #[clap(long, value_name = "SVN")]
+    sev_guest_svn: Option<u32>,
+
+    /// Whether the SEV guest policy allows debugging
+    #[clap(long, value_name = "BOOL")]
+    sev_policy_debug: Option<bool>,
+
+    /// Whether the SEV guest policy allows SMT
+    #[clap(long, value_name = "BOOL")]
+    sev_policy_smt: Option<bool>,
+
+    /// Whether the SEV guest policy allows the association with a migration agent
+    #[clap(long, value_name = "BOOL")]
+    sev_policy_migrate_ma: Option<bool>,
+
+    /// Whether the SEV guest policy restricts the guest to a single socket
+    #[clap(long, value_name = "BOOL")]
+    sev_policy_single_socket: Option<bool>,
+
+    /// Minimum firmware ABI version of the SEV guest policy, e.g. "1.51"
+    #[clap(long, value_name = "VERSION", value_parser = parse_version)]
+    sev_policy_abi: Option<Version>,
+}
+
+impl SevIdOptions {
+    /// Whether any option is set
+    pub fn is_set(&self) -> bool {
+        self.sev_family_id.is_some()
+            || self.sev_image_id.is_some()
+            || self.sev_guest_svn.is_some()
+            || self.sev_policy_debug.is_some()
+            || self.sev_policy_smt.is_some()
+            || self.sev_policy_migrate_ma.is_some()
+            || self.sev_policy_single_socket.is_some()
+            || self.sev_policy_abi.is_some()
+    }
+
+    /// Applies the options to the SEV ID block `id_block`.
+    pub fn apply(&self, id_block: &mut IdBlock) {
+        if let Some(family_id) = self.sev_family_id {
+            id_block.family_id = family_id;
+        }
+        if let Some(image_id) = self.sev_image_id {
+            id_block.image_id = image_id;
+        }
+        if let Some(guest_svn) = self.sev_guest_svn {
+            id_block.guest_svn = guest_svn;
+        }
+
+        for (allow, bit) in [
+            (self.sev_policy_debug, policy::DEBUG),
+            (self.sev_policy_smt, policy::SMT),
+            (self.sev_policy_migrate_ma, policy::MIGRATE_MA),
+            (self.sev_policy_single_socket, policy::SINGLE_SOCKET),
+        ] {
+            match allow {
+                Some(true) => id_block.policy |= bit,
+                Some(false) => id_block.policy &= !bit,
+                None => (),
+            }
+        }
+        if let Some(abi) = self.sev_policy_abi {
+            id_block.policy &= !(policy::ABI_MAJOR | policy::ABI_MINOR);
+            id_block.policy |= u64::from(abi.major) << 8 | u64::from(abi.minor);
+        }
+    }
+}
+
+/// Signature bundle options, which add the signatures to a bundle of several Enarx versions and keys
+///
+/// Hosts running different versions of Enarx can share a bundle, from which each picks
+/// the signatures of its version, signed with a key that is currently valid.
+#[derive(Args, Debug, Default)]
+pub struct BundleOptions {
+    /// Signature bundle or signatures file to add the signatures to, which is not modified
+    #[clap(long, value_name = "PATH")]
+    bundle: Option<Utf8PathBuf>,
+
+    /// ID of the signing key in the signature bundle, e.g. to rotate keys
+    #[clap(long, value_name = "ID")]
+    key_id: Option<String>,
+
+    /// Time from which the signatures of the key are valid, e.g. "2026-01-01T00:00:00Z"
+    #[clap(long, value_name = "TIME", requires = "key_id")]
+    not_before: Option<DateTime<Utc>>,
+
+    /// Time until which the signatures of the key are valid, e.g. "2027-01-01T00:00:00Z"
+    #[clap(long, value_name = "TIME", requires = "key_id")]
+    not_after: Option<DateTime<Utc>>,
 }
 
-fn sign_sgx(body_bytes: &[u8], sgx_key: &RS256PrivateKey) -> Result<Vec<u8>> {
+impl BundleOptions {
+    /// Whether a signature bundle is written instead of a signatures file
+    fn is_set(&self) -> bool {
+        self.bundle.is_some() || self.key_id.is_some()
+    }
+
+    /// Adds `signatures` to `bundle`.
+    ///
+    /// The signatures replace the ones of the same version, key and measurements,
+    /// and the validity of the key replaces its previous validity. The fingerprints
+    /// of the key are the ones of the SGX and SEV keys, which `signatures` are signed with.
+    fn add(&self, bundle: &mut SignatureBundle, signatures: Signatures) -> Result<()> {
+        if let (Some(not_before), Some(not_after)) = (self.not_before, self.not_after) {
+            if not_after < not_before {
+                bail!("The signing key expires before it becomes valid");
+            }
+        }
+
+        if let Some(ref id) = self.key_id {
+            let mut key = BundleKey {
+                id: id.clone(),
+                not_before: self.not_before,
+                not_after: self.not_after,
+                sgx_mrsigner: signatures.sgx_mrsigner(),
+                sev_id_key_digest: signatures.sev_id_key_digest(),
+            };
+            match bundle.keys.iter_mut().find(|key| key.id == *id) {
+                Some(existing) => {
+                    key.sgx_mrsigner = key.sgx_mrsigner.or(existing.sgx_mrsigner.take());
+                    key.sev_id_key_digest =
+                        key.sev_id_key_digest.or(existing.sev_id_key_digest.take());
+                    *existing = key;
+                }
+                None => bundle.keys.push(key),
+            }
+        }
+
+        let measurements = signed_measurements(&signatures)?;
+        let mut entries = vec![];
+        for entry in bundle.entries.drain(..) {
+            if entry.key != self.key_id
+                || entry.signatures.version != signatures.version
+                || signed_measurements(&entry.signatures)? != measurements
+            {
+                entries.push(entry);
+            }
+        }
+        entries.push(BundleEntry {
+            key: self.key_id.clone(),
+            signatures,
+        });
+        bundle.entries = entries;
+        bundle.check()
+    }
+
+    /// Returns the signature bundle with `signatures`.
+    fn to_bundle(&self, signatures: Signatures) -> Result<SignatureBundle> {
+        let mut bundle = match self.bundle {
+            Some(ref path) => {
+                let json = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read signature bundle {path}"))?;
+                SignatureBundle::from_json(&json)
+                    .with_context(|| format!("Failed to load signature bundle {path}"))?
+            }
+            None => SignatureBundle::default(),
+        };
+        self.add(&mut bundle, signatures)?;
+        Ok(bundle)
+    }
+}
+
+fn sign_sgx(body_bytes: &[u8], sgx_key: &RS256PrivateKey, author: Author) -> Result<Vec<u8>> {
     let body = Body::from_bytes(body_bytes).ok_or_else(|| anyhow!("Invalid SGX input data"))?;
 
-    // FIXME: do we need the date and sw defined value?
-    let author = Author::new(0, 0);
     let sig = Signature::new(sgx_key, author, body).context("Failed to create RSA signature")?;
 
     Ok(sig.as_bytes().to_vec())
@@ -138,6 +428,7 @@ impl Options {
 
     pub fn execute(self) -> anyhow::Result<ExitCode> {
         use mmarinus::{perms, Map, Private};
+        self.keep.configure(Default::default())?;
         let binary = if let Some(ref path) = self.binpath {
             Some(Map::load(path, Private, perms::Read)?)
         } else {
@@ -169,12 +460,19 @@ impl Options {
 
             match backend.name() {
                 "sgx" => {
-                    let signature = sign_sgx(&blob, &self.load_sgx_key()?)?;
+                    let mut body = blob;
+                    self.sgx.apply(&mut body)?;
+
+                    let signature = sign_sgx(&body, &self.load_sgx_key()?, self.sgx.author())?;
                     signatures.sgx = signature;
                 }
                 "sev" => {
+                    let mut id_block = IdBlock::from_bytes(&blob)
+                        .ok_or_else(|| anyhow!("Invalid length of SEV input data"))?;
+                    self.sev_id.apply(&mut id_block);
+
                     let (id_key, id_key_signature) = self.load_sev_key()?;
-                    let signature = sign_sev(&blob, &id_key, &id_key_signature)?;
+                    let signature = sign_sev(id_block.as_bytes(), &id_key, &id_key_signature)?;
                     signatures.sev = signature;
                 }
                 _ => {
@@ -183,11 +481,16 @@ impl Options {
             };
         }
 
+        let json = if self.bundle.is_set() {
+            serde_json::to_string(&self.bundle.to_bundle(signatures)?)?
+        } else {
+            serde_json::to_string(&signatures)?
+        };
         if let Some(path) = self.out {
             let mut file = File::create(path)?;
-            file.write_all(serde_json::to_string(&signatures)?.as_bytes())?;
+            file.write_all(json.as_bytes())?;
         } else {
-            stdout().write_all(serde_json::to_string(&signatures)?.as_bytes())?;
+            stdout().write_all(json.as_bytes())?;
         }
         Ok(ExitCode::SUCCESS)
     }
@@ -195,11 +498,17 @@ impl Options {
 
 #[cfg(test)]
 mod test {
+    use crate::backend::sev::snp::launch::{policy, IdBlock};
+    use crate::backend::{SevSignature, SignatureBundle, Signatures, SIGNATURES_VERSION};
     use crate::cli::key::sev::sign::sign_id_sev_key;
-    use crate::cli::sign::{sign_sev, sign_sgx};
+    use crate::cli::measure::{BODY_ISVPRODID, BODY_ISVSVN, BODY_MISCMASK};
+    use crate::cli::sign::{
+        parse_id, parse_version, sign_sev, sign_sgx, BundleOptions, SevIdOptions, SgxSignOptions,
+    };
     use p384::ecdsa::SigningKey;
     use p384::pkcs8::DecodePrivateKey;
     use sgx::crypto::{rcrypto::*, *};
+    use sgx::signature::Author;
 
     const SGX_KEY: &str = include_str!("../../tests/data/sgx-test.key");
     const SEV_ID_KEY: &str = include_str!("../../tests/data/sev-id.key");
@@ -461,7 +770,7 @@ mod test {
     #[test]
     fn test_sgx_vector() {
         let key = RS256PrivateKey::from_pem(SGX_KEY).unwrap();
-        let out = sign_sgx(SGX_IN.as_slice(), &key).unwrap();
+        let out = sign_sgx(SGX_IN.as_slice(), &key, Author::new(0, 0)).unwrap();
         assert_eq!(SGX_OUT.as_slice(), out.as_slice());
     }
 
@@ -476,4 +785,133 @@ mod test {
         assert_eq!(SEV_IN.as_slice(), out.id_block.as_slice());
         assert_eq!(SEV_OUT.as_slice(), out.id_auth.as_slice());
     }
+
+    #[test]
+    fn test_sgx_sign_options() {
+        let mut body = SGX_IN;
+        SgxSignOptions::default().apply(&mut body).unwrap();
+        assert_eq!(body, SGX_IN);
+
+        let options = SgxSignOptions {
+            sgx_isv_prod_id: Some(0x1234),
+            sgx_isv_svn: Some(7),
+            sgx_date: Some("2023-04-15".parse().unwrap()),
+            sgx_misc_mask: Some(0xffff_ffff),
+            ..Default::default()
+        };
+        options.apply(&mut body).unwrap();
+        assert_eq!(body[BODY_ISVPRODID], 0x1234u16.to_le_bytes());
+        assert_eq!(body[BODY_ISVSVN], 7u16.to_le_bytes());
+        assert_eq!(body[BODY_MISCMASK], [0xff; 4]);
+        assert!(options.apply(&mut body[1..]).is_err());
+
+        let key = RS256PrivateKey::from_pem(SGX_KEY).unwrap();
+        let dated = sign_sgx(&body, &key, options.author()).unwrap();
+        let undated = sign_sgx(&body, &key, Author::new(0, 0)).unwrap();
+        assert_ne!(dated, undated);
+        // Without `--sgx-date`, the signature does not depend on the day of signing.
+        let default = sign_sgx(&body, &key, SgxSignOptions::default().author()).unwrap();
+        assert_eq!(default, undated);
+        // The date follows the header and the vendor in the SIGSTRUCT.
+        assert_eq!(dated[20..24], 0x20230415u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_sev_id_options() {
+        let mut id_block = IdBlock {
+            policy: 0x30000 | policy::DEBUG,
+            ..Default::default()
+        };
+
+        SevIdOptions::default().apply(&mut id_block);
+        assert_eq!(id_block.policy, 0x30000 | policy::DEBUG);
+
+        let options = SevIdOptions {
+            sev_family_id: Some(parse_id("000102030405060708090a0b0c0d0e0f").unwrap()),
+            sev_guest_svn: Some(7),
+            sev_policy_debug: Some(false),
+            sev_policy_smt: Some(false),
+            sev_policy_single_socket: Some(true),
+            sev_policy_abi: Some(parse_version("1.51").unwrap()),
+            ..Default::default()
+        };
+        assert!(options.is_set());
+        options.apply(&mut id_block);
+
+        assert_eq!(id_block.family_id, std::array::from_fn(|i| i as u8));
+        assert_eq!(id_block.image_id, [0; 16]);
+        assert_eq!(id_block.guest_svn, 7);
+        assert_eq!(
+            id_block.policy,
+            policy::RESERVED | policy::SINGLE_SOCKET | 1 << 8 | 51
+        );
+
+        assert!(parse_id("0001").is_err());
+        assert!(parse_version("1").is_err());
+    }
+
+    #[test]
+    fn test_bundle_options() {
+        let signed = |version: &str, mrenclave: u8, svn: u8, key: u8| Signatures {
+            version: version.into(),
+            sev: SevSignature::default(),
+            sgx: [
+                vec![svn; 128],
+                vec![key; 384],
+                vec![svn; 388],
+                vec![mrenclave; 128],
+                vec![0; 780],
+            ]
+            .concat(),
+        };
+        let signatures = |version: &str, mrenclave: u8, svn: u8| signed(version, mrenclave, svn, 1);
+        let options = |key_id: &str| BundleOptions {
+            key_id: Some(key_id.into()),
+            not_before: Some("2026-01-01T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let mut bundle = SignatureBundle::default();
+        options("2026")
+            .add(&mut bundle, signatures("0.0.1", 1, 0))
+            .unwrap();
+        options("2026")
+            .add(&mut bundle, signatures(SIGNATURES_VERSION, 1, 0))
+            .unwrap();
+        options("2026")
+            .add(&mut bundle, signatures(SIGNATURES_VERSION, 2, 0))
+            .unwrap();
+        assert_eq!(bundle.entries.len(), 3);
+
+        // Re-signing the same measurement replaces its signatures.
+        options("2026")
+            .add(&mut bundle, signatures(SIGNATURES_VERSION, 1, 1))
+            .unwrap();
+        assert_eq!(bundle.entries.len(), 3);
+        assert_eq!(bundle.entries[2].signatures.sgx[0], 1);
+
+        // A new key is added next to the old one.
+        options("2027")
+            .add(&mut bundle, signatures(SIGNATURES_VERSION, 1, 2))
+            .unwrap();
+        assert_eq!(bundle.keys.len(), 2);
+        assert_eq!(bundle.entries.len(), 4);
+
+        let invalid = BundleOptions {
+            not_after: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+            ..options("2028")
+        };
+        assert!(invalid
+            .add(&mut bundle, signatures(SIGNATURES_VERSION, 1, 3))
+            .is_err());
+
+        // The key is bound to the SGX key of its signatures.
+        let mrsigner = bundle.keys[0].sgx_mrsigner.clone();
+        assert!(mrsigner.is_some());
+        assert_eq!(bundle.keys[1].sgx_mrsigner, mrsigner);
+        let err = options("2026")
+            .add(&mut bundle, signed(SIGNATURES_VERSION, 3, 0, 2))
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("SGX signature is signed with the key"));
+    }
 }
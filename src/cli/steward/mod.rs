@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod serve;
+
+use std::process::ExitCode;
+
+use clap::Subcommand;
+
+/// Commands for running a local Steward for development and testing.
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    Serve(serve::Options),
+}
+
+impl Subcommands {
+    pub fn dispatch(self) -> anyhow::Result<ExitCode> {
+        match self {
+            Self::Serve(cmd) => cmd.execute(),
+        }
+    }
+}
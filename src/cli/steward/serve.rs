@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::attestation::pki;
+use crate::cli::attestation::{read_reference, CollateralOptions};
+use crate::cli::output;
+use crate::steward::{server, Steward};
+
+use std::fs;
+use std::io::{stdout, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Context};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use der::pem::LineEnding;
+use der::Encode;
+use serde::Serialize;
+
+/// Run a local Steward for development and testing.
+///
+/// The Steward verifies the attestation evidence in the CSRs POSTed by Keeps and
+/// issues certificates signed by its CA. KVM Keeps, which cannot attest, are
+/// accepted with a warning. Without `--ca-cert` and `--ca-key` an ephemeral CA is
+/// generated and its certificate is printed on startup.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Address to listen on
+    #[clap(long, default_value = "127.0.0.1:8443")]
+    addr: SocketAddr,
+
+    /// PEM encoded CA certificate to issue certificates with
+    #[clap(long, requires = "ca_key")]
+    ca_cert: Option<Utf8PathBuf>,
+
+    /// PEM encoded PKCS#8 P-256 or P-384 key of the CA certificate
+    #[clap(long, requires = "ca_cert")]
+    ca_key: Option<Utf8PathBuf>,
+
+    /// PEM encoded TLS certificate chain of the Steward, leaf first
+    #[clap(long, requires = "tls_key")]
+    tls_cert: Option<Utf8PathBuf>,
+
+    /// PEM encoded PKCS#8 key of the TLS certificate
+    #[clap(long, requires = "tls_cert")]
+    tls_key: Option<Utf8PathBuf>,
+
+    /// Name to issue a TLS certificate for, if no `--tls-cert` is given
+    #[clap(long, default_value = "localhost", conflicts_with = "tls_cert")]
+    tls_name: String,
+
+    #[clap(flatten)]
+    collateral: CollateralOptions,
+
+    /// Measurements Keeps must match, as printed by `enarx measure`
+    #[clap(long)]
+    policy: Option<Utf8PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Listening {
+    url: String,
+    ca: String,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<ExitCode> {
+        let collateral = self.collateral.load()?;
+        let policy = read_reference(self.policy.as_deref())?;
+
+        let steward = match (self.ca_cert, self.ca_key) {
+            (Some(cert), Some(key)) => {
+                let ca = pki::read_certificates(&read(&cert)?)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow!("No certificate found in {cert}"))?;
+                Steward::new(ca, &read_key(&key)?, collateral, policy)?
+            }
+            _ => Steward::generate(collateral, policy)?,
+        };
+
+        let (certs, key) = match (self.tls_cert, self.tls_key) {
+            (Some(cert), Some(key)) => {
+                let certs = rustls_pemfile::certs(&mut read(&cert)?.as_slice())
+                    .with_context(|| format!("Failed to decode certificates in {cert}"))?;
+                (certs, read_key(&key)?)
+            }
+            _ => steward.tls_identity(&self.tls_name)?,
+        };
+        let tls = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                certs.into_iter().map(rustls::Certificate).collect(),
+                rustls::PrivateKey(key),
+            )
+            .context("Invalid TLS certificate or key")?;
+
+        let listener = TcpListener::bind(self.addr)
+            .with_context(|| format!("Failed to listen on {}", self.addr))?;
+        let listening = Listening {
+            url: format!("https://{}", listener.local_addr()?),
+            ca: der::pem::encode_string("CERTIFICATE", LineEnding::LF, &steward.ca().to_der()?)
+                .map_err(der::Error::from)?,
+        };
+        output::print(&listening, |listening| {
+            Ok(format!(
+                "Listening on {}\nCA certificate:\n{}",
+                listening.url,
+                listening.ca.trim_end()
+            ))
+        })?;
+        stdout().flush()?;
+
+        server::serve(listener, steward, tls)?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+fn read(path: &Utf8Path) -> anyhow::Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read {path}"))
+}
+
+fn read_key(path: &Utf8Path) -> anyhow::Result<Vec<u8>> {
+    rustls_pemfile::pkcs8_private_keys(&mut read(path)?.as_slice())
+        .with_context(|| format!("Failed to decode {path}"))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No PKCS#8 private key found in {path}"))
+}
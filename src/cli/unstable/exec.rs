@@ -28,7 +28,7 @@ pub struct Options {
     #[clap(long)]
     pub unsigned: bool,
 
-    /// Path of the signature file to use.
+    /// Path of the signature file or signature bundle to use.
     #[clap(long, value_name = "SIGNATURES")]
     pub signatures: Option<Utf8PathBuf>,
 
@@ -41,7 +41,11 @@ pub struct Options {
 #[cfg(enarx_with_shim)]
 impl Options {
     pub fn execute(self) -> anyhow::Result<std::process::ExitCode> {
-        use crate::backend::Signatures;
+        use crate::backend::SignatureBundle;
+        use crate::cli::measure::signature_matcher;
+
+        use anyhow::Context;
+        use chrono::Utc;
 
         let Self {
             backend,
@@ -55,13 +59,29 @@ impl Options {
         use crate::exec::keep_exec;
         use mmarinus::{perms, Map, Private};
 
-        let backend = backend.pick()?;
+        backend.configure(Default::default())?;
+        let keep_backend = backend.pick()?;
+        let shim = backend.shim(keep_backend)?;
         let binary = Map::load(binpath, Private, perms::Read)?;
 
         let signatures = if unsigned {
             None
         } else {
-            Signatures::load(signatures)?
+            SignatureBundle::load(signatures)?
+        };
+        let signatures = match signatures {
+            Some(bundle) => Some(
+                bundle
+                    .select(
+                        keep_backend.name(),
+                        Utc::now(),
+                        true,
+                        signature_matcher(keep_backend, &shim, binary.as_ref()),
+                    )
+                    .context("failed to select the signatures of the Keep")?
+                    .clone(),
+            ),
+            None => None,
         };
 
         #[cfg(not(feature = "gdb"))]
@@ -70,6 +90,6 @@ impl Options {
         #[cfg(feature = "gdb")]
         let gdblisten = Some(gdblisten);
 
-        keep_exec(backend, backend.shim(), binary, signatures, gdblisten)
+        keep_exec(keep_backend, shim, binary, signatures, gdblisten)
     }
 }
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cli::output;
 use crate::drawbridge::{client, UserSpec};
 
 use std::ffi::OsString;
@@ -37,7 +38,7 @@ impl Options {
         let record = user
             .get()
             .with_context(|| format!("Failed to get record for user: {}", self.spec.ctx.name))?;
-        println!("{}", serde_json::to_string_pretty(&record)?);
+        output::print_json(&record)?;
         Ok(ExitCode::SUCCESS)
     }
 }
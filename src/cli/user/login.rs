@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use super::oidc_client_secret;
+use super::{oidc_client_secret, prompt_device_code};
+use crate::cli::output;
 use crate::drawbridge::{LoginContext, OidcLoginFlow};
 
 use std::ffi::OsString;
@@ -8,6 +9,12 @@ use std::process::ExitCode;
 
 use clap::Args;
 use oauth2::url::Url;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Login<'a> {
+    host: &'a str,
+}
 
 /// Log in to an Enarx package host and save credentials locally.
 #[derive(Args, Debug)]
@@ -48,9 +55,12 @@ impl Options {
             oidc_flow,
             credential_helper,
         }
-        .login()?;
+        .login(prompt_device_code)?;
+        output::status("Credentials saved locally.");
 
-        println!("Login successful.");
+        output::print(&Login { host: store_host }, |_| {
+            Ok("Login successful.".into())
+        })?;
 
         Ok(ExitCode::SUCCESS)
     }
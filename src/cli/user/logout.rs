@@ -1,15 +1,83 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use super::oidc_client_secret;
+use crate::cli::output;
+use crate::drawbridge::{delete_token, get_token, revoke_token};
+
+use std::ffi::OsString;
 use std::process::ExitCode;
 
+use anyhow::Context;
 use clap::Args;
+use oauth2::url::Url;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Logout<'a> {
+    host: &'a str,
+    revoked: bool,
+    deleted: bool,
+}
 
 /// Log out of an Enarx package host and delete local credentials.
 #[derive(Args, Debug)]
-pub struct Options {}
+pub struct Options {
+    #[clap(
+        long,
+        env = "ENARX_OIDC_DOMAIN",
+        default_value = "https://auth.profian.com/"
+    )]
+    oidc_domain: Url,
+    #[clap(long, default_value = "4NuaJxkQv8EZBeJKE56R57gKJbxrTLG2")]
+    oidc_client_id: String,
+    #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
+    credential_helper: Option<OsString>,
+    #[clap(long, default_value = "store.profian.com")]
+    store_host: String,
+    /// Also revoke the token with the OpenID Connect provider.
+    #[clap(long)]
+    revoke: bool,
+}
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<ExitCode> {
-        unimplemented!()
+        let Self {
+            ref oidc_domain,
+            oidc_client_id,
+            credential_helper,
+            ref store_host,
+            revoke,
+        } = self;
+        let credential_helper = credential_helper.as_ref().map(AsRef::as_ref);
+
+        if revoke {
+            let oidc_client_secret = oidc_client_secret()?;
+            let token = get_token(store_host, oidc_domain, None::<String>, credential_helper)
+                .context("Failed to retrieve local credentials")?;
+            revoke_token(oidc_domain, oidc_client_id, oidc_client_secret, token)?;
+            output::status("Token revoked.");
+        }
+
+        let deleted = delete_token(store_host, oidc_domain, credential_helper)?;
+        if deleted {
+            output::status("Local credentials deleted.");
+        }
+
+        output::print(
+            &Logout {
+                host: store_host,
+                revoked: revoke,
+                deleted,
+            },
+            |logout| {
+                Ok(if logout.deleted {
+                    "Logout successful.".into()
+                } else {
+                    "Already logged out.".into()
+                })
+            },
+        )?;
+
+        Ok(ExitCode::SUCCESS)
     }
 }
@@ -5,6 +5,9 @@ mod login;
 mod logout;
 mod register;
 
+use crate::cli::output;
+use crate::drawbridge::DeviceCode;
+
 use std::env::{var, VarError};
 use std::process::ExitCode;
 
@@ -17,7 +20,6 @@ pub enum Subcommands {
     #[clap(hide = true)]
     Info(info::Options),
     Login(login::Options),
-    #[clap(hide = true)]
     Logout(logout::Options),
     Register(register::Options),
 }
@@ -43,3 +45,15 @@ pub fn oidc_client_secret() -> anyhow::Result<Option<String>> {
         Ok(secret) => Ok(Some(secret)),
     }
 }
+
+/// Asks the user to enter the one-time code of a device login.
+pub fn prompt_device_code(code: &DeviceCode<'_>) {
+    output::status(format_args!(
+        "To continue, open the following link in your browser:\n\
+         \t{}\n\
+         At the prompt, enter the following one-time code:\n\
+         \t{}\n\
+         Once entered, please wait a few moments for authorization to complete.",
+        code.verification_uri, code.user_code
+    ));
+}
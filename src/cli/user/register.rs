@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use super::oidc_client_secret;
+use super::{oidc_client_secret, prompt_device_code};
+use crate::cli::output;
 use crate::drawbridge::{client, get_token, LoginContext, OidcLoginFlow, UserSpec};
 
 use std::ffi::OsString;
@@ -60,15 +61,19 @@ impl Options {
             credential_helper,
         ) {
             Ok(token) => token,
-            _ => LoginContext {
-                host: &spec.host,
-                oidc_domain,
-                oidc_client_id: oidc_client_id.clone(),
-                oidc_client_secret,
-                oidc_flow,
-                credential_helper,
+            _ => {
+                let token = LoginContext {
+                    host: &spec.host,
+                    oidc_domain,
+                    oidc_client_id: oidc_client_id.clone(),
+                    oidc_client_secret,
+                    oidc_flow,
+                    credential_helper,
+                }
+                .login(prompt_device_code)?;
+                output::status("Credentials saved locally.");
+                token
             }
-            .login()?,
         };
 
         let cl = client(
@@ -105,6 +110,7 @@ impl Options {
 
         user.create(&record)
             .context("Failed to register new user")?;
+        output::print_json_only(&record)?;
 
         Ok(ExitCode::SUCCESS)
     }
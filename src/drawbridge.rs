@@ -3,25 +3,29 @@
 use std::borrow::Borrow;
 use std::convert::TryInto;
 use std::ffi::OsStr;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{stderr, Write};
 use std::process::{Command, Stdio};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::thread::spawn;
 use std::time::Duration;
 
-use anyhow::{anyhow, bail, Context};
-use camino::Utf8Path;
-use drawbridge_client::types::{RepositoryContext, TagContext, UserContext};
+use anyhow::{anyhow, bail, ensure, Context};
+use camino::{Utf8Path, Utf8PathBuf};
+use drawbridge_client::types::{RepositoryContext, TagContext, TagEntry, TreePath, UserContext};
 use drawbridge_client::Client;
+use enarx_exec_wasmtime::{latest_yank_marker, yank_marker, Yank, MAX_YANK_SIZE, YANK_FILE};
 use oauth2::basic::BasicClient;
 use oauth2::devicecode::StandardDeviceAuthorizationResponse;
+use oauth2::http::{HeaderMap, Method};
 use oauth2::url::Url;
 use oauth2::{
-    AuthType, AuthUrl, ClientId, ClientSecret, DeviceAuthorizationUrl, Scope, TokenResponse,
-    TokenUrl,
+    AccessToken, AuthType, AuthUrl, ClientId, ClientSecret, DeviceAuthorizationUrl, HttpRequest,
+    RevocationUrl, Scope, StandardRevocableToken, TokenResponse, TokenUrl,
 };
-use rustls::{Certificate, RootCertStore};
+use rustls::{Certificate, ClientConfig, RootCertStore};
+use serde::Deserialize;
 
 const DEFAULT_HOST: &str = "store.profian.com";
 
@@ -172,6 +176,22 @@ pub fn client(
     let host = host.as_ref();
 
     let token = get_token(host, oidc_domain, insecure_token, helper)?;
+    build_client(host, Some(token), ca_bundle)
+}
+
+/// Construct a client for `host` without credentials, which can only read public repositories.
+pub fn anonymous_client(
+    host: impl AsRef<str>,
+    ca_bundle: Option<impl AsRef<Utf8Path>>,
+) -> anyhow::Result<Client> {
+    build_client(host.as_ref(), None, ca_bundle)
+}
+
+fn build_client(
+    host: &str,
+    token: Option<String>,
+    ca_bundle: Option<impl AsRef<Utf8Path>>,
+) -> anyhow::Result<Client> {
     let url = format!("https://{host}");
 
     let mut cl = Client::builder(
@@ -179,32 +199,122 @@ pub fn client(
             .with_context(|| format!("Failed to parse URL: {url}"))?,
     );
 
-    if let Some(ca_bundle_path) = ca_bundle {
-        cl = cl.roots({
-            let mut roots = RootCertStore::empty();
+    if let Some(ca_bundle) = ca_bundle {
+        cl = cl.roots(root_store(ca_bundle.as_ref())?);
+    }
+
+    if let Some(token) = token {
+        cl = cl.token(token.trim());
+    }
+
+    cl.build().context("Failed to build client")
+}
 
-            let ca_bundle_file = File::open(ca_bundle_path.as_ref())?;
+/// Reads the root certificates of the package host from the PEM file `ca_bundle`.
+fn root_store(ca_bundle: &Utf8Path) -> anyhow::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
 
-            rustls_pemfile::certs(&mut std::io::BufReader::new(ca_bundle_file))
-                .unwrap()
-                .into_iter()
-                .map(Certificate)
-                .try_for_each(|ref cert| {
-                    roots
-                        .add(cert)
-                        .with_context(|| format!("Failed to add root certificate: {cert:?}"))
-                })?;
+    let ca_bundle_file = File::open(ca_bundle)?;
 
+    rustls_pemfile::certs(&mut std::io::BufReader::new(ca_bundle_file))
+        .unwrap()
+        .into_iter()
+        .map(Certificate)
+        .try_for_each(|ref cert| {
             roots
-        });
+                .add(cert)
+                .with_context(|| format!("Failed to add root certificate: {cert:?}"))
+        })?;
+
+    Ok(roots)
+}
+
+/// Construct an HTTP agent for requests to a package host, which the Drawbridge client
+/// does not provide, trusting the root certificates in `ca_bundle` if given.
+pub fn agent(ca_bundle: Option<impl AsRef<Utf8Path>>) -> anyhow::Result<ureq::Agent> {
+    let mut agent =
+        ureq::AgentBuilder::new().user_agent(&format!("enarx-cli/{}", env!("CARGO_PKG_VERSION")));
+    if let Some(ca_bundle) = ca_bundle {
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_store(ca_bundle.as_ref())?)
+            .with_no_client_auth();
+        agent = agent.tls_config(Arc::new(config));
     }
+    Ok(agent.build())
+}
+
+fn latest_yank_tag(cl: &Client, tag: &TagContext) -> anyhow::Result<Option<(u64, TagContext)>> {
+    let tags = cl
+        .repository(&tag.repository)
+        .tags()
+        .context("Failed to retrieve repository tags")?
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    latest_yank_marker(&tag.name.to_string(), tags.iter().map(String::as_str))
+        .map(|(seq, name)| {
+            let name = name
+                .parse()
+                .with_context(|| format!("Invalid yank marker tag name: {name}"))?;
+            Ok((
+                seq,
+                TagContext {
+                    repository: tag.repository.clone(),
+                    name,
+                },
+            ))
+        })
+        .transpose()
+}
 
-    let cl = cl
-        .token(token.trim())
-        .build()
-        .context("Failed to build client")?;
+/// Retrieve the yank status of a tag, if it was ever yanked.
+pub fn get_yank(cl: &Client, tag: &TagContext) -> anyhow::Result<Option<Yank>> {
+    let marker = match latest_yank_tag(cl, tag)? {
+        Some((_, marker)) => marker,
+        None => return Ok(None),
+    };
+    let tag = cl.tag(&marker);
+    let entry = match tag
+        .get()
+        .with_context(|| format!("Failed to retrieve yank marker: {}", marker.name))?
+    {
+        TagEntry::Unsigned(entry) => entry,
+        TagEntry::Signed(_) => bail!("Signed yank markers are not currently supported"),
+    };
+    let (meta, yank) = tag
+        .path(&TreePath::ROOT)
+        .get_bytes(MAX_YANK_SIZE)
+        .with_context(|| format!("Failed to retrieve yank marker: {}", marker.name))?;
+    ensure!(
+        meta == entry.meta,
+        "Yank record metadata does not match the yank marker: {}",
+        marker.name
+    );
+    serde_json::from_slice(&yank)
+        .context("Failed to decode yank record")
+        .map(Some)
+}
 
-    Ok(cl)
+/// Record a new yank status of a tag.
+pub fn set_yank(cl: &Client, tag: &TagContext, yank: &Yank) -> anyhow::Result<()> {
+    let seq = latest_yank_tag(cl, tag)?.map_or(0, |(seq, _)| seq + 1);
+    let name = yank_marker(&tag.name.to_string(), seq);
+    let marker = TagContext {
+        repository: tag.repository.clone(),
+        name: name
+            .parse()
+            .with_context(|| format!("Invalid yank marker tag name: {name}"))?,
+    };
+
+    let dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+    let path = Utf8PathBuf::try_from(dir.path().join(YANK_FILE))
+        .context("Temporary directory path is not valid UTF-8")?;
+    fs::write(&path, serde_json::to_vec(yank)?).context("Failed to write yank record")?;
+    cl.tag(&marker)
+        .create_from_path_unsigned(&path)
+        .context("Failed to create yank marker tag")?;
+    Ok(())
 }
 
 fn http_client(mut req: oauth2::HttpRequest) -> Result<oauth2::HttpResponse, oauth2::ureq::Error> {
@@ -260,11 +370,112 @@ fn store_retrieved_access_token(
             .set_password(&secret)
             .context("Failed to save user credentials")?;
     }
-    println!("Credentials saved locally.");
-
     Ok(secret)
 }
 
+/// Delete the locally stored token for `host`.
+///
+/// Returns `false` if the keyring has no token, i.e. the user is already logged out.
+pub fn delete_token(
+    host: impl AsRef<str>,
+    oidc_domain: impl Borrow<Url>,
+    helper: Option<impl AsRef<OsStr>>,
+) -> anyhow::Result<bool> {
+    let host = host.as_ref();
+
+    let oidc_domain = oidc_domain
+        .borrow()
+        .host_str()
+        .ok_or_else(|| anyhow!("invalid OpenID Connect domain"))?;
+    let oidc_token_id = format!("{oidc_domain}-{host}");
+
+    if let Some(helper) = helper {
+        let output = Command::new(helper)
+            .arg("erase")
+            .arg(oidc_token_id)
+            .output()
+            .context("Failed to execute credential helper")?;
+        stderr()
+            .write_all(&output.stderr)
+            .context("Failed to write stderr")?;
+        if !output.status.success() {
+            if let Some(code) = output.status.code() {
+                bail!("Credential helper failed with exit code {code}")
+            } else {
+                bail!("Credential helper was killed")
+            }
+        }
+    } else {
+        match keyring::Entry::new("enarx", &oidc_token_id).delete_password() {
+            Ok(()) => {}
+            Err(keyring::Error::NoEntry) => return Ok(false),
+            Err(e) => return Err(e).context("Failed to delete credentials from keyring"),
+        }
+    }
+
+    Ok(true)
+}
+
+/// The part of the OpenID Connect provider metadata needed for token revocation
+#[derive(Deserialize)]
+struct RevocationMetadata {
+    revocation_endpoint: Option<String>,
+}
+
+/// Discover the token revocation endpoint of the OpenID Connect provider.
+fn revocation_url(oidc_domain: &Url) -> anyhow::Result<RevocationUrl> {
+    let url = oidc_domain
+        .join(".well-known/openid-configuration")
+        .context("Failed to construct provider metadata URL")?;
+    let response = http_client(HttpRequest {
+        url,
+        method: Method::GET,
+        headers: HeaderMap::new(),
+        body: Vec::new(),
+    })
+    .context("Failed to discover provider metadata")?;
+    if !response.status_code.is_success() {
+        bail!(
+            "Failed to discover provider metadata: HTTP status {}",
+            response.status_code
+        );
+    }
+    let metadata: RevocationMetadata =
+        serde_json::from_slice(&response.body).context("Failed to decode provider metadata")?;
+    let endpoint = metadata
+        .revocation_endpoint
+        .ok_or_else(|| anyhow!("The OpenID Connect provider does not support token revocation"))?;
+    RevocationUrl::new(endpoint).context("Failed to construct revocation URL")
+}
+
+pub fn revoke_token(
+    oidc_domain: &Url,
+    oidc_client_id: String,
+    oidc_client_secret: Option<String>,
+    token: String,
+) -> anyhow::Result<()> {
+    let auth_url = AuthUrl::new(format!("{oidc_domain}authorize"))
+        .context("Failed to construct authorization URL")?;
+    let token_url = TokenUrl::new(format!("{oidc_domain}oauth/token"))
+        .context("Failed to construct token URL")?;
+    let revocation_url = revocation_url(oidc_domain)?;
+
+    BasicClient::new(
+        ClientId::new(oidc_client_id),
+        oidc_client_secret.map(ClientSecret::new),
+        auth_url,
+        Some(token_url),
+    )
+    .set_auth_type(AuthType::RequestBody)
+    .set_revocation_uri(revocation_url)
+    .revoke_token(StandardRevocableToken::AccessToken(AccessToken::new(
+        token.trim().into(),
+    )))
+    .context("Failed to construct token revocation request")?
+    .request(http_client)
+    .context("Failed to revoke token")
+}
+
 #[derive(Debug, Clone)]
 pub enum OidcLoginFlow {
     ClientCredentials,
@@ -283,6 +494,14 @@ impl FromStr for OidcLoginFlow {
     }
 }
 
+/// The one-time code, which a user enters at the verification URI to authorize
+/// a device login.
+#[derive(Debug)]
+pub struct DeviceCode<'a> {
+    pub verification_uri: &'a str,
+    pub user_code: &'a str,
+}
+
 #[derive(Debug)]
 pub struct LoginContext<'a> {
     pub host: &'a str,
@@ -307,7 +526,11 @@ impl LoginContext<'_> {
         std::thread::sleep(Duration::from_secs(3));
     }
 
-    pub fn login(self) -> anyhow::Result<String> {
+    /// Log in and store the retrieved access token locally.
+    ///
+    /// `prompt` is called with the one-time code the user has to enter, if the
+    /// device authorization flow is used.
+    pub fn login(self, prompt: impl FnOnce(&DeviceCode<'_>)) -> anyhow::Result<String> {
         let Self {
             host,
             oidc_domain,
@@ -347,15 +570,10 @@ impl LoginContext<'_> {
                     .add_extra_param("audience", audience)
                     .request(http_client)
                     .context("Failed to request device code")?;
-                println!(
-                    "To continue, open the following link in your browser:\n\
-         \t{}\n\
-         At the prompt, enter the following one-time code:\n\
-         \t{}\n\
-         Once entered, please wait a few moments for authorization to complete.",
-                    details.verification_uri().as_str(),
-                    details.user_code().secret()
-                );
+                prompt(&DeviceCode {
+                    verification_uri: details.verification_uri().as_str(),
+                    user_code: details.user_code().secret(),
+                });
 
                 // TODO: graceful timeout, so that users are not forced to Ctrl+C if the server errors
 
@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Endpoints of the services platform collateral is fetched from
+//!
+//! By default, collateral is fetched from the AMD Key Distribution Service (KDS) and
+//! the Intel Provisioning Certification Service (PCS). Hosts without internet access
+//! can point Enarx to a local mirror or an Intel Provisioning Certificate Caching
+//! Service (PCCS) in the `[endpoints]` table of the host configuration file:
+//!
+//! ```toml
+//! [endpoints.amd]
+//! kds = "https://kds-mirror.example.com"
+//!
+//! [endpoints.intel]
+//! api = "pccs"
+//! url = "https://pccs.example.com:8081"
+//! certificates = "https://intel-mirror.example.com"
+//! ```
+//!
+//! The host configuration file is read from `/etc/enarx/host.toml`, or the path in
+//! `ENARX_HOST_CONFIG`. The endpoints can also be overridden with the environment
+//! variables `ENARX_AMD_KDS_URL`, `ENARX_INTEL_API`, `ENARX_INTEL_URL` and
+//! `ENARX_INTEL_CERTIFICATES_URL`.
+//!
+//! Mirrors only serve collateral, i.e. certificates, CRLs and TCB info. All of it
+//! is verified against the root certificates pinned in `crate::attestation::roots`,
+//! a root certificate served by a mirror is never trusted.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+/// Default path of the host configuration file
+const HOST_CONFIG: &str = "/etc/enarx/host.toml";
+
+static ENDPOINTS: OnceCell<Endpoints> = OnceCell::new();
+
+#[derive(Debug, Default, Deserialize)]
+struct HostConfig {
+    #[serde(default)]
+    endpoints: Endpoints,
+}
+
+/// Endpoints of the services platform collateral is fetched from
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Endpoints {
+    pub amd: Amd,
+    pub intel: Intel,
+}
+
+impl Endpoints {
+    /// Returns the endpoints configured for this host.
+    pub fn get() -> Result<&'static Self> {
+        ENDPOINTS.get_or_try_init(|| {
+            let path = std::env::var_os("ENARX_HOST_CONFIG")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| HOST_CONFIG.into());
+            let mut endpoints = match fs::read_to_string(&path) {
+                Ok(config) => Self::from_toml(&config)
+                    .with_context(|| format!("Invalid host configuration file {path:?}"))?,
+                Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to read host configuration {path:?}"))
+                }
+            };
+            endpoints.apply(|name| std::env::var(name).ok())?;
+            Ok(endpoints)
+        })
+    }
+
+    fn from_toml(config: &str) -> Result<Self> {
+        Ok(toml::from_str::<HostConfig>(config)?.endpoints)
+    }
+
+    /// Overrides the endpoints with the environment variables returned by `var`.
+    fn apply(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        if let Some(kds) = var("ENARX_AMD_KDS_URL") {
+            self.amd.kds = kds;
+        }
+        if let Some(api) = var("ENARX_INTEL_API") {
+            self.intel.api = match api.as_str() {
+                "pcs" => IntelApi::Pcs,
+                "pccs" => IntelApi::Pccs,
+                _ => bail!("ENARX_INTEL_API must be `pcs` or `pccs`, not `{api}`"),
+            };
+        }
+        if let Some(url) = var("ENARX_INTEL_URL") {
+            self.intel.url = url;
+        }
+        if let Some(certificates) = var("ENARX_INTEL_CERTIFICATES_URL") {
+            self.intel.certificates = certificates;
+        }
+        Ok(())
+    }
+}
+
+/// Endpoints of the AMD Key Distribution Service
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Amd {
+    /// Base URL of the KDS or a mirror of it
+    pub kds: String,
+}
+
+impl Default for Amd {
+    fn default() -> Self {
+        Self {
+            kds: "https://kdsintf.amd.com".into(),
+        }
+    }
+}
+
+impl Amd {
+    /// Returns the URL of the CRL of the processor model `product`, e.g. `Milan`.
+    pub fn crl(&self, product: &str) -> String {
+        format!("{}/vcek/v1/{product}/crl", self.base())
+    }
+
+    /// Returns the URL of the ASK and ARK certificates of the processor model `product`.
+    pub fn cert_chain(&self, product: &str) -> String {
+        format!("{}/vcek/v1/{product}/cert_chain", self.base())
+    }
+
+    /// Returns the base URL of the KDS without a trailing slash.
+    pub fn base(&self) -> &str {
+        self.kds.trim_end_matches('/')
+    }
+}
+
+/// API of the service providing Intel's collateral
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntelApi {
+    /// The Intel Provisioning Certification Service or a mirror of it
+    #[default]
+    Pcs,
+    /// An Intel Provisioning Certificate Caching Service
+    Pccs,
+}
+
+/// Endpoints of the services providing Intel's collateral
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Intel {
+    /// API of the service at `url`
+    pub api: IntelApi,
+    /// Base URL of the PCS or PCCS
+    pub url: String,
+    /// Base URL of the CRL of the Intel SGX root CA
+    pub certificates: String,
+}
+
+impl Default for Intel {
+    fn default() -> Self {
+        Self {
+            api: IntelApi::Pcs,
+            url: "https://api.trustedservices.intel.com".into(),
+            certificates: "https://certificates.trustedservices.intel.com".into(),
+        }
+    }
+}
+
+impl Intel {
+    fn certification(&self) -> String {
+        format!("{}/sgx/certification/v4", self.url.trim_end_matches('/'))
+    }
+
+    /// Returns the URL of the PCK certificate of the platform with the given identifiers,
+    /// as printed by Intel's `PCKIDRetrievalTool`.
+    pub fn pck_cert(
+        &self,
+        encrypted_ppid: &str,
+        pce_id: &str,
+        cpusvn: &str,
+        pcesvn: &str,
+        qe_id: &str,
+    ) -> String {
+        let url = format!(
+            "{}/pckcert?encrypted_ppid={encrypted_ppid}&cpusvn={cpusvn}&pcesvn={pcesvn}&pceid={pce_id}",
+            self.certification()
+        );
+        match self.api {
+            IntelApi::Pcs => url,
+            // The PCCS looks up cached PCK certificates by the QE ID.
+            IntelApi::Pccs => format!("{url}&qeid={qe_id}"),
+        }
+    }
+
+    /// Returns the URL of the TCB info of the platform model `fmspc`.
+    pub fn tcb(&self, fmspc: &str) -> String {
+        format!("{}/tcb?fmspc={fmspc}", self.certification())
+    }
+
+    /// Returns the URL of the CRL of the PCK `ca`, i.e. `processor` or `platform`.
+    pub fn pck_crl(&self, ca: &str) -> String {
+        format!("{}/pckcrl?ca={ca}&encoding=der", self.certification())
+    }
+
+    /// Returns the URL of the CRL of the Intel SGX root CA.
+    pub fn root_ca_crl(&self) -> String {
+        match self.api {
+            IntelApi::Pcs => format!(
+                "{}/IntelSGXRootCA.der",
+                self.certificates.trim_end_matches('/')
+            ),
+            IntelApi::Pccs => format!("{}/rootcacrl", self.certification()),
+        }
+    }
+
+    /// Returns the URL to register platforms at.
+    pub fn registration(&self) -> Result<String> {
+        match self.api {
+            IntelApi::Pcs => Ok(format!(
+                "{}/sgx/registration/v1/platform",
+                self.url.trim_end_matches('/')
+            )),
+            IntelApi::Pccs => {
+                bail!(
+                    "Platforms cannot be registered through a PCCS, use Intel's PCKIDRetrievalTool"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default() {
+        let endpoints = Endpoints::from_toml("").unwrap();
+        assert_eq!(
+            endpoints.amd.crl("Milan"),
+            "https://kdsintf.amd.com/vcek/v1/Milan/crl"
+        );
+        assert_eq!(
+            endpoints.intel.tcb("00906ED50000"),
+            "https://api.trustedservices.intel.com/sgx/certification/v4/tcb?fmspc=00906ED50000"
+        );
+        assert_eq!(
+            endpoints.intel.root_ca_crl(),
+            "https://certificates.trustedservices.intel.com/IntelSGXRootCA.der"
+        );
+        assert_eq!(
+            endpoints.intel.registration().unwrap(),
+            "https://api.trustedservices.intel.com/sgx/registration/v1/platform"
+        );
+    }
+
+    #[test]
+    fn pccs() {
+        let mut endpoints = Endpoints::from_toml(
+            r#"
+            [endpoints.amd]
+            kds = "http://127.0.0.1:8080/"
+
+            [endpoints.intel]
+            api = "pccs"
+            url = "https://pccs.example.com:8081"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            endpoints.amd.cert_chain("Genoa"),
+            "http://127.0.0.1:8080/vcek/v1/Genoa/cert_chain"
+        );
+        assert_eq!(
+            endpoints.intel.root_ca_crl(),
+            "https://pccs.example.com:8081/sgx/certification/v4/rootcacrl"
+        );
+        assert_eq!(
+            endpoints.intel.pck_cert("ppid", "pceid", "cpusvn", "pcesvn", "qeid"),
+            "https://pccs.example.com:8081/sgx/certification/v4/pckcert?encrypted_ppid=ppid&cpusvn=cpusvn&pcesvn=pcesvn&pceid=pceid&qeid=qeid"
+        );
+        assert!(endpoints.intel.registration().is_err());
+
+        endpoints
+            .apply(|name| match name {
+                "ENARX_INTEL_API" => Some("pcs".into()),
+                "ENARX_INTEL_URL" => Some("http://127.0.0.1:8081".into()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(endpoints.intel.api, IntelApi::Pcs);
+        assert_eq!(
+            endpoints.intel.pck_crl("platform"),
+            "http://127.0.0.1:8081/sgx/certification/v4/pckcrl?ca=platform&encoding=der"
+        );
+        assert!(endpoints
+            .apply(|name| (name == "ENARX_INTEL_API").then(|| "dcap".into()))
+            .is_err());
+
+        assert!(Endpoints::from_toml("[endpoints.amd]\nurl = \"http://x\"").is_err());
+    }
+}
@@ -137,6 +137,7 @@ pub fn open_package(
 #[cfg(windows)]
 pub fn run_package(
     backend: &dyn Backend,
+    shim: impl AsRef<[u8]>,
     exec: impl AsRef<[u8]>,
     _signatures: Option<Signatures>,
     gdblisten: Option<String>,
@@ -145,7 +146,7 @@ pub fn run_package(
     let package = package()?;
     let args = ExecArgs { package };
     backend.set_args(args);
-    keep_exec(backend, backend.shim(), exec, None, gdblisten)
+    keep_exec(backend, shim, exec, None, gdblisten)
 }
 
 /// Runs a package.
@@ -155,6 +156,7 @@ pub fn run_package(
 #[cfg(unix)]
 pub fn run_package(
     backend: &dyn Backend,
+    shim: impl AsRef<[u8]>,
     exec: impl AsRef<[u8]>,
     signatures: Option<Signatures>,
     gdblisten: Option<String>,
@@ -177,11 +179,32 @@ pub fn run_package(
     );
 
     let package = package()?;
+    #[cfg(feature = "bench")]
+    let profile = profile.map(IntoRawFd::into_raw_fd);
+
+    // The Keep may only use the host file descriptors passed to it.
+    #[cfg(enarx_with_shim)]
+    {
+        use crate::backend::policy::SYSCALL_POLICY;
+
+        SYSCALL_POLICY.grant(exec_sock.as_raw_fd());
+        if let Package::Local { wasm, conf } = &package {
+            SYSCALL_POLICY.grant(*wasm);
+            if let Some(conf) = conf {
+                SYSCALL_POLICY.grant(*conf);
+            }
+        }
+        #[cfg(feature = "bench")]
+        if let Some(profile) = profile {
+            SYSCALL_POLICY.grant(profile);
+        }
+    }
+
     let args = toml::to_vec(&ExecArgs {
         package,
         log_level,
         #[cfg(feature = "bench")]
-        profile: profile.map(IntoRawFd::into_raw_fd),
+        profile,
     })
     .context("failed to encode exec-wasmtime arguments")?;
 
@@ -205,7 +228,7 @@ pub fn run_package(
             .context("failed to shutdown read half of host's socket")
     });
 
-    let exit_code = keep_exec(backend, backend.shim(), exec, signatures, gdblisten)?;
+    let exit_code = keep_exec(backend, shim, exec, signatures, gdblisten)?;
     exec_io
         .join()
         .expect("failed to join exec-wasmtime I/O thread")?;
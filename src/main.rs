@@ -7,14 +7,20 @@
 // protobuf-codegen-pure would generate warnings
 #![allow(elided_lifetimes_in_paths)]
 
+#[cfg(enarx_with_shim)]
+mod attestation;
 mod backend;
 #[cfg(enarx_with_shim)]
 pub(crate) mod caching;
 mod cli;
 mod drawbridge;
+#[cfg(enarx_with_shim)]
+mod endpoints;
 mod exec;
 #[cfg(enarx_with_shim)]
 mod protobuf;
+#[cfg(enarx_with_shim)]
+mod steward;
 
 use std::process::ExitCode;
 
@@ -0,0 +1,394 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A local Steward for development and testing
+//!
+//! The Steward verifies the attestation evidence in the CSR of a Keep and issues
+//! a certificate for the Keep's key, which is signed by a local CA.
+
+pub mod server;
+
+use crate::attestation::{self, Collateral, Evidence, Reference, Technology};
+
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, bail, Context, Result};
+use der::asn1::{BitString, Ia5String, OctetString};
+use der::oid::db::rfc5280::{ID_KP_CLIENT_AUTH, ID_KP_SERVER_AUTH};
+use der::oid::db::rfc5912::{
+    ECDSA_WITH_SHA_256, ECDSA_WITH_SHA_384, ID_EC_PUBLIC_KEY, SECP_256_R_1, SECP_384_R_1,
+};
+use der::oid::AssociatedOid;
+use der::{Decode, Encode};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{
+    EcdsaKeyPair, EcdsaSigningAlgorithm, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING,
+    ECDSA_P384_SHA384_ASN1_SIGNING,
+};
+use tracing::{info, warn};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::{
+    BasicConstraints, ExtendedKeyUsage, KeyUsage, KeyUsages, SubjectAltName,
+};
+use x509_cert::ext::Extension;
+use x509_cert::name::RdnSequence;
+use x509_cert::request::CertReq;
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::time::{Time, Validity};
+use x509_cert::{Certificate, PkiPath, TbsCertificate, Version};
+
+/// Validity of the certificates issued by the Steward
+const VALIDITY: Duration = Duration::from_secs(60 * 60 * 24);
+/// Validity of a generated CA certificate
+const CA_VALIDITY: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+
+/// The ECDSA signing key of the CA
+struct SigningKey {
+    pair: EcdsaKeyPair,
+    algorithm: AlgorithmIdentifierOwned,
+    curve: der::oid::ObjectIdentifier,
+}
+
+impl SigningKey {
+    /// Loads a PKCS#8 encoded P-256 or P-384 key.
+    fn from_pkcs8(pkcs8: &[u8]) -> Result<Self> {
+        let algorithms: [(&'static EcdsaSigningAlgorithm, _, _); 2] = [
+            (
+                &ECDSA_P256_SHA256_ASN1_SIGNING,
+                ECDSA_WITH_SHA_256,
+                SECP_256_R_1,
+            ),
+            (
+                &ECDSA_P384_SHA384_ASN1_SIGNING,
+                ECDSA_WITH_SHA_384,
+                SECP_384_R_1,
+            ),
+        ];
+        for (signing, oid, curve) in algorithms {
+            if let Ok(pair) = EcdsaKeyPair::from_pkcs8(signing, pkcs8) {
+                return Ok(Self {
+                    pair,
+                    algorithm: AlgorithmIdentifierOwned {
+                        oid,
+                        parameters: None,
+                    },
+                    curve,
+                });
+            }
+        }
+        bail!("key is not a PKCS#8 encoded P-256 or P-384 key")
+    }
+
+    /// Generates a new P-256 key, returning it along with its PKCS#8 encoding.
+    fn generate() -> Result<(Self, Vec<u8>)> {
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &SystemRandom::new())
+                .map_err(|_| anyhow!("failed to generate a key"))?;
+        let key = Self::from_pkcs8(pkcs8.as_ref())?;
+        Ok((key, pkcs8.as_ref().to_vec()))
+    }
+
+    fn public_key(&self) -> Result<SubjectPublicKeyInfoOwned> {
+        Ok(SubjectPublicKeyInfoOwned {
+            algorithm: AlgorithmIdentifierOwned {
+                oid: ID_EC_PUBLIC_KEY,
+                parameters: Some(self.curve.into()),
+            },
+            subject_public_key: BitString::from_bytes(self.pair.public_key().as_ref())?,
+        })
+    }
+
+    fn sign(&self, tbs: TbsCertificate) -> Result<Certificate> {
+        let signature = self
+            .pair
+            .sign(&SystemRandom::new(), &tbs.to_der()?)
+            .map_err(|_| anyhow!("failed to sign certificate"))?;
+        Ok(Certificate {
+            tbs_certificate: tbs,
+            signature_algorithm: self.algorithm.clone(),
+            signature: BitString::from_bytes(signature.as_ref())?,
+        })
+    }
+}
+
+fn extension<T: AssociatedOid + Encode>(value: T, critical: bool) -> Result<Extension> {
+    Ok(Extension {
+        extn_id: T::OID,
+        critical,
+        extn_value: OctetString::new(value.to_der()?)?,
+    })
+}
+
+/// Extensions of the certificates issued to Keeps and to the Steward itself
+fn leaf_extensions() -> Result<Vec<Extension>> {
+    Ok(vec![
+        extension(
+            KeyUsage(KeyUsages::DigitalSignature | KeyUsages::KeyEncipherment),
+            true,
+        )?,
+        extension(
+            BasicConstraints {
+                ca: false,
+                path_len_constraint: None,
+            },
+            true,
+        )?,
+        extension(
+            ExtendedKeyUsage(vec![ID_KP_SERVER_AUTH, ID_KP_CLIENT_AUTH]),
+            false,
+        )?,
+    ])
+}
+
+/// A Steward with a local CA
+pub struct Steward {
+    ca: Certificate,
+    key: SigningKey,
+    collateral: Collateral,
+    policy: Reference,
+}
+
+impl Steward {
+    /// Creates a Steward issuing certificates with the CA certificate `ca` and its PKCS#8 encoded `key`.
+    ///
+    /// Keeps must attest with `collateral` and match the measurements of `policy`.
+    pub fn new(
+        ca: Certificate,
+        key: &[u8],
+        collateral: Collateral,
+        policy: Reference,
+    ) -> Result<Self> {
+        let key = SigningKey::from_pkcs8(key).context("Invalid CA key")?;
+        if key.public_key()?.to_der()? != ca.tbs_certificate.subject_public_key_info.to_der()? {
+            bail!("CA key does not match the CA certificate");
+        }
+        Ok(Self {
+            ca,
+            key,
+            collateral,
+            policy,
+        })
+    }
+
+    /// Creates a Steward with a newly generated, self-signed CA.
+    pub fn generate(collateral: Collateral, policy: Reference) -> Result<Self> {
+        let (key, _) = SigningKey::generate()?;
+        let subject = RdnSequence::from_str("CN=Enarx Steward CA")?;
+        let tbs = TbsCertificate {
+            version: Version::V3,
+            serial_number: serial_number()?,
+            signature: key.algorithm.clone(),
+            issuer: subject.clone(),
+            validity: validity(SystemTime::now(), CA_VALIDITY)?,
+            subject,
+            subject_public_key_info: key.public_key()?,
+            issuer_unique_id: None,
+            subject_unique_id: None,
+            extensions: Some(vec![
+                extension(KeyUsage(KeyUsages::KeyCertSign | KeyUsages::CRLSign), true)?,
+                extension(
+                    BasicConstraints {
+                        ca: true,
+                        path_len_constraint: Some(0),
+                    },
+                    true,
+                )?,
+            ]),
+        };
+        let ca = key.sign(tbs)?;
+        Ok(Self {
+            ca,
+            key,
+            collateral,
+            policy,
+        })
+    }
+
+    /// Returns the CA certificate.
+    pub fn ca(&self) -> &Certificate {
+        &self.ca
+    }
+
+    fn issue(
+        &self,
+        subject: RdnSequence,
+        public_key: SubjectPublicKeyInfoOwned,
+        extensions: Vec<Extension>,
+        now: SystemTime,
+    ) -> Result<Certificate> {
+        let tbs = TbsCertificate {
+            version: Version::V3,
+            serial_number: serial_number()?,
+            signature: self.key.algorithm.clone(),
+            issuer: self.ca.tbs_certificate.subject.clone(),
+            validity: validity(now, VALIDITY)?,
+            subject,
+            subject_public_key_info: public_key,
+            issuer_unique_id: None,
+            subject_unique_id: None,
+            extensions: Some(extensions),
+        };
+        self.key.sign(tbs)
+    }
+
+    /// Verifies the DER encoded `csr` of a Keep and returns the DER encoded `PkiPath` issued to it.
+    ///
+    /// KVM Keeps cannot attest, they are accepted with a warning.
+    pub fn attest(&self, csr: &[u8], now: SystemTime) -> Result<Vec<u8>> {
+        let req = CertReq::from_der(csr).context("Failed to decode the CSR")?;
+        let evidence = Evidence::from_csr(csr)?;
+        let verdict = attestation::verify(&evidence, &self.collateral, &self.policy, now)?;
+
+        if evidence.technology == Technology::Kvm {
+            warn!("accepting a KVM Keep, which provides no attestation evidence");
+        } else if !verdict.verified {
+            let failed: Vec<_> = verdict
+                .checks
+                .iter()
+                .filter(|check| !check.pass)
+                .map(|check| match check.info {
+                    Some(ref info) => format!("{}: {info}", check.name),
+                    None => check.name.to_string(),
+                })
+                .collect();
+            bail!("attestation failed: {}", failed.join(", "));
+        }
+        info!(technology = ?evidence.technology, claims = ?verdict.claims, "issuing certificate");
+
+        let crt = self.issue(
+            req.info.subject,
+            req.info.public_key,
+            leaf_extensions()?,
+            now,
+        )?;
+        let path: PkiPath = vec![self.ca.clone(), crt];
+        Ok(path.to_der()?)
+    }
+
+    /// Issues a TLS server certificate for `name` to a newly generated key.
+    ///
+    /// Returns the DER encoded certificate chain, leaf first, and the PKCS#8 encoded key.
+    pub fn tls_identity(&self, name: &str) -> Result<(Vec<Vec<u8>>, Vec<u8>)> {
+        let (key, pkcs8) = SigningKey::generate()?;
+        let san = match IpAddr::from_str(name) {
+            Ok(IpAddr::V4(ip)) => GeneralName::IpAddress(OctetString::new(ip.octets())?),
+            Ok(IpAddr::V6(ip)) => GeneralName::IpAddress(OctetString::new(ip.octets())?),
+            Err(_) => GeneralName::DnsName(Ia5String::new(name)?),
+        };
+        let mut extensions = leaf_extensions()?;
+        extensions.push(extension(SubjectAltName(vec![san]), false)?);
+
+        let subject = RdnSequence::from_str(&format!("CN={name}"))?;
+        let crt = self.issue(subject, key.public_key()?, extensions, SystemTime::now())?;
+        Ok((vec![crt.to_der()?, self.ca.to_der()?], pkcs8))
+    }
+}
+
+fn serial_number() -> Result<SerialNumber> {
+    let mut serial = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut serial)
+        .map_err(|_| anyhow!("failed to generate a serial number"))?;
+    // Keep the serial number positive and of constant length.
+    serial[0] = serial[0] & 0x7f | 0x40;
+    Ok(SerialNumber::new(&serial)?)
+}
+
+fn validity(now: SystemTime, duration: Duration) -> Result<Validity> {
+    Ok(Validity {
+        not_before: Time::try_from(now)?,
+        not_after: Time::try_from(now + duration)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attestation::pki;
+
+    use std::fs;
+
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data");
+
+    fn read(name: &str) -> Vec<u8> {
+        fs::read(format!("{DATA}/{name}")).unwrap()
+    }
+
+    fn now() -> SystemTime {
+        // The attestation fixtures were generated on 2026-10-18.
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_281_600)
+    }
+
+    fn steward(policy: Reference) -> Steward {
+        let collateral = Collateral {
+            sgx_root: Some(
+                pki::read_certificates(&read("attestation/sgx-root.pem"))
+                    .unwrap()
+                    .remove(0),
+            ),
+            snp_chain: pki::read_certificates(&read("attestation/snp-chain.pem")).unwrap(),
+            ..Default::default()
+        };
+        let ca = pki::read_certificates(&read("tls/ca.crt"))
+            .unwrap()
+            .remove(0);
+        let key = rustls_pemfile::pkcs8_private_keys(&mut read("tls/ca.key").as_slice())
+            .unwrap()
+            .remove(0);
+        Steward::new(ca, &key, collateral, policy).unwrap()
+    }
+
+    fn policy() -> Reference {
+        serde_json::from_slice(&read("attestation/reference.json")).unwrap()
+    }
+
+    fn issued(steward: &Steward, name: &str) -> Result<PkiPath> {
+        let csr = read(&format!("attestation/{name}"));
+        let path = PkiPath::from_der(&steward.attest(&csr, now())?)?;
+
+        let req = CertReq::from_der(&csr)?;
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[0].to_der()?, steward.ca().to_der()?);
+        assert_eq!(
+            path[1].tbs_certificate.subject_public_key_info,
+            req.info.public_key
+        );
+        pki::verify_certificate(&path[1], &path[0])?;
+        Ok(path)
+    }
+
+    #[test]
+    fn attest() {
+        let steward = steward(policy());
+        issued(&steward, "sgx.csr").unwrap();
+        issued(&steward, "snp.csr").unwrap();
+        issued(&steward, "kvm.csr").unwrap();
+
+        let mut policy = policy();
+        policy.sgx.as_mut().unwrap().isv_svn = Some(3);
+        let steward = self::steward(policy);
+        let err = issued(&steward, "sgx.csr").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "attestation failed: ISVSVN: expected at least 3, found 2"
+        );
+        issued(&steward, "snp.csr").unwrap();
+
+        assert!(steward.attest(b"garbage", now()).is_err());
+    }
+
+    #[test]
+    fn generate() {
+        let steward = Steward::generate(Collateral::default(), Reference::default()).unwrap();
+        pki::verify_chain(&[steward.ca().clone()], None, SystemTime::now()).unwrap();
+
+        let (chain, _) = steward.tls_identity("localhost").unwrap();
+        let chain: Vec<_> = chain
+            .iter()
+            .map(|der| Certificate::from_der(der).unwrap())
+            .collect();
+        pki::verify_chain(&chain, Some(steward.ca()), SystemTime::now()).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A minimal HTTPS server for the Steward protocol
+//!
+//! A Keep POSTs its DER encoded CSR with the `application/pkcs10` content type
+//! and receives the DER encoded `PkiPath` issued to it.
+
+use super::Steward;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use tracing::{debug, error};
+
+/// Maximum size of a CSR in bytes
+const MAX_CSR_SIZE: usize = 1 << 20;
+/// Maximum size of the request line and headers in bytes
+const MAX_HEAD_SIZE: usize = 16 << 10;
+/// Time after which idle connections are dropped
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn error(status: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: message.into().into_bytes(),
+        }
+    }
+}
+
+/// Reads an HTTP/1.1 request, returning the response to send.
+fn handle(steward: &Steward, stream: &mut impl Read) -> Result<Response> {
+    let mut reader = BufReader::new(stream.take((MAX_HEAD_SIZE + MAX_CSR_SIZE) as u64));
+
+    let mut head = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("connection closed before the end of the request headers");
+        }
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            break;
+        }
+        head.push(trimmed.to_string());
+        if head.iter().map(String::len).sum::<usize>() > MAX_HEAD_SIZE {
+            return Ok(Response::error(
+                "431 Request Header Fields Too Large",
+                "request headers are too large",
+            ));
+        }
+    }
+
+    let method = head.first().and_then(|line| line.split(' ').next());
+    if method != Some("POST") {
+        return Ok(Response::error(
+            "405 Method Not Allowed",
+            "the Steward only accepts POST requests",
+        ));
+    }
+
+    let mut content_type = None;
+    let mut content_length = None;
+    for header in head.iter().skip(1) {
+        if let Some((name, value)) = header.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-type" => content_type = Some(value.trim().to_ascii_lowercase()),
+                "content-length" => content_length = value.trim().parse::<usize>().ok(),
+                _ => {}
+            }
+        }
+    }
+    if content_type.as_deref() != Some("application/pkcs10") {
+        return Ok(Response::error(
+            "415 Unsupported Media Type",
+            "the request must contain an `application/pkcs10` CSR",
+        ));
+    }
+    let length = match content_length {
+        Some(length) if length <= MAX_CSR_SIZE => length,
+        Some(_) => return Ok(Response::error("413 Payload Too Large", "CSR is too large")),
+        None => {
+            return Ok(Response::error(
+                "411 Length Required",
+                "missing content length",
+            ))
+        }
+    };
+
+    let mut csr = vec![0; length];
+    reader
+        .read_exact(&mut csr)
+        .context("failed to read the CSR")?;
+
+    Ok(match steward.attest(&csr, SystemTime::now()) {
+        Ok(path) => Response {
+            status: "200 OK",
+            content_type: "application/pkix-pkipath",
+            body: path,
+        },
+        Err(e) => {
+            error!("rejected CSR: {e:#}");
+            Response::error("403 Forbidden", format!("{e:#}"))
+        }
+    })
+}
+
+fn connection(steward: &Steward, tls: Arc<ServerConfig>, stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let conn = ServerConnection::new(tls).context("failed to create TLS connection")?;
+    let mut stream = StreamOwned::new(conn, stream);
+
+    let response = handle(steward, &mut stream)?;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()?;
+    stream.conn.send_close_notify();
+    stream.flush()?;
+    Ok(())
+}
+
+/// Serves the Steward protocol over TLS on `listener`, handling each connection on its own thread.
+pub fn serve(listener: TcpListener, steward: Steward, tls: ServerConfig) -> Result<()> {
+    let steward = Arc::new(steward);
+    let tls = Arc::new(tls);
+    for stream in listener.incoming() {
+        let stream = stream.context("failed to accept connection")?;
+        let steward = steward.clone();
+        let tls = tls.clone();
+        thread::spawn(move || {
+            let peer = stream.peer_addr().ok();
+            debug!(?peer, "accepted connection");
+            if let Err(e) = connection(&steward, tls, stream) {
+                error!(?peer, "failed to handle connection: {e:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attestation::{Collateral, Reference};
+
+    fn steward() -> Steward {
+        Steward::generate(Collateral::default(), Reference::default()).unwrap()
+    }
+
+    fn status(request: &[u8]) -> &'static str {
+        handle(&steward(), &mut &request[..]).unwrap().status
+    }
+
+    #[test]
+    fn requests() {
+        assert_eq!(status(b"GET / HTTP/1.1\r\n\r\n"), "405 Method Not Allowed");
+        assert_eq!(
+            status(b"POST / HTTP/1.1\r\nContent-Type: text/plain\r\n\r\n"),
+            "415 Unsupported Media Type"
+        );
+        assert_eq!(
+            status(b"POST / HTTP/1.1\r\nContent-Type: application/pkcs10\r\n\r\n"),
+            "411 Length Required"
+        );
+        assert_eq!(
+            status(b"POST / HTTP/1.1\r\nContent-Type: application/pkcs10\r\nContent-Length: 4\r\n\r\nnope"),
+            "403 Forbidden"
+        );
+        assert!(handle(&steward(), &mut &b"POST / HTTP/1.1\r\n"[..]).is_err());
+    }
+}
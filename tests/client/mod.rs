@@ -40,6 +40,21 @@ Caused by:
     2: User with OpenID Connect subject `test|subject` not found"
     );
 
+    cmd!(
+        fail: // when looking up a user that does not exist with JSON output
+        "enarx --output json user info {db_host}/testuser",
+        json: {
+            "error": {
+                "message": "Failed to get record for user: testuser",
+                "causes": [
+                    "GET request failed",
+                    "request failed with status code `401`",
+                    "User with OpenID Connect subject `test|subject` not found"
+                ]
+            }
+        }
+    );
+
     cmd!(
         fail: // when registering user without proper credentials
         "enarx user register --insecure-auth-token bad-token {db_host}/testuser",
@@ -114,6 +129,20 @@ Caused by:
 
     // TODO: succeed when fetching tags from an empty private repo
 
+    cmd!(
+        succeed: // when searching named repositories, skipping missing ones
+        "enarx --output json repo search --name repo {db_host}/testuser pubrepo nosuchrepo",
+        json: [
+            {
+                "name": "pubrepo",
+                "config": {
+                    "public": true
+                },
+                "tags": []
+            }
+        ]
+    );
+
     cmd!(
         fail: // when looking up a package that does not exist
         "enarx package info {db_host}/testuser/pubrepo:0.1.0",
@@ -185,6 +214,74 @@ Caused by:
           }
     );
 
+    cmd!(
+        succeed: // when yanking a public package
+        "enarx package yank --reason 'broken release' {db_host}/testuser/pubrepo:1.0.0"
+    );
+
+    cmd!(
+        succeed: // when looking up a yanked public package
+        "enarx package info {db_host}/testuser/pubrepo:1.0.0",
+        json: {
+            "digest": {
+                "sha-224": "vXCZCfe+zKI5GtcoOWP/RFi21EZw6m7NtloKEw==",
+                "sha-256": "51LuttW2C0ohRQaAf2DtNYyeahzNcYzcSeS16Rr9bJA=",
+                "sha-384": "IilZTec69dvOzQeH8rk5tZST4bhcTUjPIfI1qi2lHiXUo8YCOpIQm2vEAB/irjAL",
+                "sha-512": "zRFpR9PG1gnsjI78tYypHlst0gJgUnsZo1Jn/6aXceOzEH0YGUxQjRahGBIJJ5b7C/Zmo85d/JSU5UfkJXb4Lg=="
+            },
+            "length": 53966,
+            "type": "application/wasm",
+            "yank": {
+                "yanked": true,
+                "reason": "broken release"
+            }
+        }
+    );
+
+    cmd!(
+        fail: // when deploying a yanked package without `--allow-yanked`
+        "enarx deploy {db_host}/testuser/pubrepo:1.0.0",
+        text:
+        "Error: package `{db_host}/testuser/pubrepo:1.0.0` was yanked: broken release"
+    );
+
+    cmd!(
+        succeed: // when fetching tags from a public repo with a yanked package
+        "enarx repo info {db_host}/testuser/pubrepo",
+        json: {
+            "config": {
+                "public": true
+            },
+            "tags": [
+                "1.0.0",
+                "2.0.0"
+            ]
+        }
+    );
+
+    cmd!(
+        succeed: // when un-yanking a yanked public package
+        "enarx package yank --undo {db_host}/testuser/pubrepo:1.0.0"
+    );
+
+    cmd!(
+        succeed: // when looking up an un-yanked public package
+        "enarx package info {db_host}/testuser/pubrepo:1.0.0",
+        json: {
+            "digest": {
+                "sha-224": "vXCZCfe+zKI5GtcoOWP/RFi21EZw6m7NtloKEw==",
+                "sha-256": "51LuttW2C0ohRQaAf2DtNYyeahzNcYzcSeS16Rr9bJA=",
+                "sha-384": "IilZTec69dvOzQeH8rk5tZST4bhcTUjPIfI1qi2lHiXUo8YCOpIQm2vEAB/irjAL",
+                "sha-512": "zRFpR9PG1gnsjI78tYypHlst0gJgUnsZo1Jn/6aXceOzEH0YGUxQjRahGBIJJ5b7C/Zmo85d/JSU5UfkJXb4Lg=="
+            },
+            "length": 53966,
+            "type": "application/wasm",
+            "yank": {
+                "yanked": false
+            }
+        }
+    );
+
     // TODO: succeed when looking up a private package that exists
     // TODO: succeed when fetching a public package
     // TODO: fail when fetching a private package
@@ -217,4 +314,178 @@ fn test_config_init() {
     cmd!(succeed: "enarx config init");
     cmd!(fail: "enarx config init", text: r#"Error: "Enarx.toml" does already exist."#);
     assert!(Path::new("Enarx.toml").exists());
+
+    std::fs::remove_file("Enarx.toml").unwrap();
+    cmd!(
+        succeed: "enarx --output json config init",
+        json: {
+            "path": "Enarx.toml"
+        }
+    );
+    assert!(Path::new("Enarx.toml").exists());
+
+    cmd!(succeed: "enarx config check");
+
+    std::fs::write(
+        "Invalid.toml",
+        "steward = \"http://attest.example.com\"\n[[files]]\nkind = \"stdin\"\n[[files]]\nkind = \"stdout\"\n[[files]]\nkind = \"stderr\"\n",
+    )
+    .unwrap();
+    cmd!(
+        fail: "enarx --output json config check Invalid.toml",
+        json: {
+            "path": "Invalid.toml",
+            "diagnostics": [{
+                "severity": "error",
+                "key": "steward",
+                "line": 1,
+                "column": 1,
+                "message": "Steward URL scheme must be `https`, found `http`, the Keep refuses unencrypted Steward URLs"
+            }]
+        }
+    );
+}
+
+#[test]
+fn test_user_logout_credential_helper() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmpdir = Builder::new().prefix("test_user_logout").tempdir().unwrap();
+    let erased = tmpdir.path().join("erased");
+    let helper = tmpdir.path().join("helper.sh");
+    fs::write(
+        &helper,
+        format!(
+            "#!/bin/sh\nset -e\n[ \"${{1}}\" = \"erase\" ]\nprintf '%s' \"${{2}}\" > {}\n",
+            erased.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&helper, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let helper = helper.display();
+    cmd!(
+        succeed: // when erasing credentials with a custom credential helper
+        "enarx user logout --oidc-domain https://auth.example.com/ --store-host store.example.com --credential-helper {helper}",
+        text: "Local credentials deleted.\nLogout successful."
+    );
+    assert_eq!(
+        fs::read_to_string(erased).unwrap(),
+        "auth.example.com-store.example.com"
+    );
+}
+
+/// Sends a CSR to the Steward at `addr`, returning the HTTP status code and response body.
+#[cfg(enarx_with_shim)]
+fn steward_request(
+    addr: &str,
+    tls: std::sync::Arc<rustls::ClientConfig>,
+    csr: &[u8],
+) -> (u16, Vec<u8>) {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    let conn = rustls::ClientConnection::new(tls, "localhost".try_into().unwrap()).unwrap();
+    let mut stream = rustls::StreamOwned::new(conn, TcpStream::connect(addr).unwrap());
+    write!(
+        stream,
+        "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/pkcs10\r\nContent-Length: {}\r\n\r\n",
+        csr.len()
+    )
+    .unwrap();
+    stream.write_all(csr).unwrap();
+
+    let mut response = vec![];
+    stream.read_to_end(&mut response).unwrap();
+    let head = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .expect("malformed HTTP response");
+    let status = std::str::from_utf8(&response[9..12])
+        .unwrap()
+        .parse()
+        .unwrap();
+    (status, response[head + 4..].to_vec())
+}
+
+#[cfg(enarx_with_shim)]
+#[test]
+fn test_steward_serve() {
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use std::sync::Arc;
+
+    use der::{Decode, Encode};
+    use x509_cert::request::CertReq;
+    use x509_cert::PkiPath;
+
+    let data_dir = format!("{}/tests/data", env!("CARGO_MANIFEST_DIR"));
+    let mut steward = Command::new(env!("CARGO_BIN_EXE_enarx"))
+        .args(["steward", "serve", "--addr", "127.0.0.1:0"])
+        .arg(format!("--ca-cert={data_dir}/tls/ca.crt"))
+        .arg(format!("--ca-key={data_dir}/tls/ca.key"))
+        .arg(format!("--tls-cert={data_dir}/tls/server.crt"))
+        .arg(format!("--tls-key={data_dir}/tls/server.key"))
+        .arg(format!("--sgx-root={data_dir}/attestation/sgx-root.pem"))
+        .arg(format!("--snp-chain={data_dir}/attestation/snp-chain.pem"))
+        .arg(format!("--policy={data_dir}/attestation/reference.json"))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // Keep stdout open, so that the Steward can write the rest of its output.
+    let mut stdout = BufReader::new(steward.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    let addr = line
+        .trim()
+        .strip_prefix("Listening on https://")
+        .unwrap_or_else(|| panic!("unexpected output: {line}"))
+        .to_string();
+
+    let ca = rustls_pemfile::certs(
+        &mut fs::read(format!("{data_dir}/tls/ca.crt"))
+            .unwrap()
+            .as_slice(),
+    )
+    .unwrap()
+    .remove(0);
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(&rustls::Certificate(ca.clone())).unwrap();
+    let tls = Arc::new(
+        rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth(),
+    );
+
+    for technology in ["sgx", "snp", "kvm"] {
+        let csr = fs::read(format!("{data_dir}/attestation/{technology}.csr")).unwrap();
+        let (status, body) = steward_request(&addr, tls.clone(), &csr);
+        assert_eq!(
+            status,
+            200,
+            "{technology}: {}",
+            String::from_utf8_lossy(&body)
+        );
+
+        // Like the Keep, expect the root CA first.
+        let path = PkiPath::from_der(&body).unwrap();
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[0].to_der().unwrap(), ca);
+        let csr = CertReq::from_der(&csr).unwrap();
+        assert_eq!(
+            path[1].tbs_certificate.subject_public_key_info,
+            csr.info.public_key
+        );
+    }
+
+    let (status, _) = steward_request(&addr, tls, b"not a CSR");
+    assert_eq!(status, 403);
+
+    steward.kill().unwrap();
+    steward.wait().unwrap();
 }
@@ -46,6 +46,14 @@ macro_rules! cmd {
     (fail: $args:expr, text: $output:expr) => {
         util::enarx(format!($args), false, util::Output::Text(format!($output)))
     };
+    // A command that fails with JSON output
+    (fail: $args:expr, json: $output:tt) => {
+        util::enarx(
+            format!($args),
+            false,
+            util::Output::Json(serde_json::json!($output)),
+        )
+    };
 }
 
 #[track_caller]
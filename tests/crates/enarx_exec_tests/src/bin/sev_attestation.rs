@@ -129,6 +129,10 @@ impl TryFrom<u64> for TeeTech {
 struct SnpEvidence {
     vcek: Document,
     crl: CrlList,
+    #[asn1(context_specific = "0", optional = "true")]
+    chain: Option<Vec<Document>>,
+    #[asn1(context_specific = "1", optional = "true")]
+    tcb: Option<u64>,
 }
 
 #[cfg(target_os = "linux")]
@@ -317,6 +321,12 @@ fn get_att(mut nonce: [u8; 64]) -> io::Result<()> {
     );
 
     eprintln!("CRLs: {:?}", evidence.crl.crls);
+    if let Some(chain) = evidence.chain {
+        eprintln!("Certificate chain: {} certificates", chain.len());
+    }
+    if let Some(tcb) = evidence.tcb {
+        eprintln!("Reported TCB: {tcb:#018x}");
+    }
 
     Ok(())
 }
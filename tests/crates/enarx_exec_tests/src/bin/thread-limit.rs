@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use enarx_exec_tests::musl_fsbase_fix;
+
+musl_fsbase_fix!();
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+/// The number of threads of the Keep, as set with `ENARX_THREADS` by the test
+const THREADS: usize = 4;
+
+fn main() {
+    // Every thread waits for all others, so each one needs its own vCPU.
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let handles: Vec<_> = (1..THREADS)
+        .map(|i| {
+            let barrier = barrier.clone();
+            thread::Builder::new()
+                .spawn(move || {
+                    barrier.wait();
+                    i
+                })
+                .expect("failed to spawn thread")
+        })
+        .collect();
+
+    // All vCPUs are busy until the barrier is passed.
+    if thread::Builder::new().spawn(|| ()).is_ok() {
+        eprintln!("spawned more than {THREADS} threads");
+        std::process::exit(1);
+    }
+
+    barrier.wait();
+
+    let sum: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    println!("{sum}");
+}
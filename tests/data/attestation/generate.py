@@ -0,0 +1,381 @@
+#!/usr/bin/env python3
+# SPDX-License-Identifier: Apache-2.0
+"""Generates synthetic SGX and SEV-SNP attestation evidence for tests.
+
+The certificate chains mimic the structure of the Intel and AMD chains,
+but are rooted in freshly generated test CAs. Requires `cryptography`.
+"""
+
+import datetime
+import hashlib
+import json
+import struct
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec, padding, rsa
+from cryptography.hazmat.primitives.asymmetric.utils import decode_dss_signature
+from cryptography.x509.oid import NameOID, ObjectIdentifier
+
+NOT_BEFORE = datetime.datetime(2020, 1, 1, tzinfo=datetime.timezone.utc)
+NOT_AFTER = datetime.datetime(2120, 1, 1, tzinfo=datetime.timezone.utc)
+
+KVM = ObjectIdentifier("1.3.6.1.4.1.58270.1.1")
+SGX = ObjectIdentifier("1.3.6.1.4.1.58270.1.2")
+SNP = ObjectIdentifier("1.3.6.1.4.1.58270.1.3")
+SGX_EXTENSION = "1.2.840.113741.1.13.1"
+
+PSS = padding.PSS(mgf=padding.MGF1(hashes.SHA384()), salt_length=48)
+
+MRENCLAVE = bytes(range(32))
+MRSIGNER = bytes(range(32, 64))
+MEASUREMENT = bytes(range(48))
+FAMILY_ID = bytes(range(16))
+IMAGE_ID = bytes(range(16, 32))
+ID_KEY_DIGEST = hashlib.sha384(b"id key").digest()
+AUTHOR_KEY_DIGEST = hashlib.sha384(b"author key").digest()
+FMSPC = bytes.fromhex("00906ED50000")
+CPUSVN = [2, 2, 2, 2, 3, 1, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0]
+PCESVN = 13
+# Bootloader 3, TEE 0, SNP 8 and microcode 115
+REPORTED_TCB = 0x7308000000000003
+CHIP_ID = bytes(range(64))
+VCEK_EXTENSION = "1.3.6.1.4.1.3704.1"
+
+
+def der(tag, content):
+    n = len(content)
+    if n < 0x80:
+        length = bytes([n])
+    else:
+        length = n.to_bytes((n.bit_length() + 7) // 8, "big")
+        length = bytes([0x80 | len(length)]) + length
+    return bytes([tag]) + length + content
+
+
+def seq(*items):
+    return der(0x30, b"".join(items))
+
+
+def octets(data):
+    return der(0x04, data)
+
+
+def integer(value):
+    return der(0x02, value.to_bytes(value.bit_length() // 8 + 1, "big"))
+
+
+def utf8(text):
+    return der(0x0C, text.encode())
+
+
+def oid(dotted):
+    arcs = [int(arc) for arc in dotted.split(".")]
+    body = bytes([arcs[0] * 40 + arcs[1]])
+    for arc in arcs[2:]:
+        chunk = [arc & 0x7F]
+        arc >>= 7
+        while arc:
+            chunk.insert(0, 0x80 | (arc & 0x7F))
+            arc >>= 7
+        body += bytes(chunk)
+    return der(0x06, body)
+
+
+def name(cn):
+    return x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, cn)])
+
+
+def certificate(subject, key, issuer=None, issuer_key=None, ca=True, extensions=()):
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(name(subject))
+        .issuer_name(name(issuer or subject))
+        .public_key(key.public_key())
+        .serial_number(x509.random_serial_number())
+        .not_valid_before(NOT_BEFORE)
+        .not_valid_after(NOT_AFTER)
+        .add_extension(x509.BasicConstraints(ca=ca, path_length=None), critical=True)
+    )
+    for ext in extensions:
+        builder = builder.add_extension(ext, critical=False)
+    signer = issuer_key or key
+    if isinstance(signer, rsa.RSAPrivateKey):
+        return builder.sign(signer, hashes.SHA384(), rsa_padding=PSS)
+    return builder.sign(signer, hashes.SHA256())
+
+
+def crl(issuer, key, revoked=()):
+    builder = (
+        x509.CertificateRevocationListBuilder()
+        .issuer_name(issuer.subject)
+        .last_update(NOT_BEFORE)
+        .next_update(NOT_AFTER)
+    )
+    for cert in revoked:
+        builder = builder.add_revoked_certificate(
+            x509.RevokedCertificateBuilder()
+            .serial_number(cert.serial_number)
+            .revocation_date(NOT_BEFORE)
+            .build()
+        )
+    if isinstance(key, rsa.RSAPrivateKey):
+        return builder.sign(key, hashes.SHA384(), rsa_padding=PSS)
+    return builder.sign(key, hashes.SHA256())
+
+
+def crl_list(*crls):
+    """Encodes a `CrlList` as cached by `enarx platform {sgx,snp} cache-crl`."""
+    return seq(
+        seq(
+            *(
+                seq(utf8(f"https://crl.example.com/{i}"), c.public_bytes(serialization.Encoding.DER))
+                for i, c in enumerate(crls)
+            )
+        )
+    )
+
+
+def pem(*certs):
+    return b"".join(c.public_bytes(serialization.Encoding.PEM) for c in certs)
+
+
+def der_cert(cert):
+    return cert.public_bytes(serialization.Encoding.DER)
+
+
+def raw_signature(key, data, hash, size):
+    r, s = decode_dss_signature(key.sign(data, ec.ECDSA(hash)))
+    return r.to_bytes(size, "big") + s.to_bytes(size, "big")
+
+
+def csr(key, technology, evidence, hash):
+    return (
+        x509.CertificateSigningRequestBuilder()
+        .subject_name(x509.Name([]))
+        .add_extension(x509.UnrecognizedExtension(technology, evidence), critical=False)
+        .sign(key, hash)
+        .public_bytes(serialization.Encoding.DER)
+    )
+
+
+def spki(key):
+    return key.public_key().public_bytes(
+        serialization.Encoding.DER, serialization.PublicFormat.SubjectPublicKeyInfo
+    )
+
+
+def vcek_extensions():
+    tcb = REPORTED_TCB.to_bytes(8, "little")
+    spls = {1: tcb[0], 2: tcb[1], 3: tcb[6], 8: tcb[7]}
+    extensions = [
+        x509.UnrecognizedExtension(ObjectIdentifier(f"{VCEK_EXTENSION}.3.{i}"), integer(spl))
+        for i, spl in spls.items()
+    ]
+    extensions.append(
+        x509.UnrecognizedExtension(ObjectIdentifier(f"{VCEK_EXTENSION}.4"), CHIP_ID)
+    )
+    return extensions
+
+
+def snp():
+    ark_key = rsa.generate_private_key(65537, 2048)
+    ask_key = rsa.generate_private_key(65537, 2048)
+    vcek_key = ec.generate_private_key(ec.SECP384R1())
+    ark = certificate("ARK-Test", ark_key)
+    ask = certificate("SEV-Test", ask_key, "ARK-Test", ark_key)
+    vcek = certificate(
+        "SEV-VCEK", vcek_key, "SEV-Test", ask_key, ca=False, extensions=vcek_extensions()
+    )
+
+    keep_key = ec.generate_private_key(ec.SECP384R1())
+    report = bytearray(0x4A0)
+    struct.pack_into("<IIQ", report, 0, 2, 1, 0x30000)
+    report[0x10:0x20] = FAMILY_ID
+    report[0x20:0x30] = IMAGE_ID
+    struct.pack_into("<I", report, 0x34, 1)
+    report[0x50:0x80] = hashlib.sha384(spki(keep_key)).digest()
+    report[0x90:0xC0] = MEASUREMENT
+    report[0xE0:0x110] = ID_KEY_DIGEST
+    report[0x110:0x140] = AUTHOR_KEY_DIGEST
+    report[0x180:0x188] = REPORTED_TCB.to_bytes(8, "little")
+    report[0x1A0:0x1E0] = CHIP_ID
+    signature = raw_signature(vcek_key, bytes(report[:0x2A0]), hashes.SHA384(), 48)
+    report[0x2A0:0x2D0] = signature[:48][::-1]
+    report[0x2E8:0x318] = signature[48:][::-1]
+
+    evidence = seq(
+        seq(der_cert(vcek), crl_list(crl(ark, ark_key))),
+        octets(bytes(report)),
+    )
+    write("snp.csr", csr(keep_key, SNP, evidence, hashes.SHA384()))
+
+    # The evidence with the certificate chain of the VCEK and the reported TCB
+    evidence = seq(
+        seq(
+            der_cert(vcek),
+            crl_list(crl(ark, ark_key)),
+            der(0xA0, seq(der_cert(ask), der_cert(ark))),
+            der(0xA1, integer(REPORTED_TCB)),
+        ),
+        octets(bytes(report)),
+    )
+    write("snp-chain.csr", csr(keep_key, SNP, evidence, hashes.SHA384()))
+    write("snp-chain.pem", pem(ask, ark))
+    write("snp.crls", crl_list(crl(ark, ark_key)))
+    write("snp-revoked.crls", crl_list(crl(ark, ark_key, [ask])))
+
+
+def sgx_extension():
+    tcb = [seq(oid(f"{SGX_EXTENSION}.2.{i + 1}"), integer(svn)) for i, svn in enumerate(CPUSVN)]
+    tcb.append(seq(oid(f"{SGX_EXTENSION}.2.17"), integer(PCESVN)))
+    tcb.append(seq(oid(f"{SGX_EXTENSION}.2.18"), octets(bytes(CPUSVN))))
+    return seq(
+        seq(oid(f"{SGX_EXTENSION}.1"), octets(bytes(16))),
+        seq(oid(f"{SGX_EXTENSION}.2"), seq(*tcb)),
+        seq(oid(f"{SGX_EXTENSION}.3"), octets(bytes(2))),
+        seq(oid(f"{SGX_EXTENSION}.4"), octets(FMSPC)),
+        seq(oid(f"{SGX_EXTENSION}.5"), der(0x0A, b"\x00")),
+    )
+
+
+def tcb_info(signer_key):
+    info = {
+        "id": "SGX",
+        "version": 3,
+        "issueDate": "2026-10-18T00:00:00Z",
+        "nextUpdate": "2119-12-31T00:00:00Z",
+        "fmspc": FMSPC.hex(),
+        "pceId": "0000",
+        "tcbType": 0,
+        "tcbEvaluationDataNumber": 16,
+        "tcbLevels": [
+            {
+                "tcb": {
+                    "sgxtcbcomponents": [{"svn": svn + 1} for svn in CPUSVN],
+                    "pcesvn": PCESVN,
+                },
+                "tcbDate": "2026-10-18T00:00:00Z",
+                "tcbStatus": "UpToDate",
+            },
+            {
+                "tcb": {
+                    "sgxtcbcomponents": [{"svn": svn} for svn in CPUSVN],
+                    "pcesvn": PCESVN,
+                },
+                "tcbDate": "2026-01-01T00:00:00Z",
+                "tcbStatus": "UpToDate",
+            },
+            {
+                "tcb": {
+                    "sgxtcbcomponents": [{"svn": 0} for _ in CPUSVN],
+                    "pcesvn": 0,
+                },
+                "tcbDate": "2020-01-01T00:00:00Z",
+                "tcbStatus": "OutOfDate",
+            },
+        ],
+    }
+    raw = json.dumps(info, separators=(",", ":"))
+    signature = raw_signature(signer_key, raw.encode(), hashes.SHA256(), 32)
+    return f'{{"tcbInfo":{raw},"signature":"{signature.hex()}"}}'.encode()
+
+
+def sgx():
+    root_key = ec.generate_private_key(ec.SECP256R1())
+    platform_key = ec.generate_private_key(ec.SECP256R1())
+    pck_key = ec.generate_private_key(ec.SECP256R1())
+    tcb_key = ec.generate_private_key(ec.SECP256R1())
+    root = certificate("SGX Test Root CA", root_key)
+    platform = certificate("SGX Test Platform CA", platform_key, "SGX Test Root CA", root_key)
+    pck = certificate(
+        "SGX PCK Certificate",
+        pck_key,
+        "SGX Test Platform CA",
+        platform_key,
+        ca=False,
+        extensions=[x509.UnrecognizedExtension(ObjectIdentifier(SGX_EXTENSION), sgx_extension())],
+    )
+    tcb_signer = certificate("SGX TCB Signing", tcb_key, "SGX Test Root CA", root_key, ca=False)
+
+    keep_key = ec.generate_private_key(ec.SECP256R1())
+    attestation_key = ec.generate_private_key(ec.SECP256R1())
+    raw_attestation_key = attestation_key.public_key().public_bytes(
+        serialization.Encoding.X962, serialization.PublicFormat.UncompressedPoint
+    )[1:]
+
+    header = struct.pack("<HHI", 3, 2, 0).ljust(48, b"\0")
+    body = bytearray(384)
+    body[48] = 0x05
+    body[64:96] = MRENCLAVE
+    body[128:160] = MRSIGNER
+    struct.pack_into("<HH", body, 256, 1, 2)
+    body[320:352] = hashlib.sha256(spki(keep_key)).digest()
+    signed = header + bytes(body)
+
+    qe_auth = bytes(range(32))
+    qe_report = bytearray(384)
+    qe_report[320:352] = hashlib.sha256(raw_attestation_key + qe_auth).digest()
+    certification = pem(pck, platform, root) + b"\0"
+    signature_data = (
+        raw_signature(attestation_key, signed, hashes.SHA256(), 32)
+        + raw_attestation_key
+        + bytes(qe_report)
+        + raw_signature(pck_key, bytes(qe_report), hashes.SHA256(), 32)
+        + struct.pack("<H", len(qe_auth))
+        + qe_auth
+        + struct.pack("<HI", 5, len(certification))
+        + certification
+    )
+    quote = signed + struct.pack("<I", len(signature_data)) + signature_data
+
+    tcb = seq(seq(der_cert(tcb_signer), der_cert(root)), octets(tcb_info(tcb_key)))
+    crls = crl_list(crl(platform, platform_key), crl(root, root_key))
+    evidence = seq(octets(quote), crls, tcb)
+    write("sgx.csr", csr(keep_key, SGX, evidence, hashes.SHA256()))
+    write("sgx-root.pem", pem(root))
+    write("sgx-crl-chain.pem", pem(platform, root))
+    write("sgx.crls", crls)
+    write("sgx-tcb.der", tcb)
+    write(
+        "sgx-revoked.crls",
+        crl_list(crl(platform, platform_key, [pck]), crl(root, root_key)),
+    )
+
+
+def kvm():
+    key = ec.generate_private_key(ec.SECP256R1())
+    write("kvm.csr", csr(key, KVM, b"", hashes.SHA256()))
+
+
+def reference():
+    ref = {
+        "sgx": {
+            "mrenclave": MRENCLAVE.hex(),
+            "mrsigner": MRSIGNER.hex(),
+            "isv_prod_id": 1,
+            "isv_svn": 2,
+        },
+        "sev": {
+            "measurement": MEASUREMENT.hex(),
+            "family_id": FAMILY_ID.hex(),
+            "image_id": IMAGE_ID.hex(),
+            "guest_svn": 1,
+            "policy": 0x30000,
+            "id_key_digest": ID_KEY_DIGEST.hex(),
+            "author_key_digest": AUTHOR_KEY_DIGEST.hex(),
+        },
+    }
+    write("reference.json", (json.dumps(ref, indent=2) + "\n").encode())
+
+
+def write(path, data):
+    with open(path, "wb") as f:
+        f.write(data)
+
+
+if __name__ == "__main__":
+    snp()
+    sgx()
+    kvm()
+    reference()
@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{assert_eq_slices, enarx, is_nil, is_sev, is_sgx, run_test, run_test_signed};
+use super::{
+    assert_eq_slices, check_output, enarx, is_nil, is_sev, is_sgx, run_test, run_test_signed,
+};
 
 use std::ffi::OsStr;
 use std::fs;
@@ -96,6 +98,27 @@ fn thread_channel() {
     run_test(bin, 0, None, output.as_bytes(), None);
 }
 
+#[test]
+fn thread_limit() {
+    if is_nil() || is_sgx() {
+        eprintln!("Not supported on nil and SGX backends, ignoring");
+        return;
+    }
+    let bin = env!("CARGO_BIN_FILE_ENARX_EXEC_TESTS_thread-limit");
+    let output = enarx(
+        |cmd| {
+            cmd.env("ENARX_THREADS", "4").args(vec![
+                OsStr::new("unstable"),
+                OsStr::new("exec"),
+                OsStr::new("--unsigned"),
+                OsStr::new(bin),
+            ])
+        },
+        None,
+    );
+    check_output(&output, 0, b"6\n".as_slice(), None);
+}
+
 #[test]
 fn echo() {
     if is_nil() {